@@ -3,6 +3,29 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::{
+    ctxs::{
+        aminoacid::domain::genetic_code::GeneticCode,
+        codon::domain::{
+            optimization::CodonOptimizer,
+            usage::{CodonUsage, TableFormat},
+        },
+        fasta::domain::{
+            alphabet::{Alphabet, ValidationPolicy},
+            fasta::{Case, MaskMode, Strand},
+            trim::Trimmer,
+        },
+        fasta::infrastructure::SequenceFormat,
+        protein::domain::{
+            backtranslation::BackTranslationMode,
+            frame::Frame,
+            orf::{CoordinateFormat, OrfFinder, StartCodons},
+            protein::{PartialCodon, TranslationOptions},
+        },
+    },
+    shared::infrastructure::{compression::Compression, io::IoOptions, ProteinLayout},
+};
+
 /// Base `command` for arguments.
 #[derive(Parser)]
 #[command(
@@ -13,6 +36,51 @@ use std::path::PathBuf;
 pub struct Arguments {
     #[command(subcommand)]
     pub cmdline: Command,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        help = "Compression of written files. Guessed from the output extension if missing"
+    )]
+    pub compress: Option<Compression>,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "lenient",
+        help = "Fail on the first illegal character of a sequence"
+    )]
+    pub strict: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Keep illegal characters of sequences as they are (default)"
+    )]
+    pub lenient: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        help = "Alphabet of every sequence read. Detected for each one from its letters if missing"
+    )]
+    pub alphabet: Option<Alphabet>,
+}
+
+impl Arguments {
+    /// Options on reading and writing files shared by all commands.
+    pub fn io_options(&self) -> IoOptions {
+        IoOptions {
+            compression: self.compress,
+            policy: match self.strict {
+                true => ValidationPolicy::Strict,
+                false => ValidationPolicy::Lenient,
+            },
+            alphabet: self.alphabet,
+        }
+    }
 }
 
 // ----------------
@@ -24,7 +92,18 @@ pub enum Command {
     Cut(CutOptions),
     Generate(GenerateOptions),
     Format(FormatOptions),
+    Mask(MaskOptions),
     Analyze(AnalysisOptions),
+    Trim(TrimOptions),
+    Faidx(FaidxOptions),
+    Region(RegionOptions),
+    Validate(ValidateOptions),
+    Orfs(OrfsOptions),
+    Backtranslate(BacktranslateOptions),
+    CodonUsage(CodonUsageOptions),
+    OptimizeCodons(OptimizeCodonsOptions),
+    Convert(ConvertOptions),
+    Cds(CdsOptions),
     #[command(subcommand)]
     Get(FastaOperation),
 }
@@ -39,8 +118,8 @@ pub enum Command {
     rename_all = "kebab-case"
 )]
 pub struct CatOptions {
-    #[arg(help = "The file to read")]
-    pub file: PathBuf,
+    #[arg(help = "The file to read. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
 }
 
 // ----------------
@@ -59,11 +138,11 @@ pub struct CutOptions {
     #[arg(help = "Position to start cutting")]
     pub to: usize,
 
-    #[arg(help = "File to read")]
-    pub input_file_name: PathBuf,
+    #[arg(help = "File to read. Standard input if `-` or missing")]
+    pub input_file_name: Option<PathBuf>,
 
-    #[arg(help = "File to write")]
-    pub output_file_name: PathBuf,
+    #[arg(help = "File to write. Standard output if `-` or missing")]
+    pub output_file_name: Option<PathBuf>,
 }
 
 // ----------------
@@ -79,8 +158,8 @@ pub struct GenerateOptions {
     #[arg(help = "Number of bases to generate. Each line has 60 bases")]
     pub length: usize,
 
-    #[arg(help = "File to write to")]
-    pub output_file: PathBuf,
+    #[arg(help = "File to write to. Standard output if `-` or missing")]
+    pub output_file: Option<PathBuf>,
 
     #[arg(short = 'r', long = "rna", help = "Generate RNA instead of DNA")]
     pub is_rna: bool,
@@ -96,14 +175,67 @@ pub struct GenerateOptions {
     rename_all = "kebab-case"
 )]
 pub struct FormatOptions {
-    #[arg(help = "File to format")]
-    pub file: PathBuf,
+    #[arg(help = "File to format. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
 
-    #[arg(help = "File to write formatted fasta. Optional")]
-    pub output_file: PathBuf,
+    #[arg(help = "File to write formatted fasta. Standard output if `-` or missing")]
+    pub output_file: Option<PathBuf>,
 
-    #[arg(short, long = "upper", help = "Format to uppercase")]
+    #[arg(
+        short,
+        long = "upper",
+        conflicts_with_all = ["lowercase", "keep_case"],
+        help = "Format to uppercase"
+    )]
     pub uppercase: bool,
+
+    #[arg(
+        short,
+        long = "lower",
+        conflicts_with = "keep_case",
+        help = "Format to lowercase"
+    )]
+    pub lowercase: bool,
+
+    #[arg(long, help = "Keep the case as read, soft-masking included (default)")]
+    pub keep_case: bool,
+}
+
+impl FormatOptions {
+    /// Case to write sequences in.
+    pub fn case(&self) -> Case {
+        match (self.uppercase, self.lowercase) {
+            (true, _) => Case::Upper,
+            (_, true) => Case::Lower,
+            _ => Case::Keep,
+        }
+    }
+}
+
+// ----------------
+
+/// Available options for `MaskOptions`.
+#[derive(Parser)]
+#[command(
+    name = "masking options",
+    about = "Converts soft-masking (lowercase bases) to hard-masking, or strips it",
+    rename_all = "kebab-case"
+)]
+pub struct MaskOptions {
+    #[arg(help = "File to mask. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write masked records. Standard output if `-` or missing")]
+    pub output_file: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value_t = MaskMode::Hard,
+        help = "Replace soft-masked bases with `N` (hard), or uppercase them (strip)"
+    )]
+    pub mode: MaskMode,
 }
 
 // ----------------
@@ -116,8 +248,454 @@ pub struct FormatOptions {
     rename_all = "kebab-case"
 )]
 pub struct AnalysisOptions {
-    #[arg(help = "File to analize")]
+    #[arg(help = "File to analize. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+}
+
+// ----------------
+
+/// Available options for `TrimOptions`.
+#[derive(Parser)]
+#[command(
+    name = "trimming options",
+    about = "Trims adapters and low quality bases from reads, and drops short ones",
+    rename_all = "kebab-case"
+)]
+pub struct TrimOptions {
+    #[arg(help = "File to trim. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write trimmed reads. Standard output if `-` or missing")]
+    pub output_file: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long = "adapter",
+        value_parser = parse_nucleotides,
+        help = "Adapter to remove, with anything after it. Can be given several times"
+    )]
+    pub adapters: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "Minimum adapter bases at the end of a read to remove them"
+    )]
+    pub min_overlap: usize,
+
+    #[arg(long, help = "Remove leading bases under this quality")]
+    pub leading: Option<u8>,
+
+    #[arg(long, help = "Remove trailing bases under this quality")]
+    pub trailing: Option<u8>,
+
+    #[arg(
+        long,
+        requires = "window_quality",
+        help = "Size of the sliding window. Requires --window-quality"
+    )]
+    pub window_size: Option<usize>,
+
+    #[arg(
+        long,
+        requires = "window_size",
+        help = "Cut where the mean quality of the sliding window drops under this"
+    )]
+    pub window_quality: Option<f64>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 1,
+        help = "Drop reads shorter than this"
+    )]
+    pub min_length: usize,
+
+    #[arg(long, help = "Write fasta, dropping qualities")]
+    pub fasta: bool,
+}
+
+impl TrimOptions {
+    /// How to trim the reads.
+    pub fn trimmer(&self) -> Trimmer {
+        Trimmer {
+            adapters: self.adapters.clone(),
+            min_overlap: self.min_overlap,
+            leading: self.leading,
+            trailing: self.trailing,
+            window: self.window_size.zip(self.window_quality),
+            min_length: self.min_length,
+        }
+    }
+}
+
+/// Takes a sequence of DNA or RNA bases, IUPAC codes included.
+fn parse_nucleotides(value: &str) -> Result<String, String> {
+    match value
+        .chars()
+        .find(|&base| !Alphabet::Dna.is_valid(base) && !Alphabet::Rna.is_valid(base))
+    {
+        Some(base) => Err(format!("'{}' is not a nucleotide", base)),
+        None => Ok(value.to_string()),
+    }
+}
+
+// ----------------
+
+/// Available options for `ConvertOptions`.
+#[derive(Parser)]
+#[command(
+    name = "conversion options",
+    about = "Converts sequences between fasta, fastq, .2bit and GenBank, or GenBank features to GFF3",
+    rename_all = "kebab-case"
+)]
+pub struct ConvertOptions {
+    #[arg(help = "File to convert. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write. Standard output if `-` or missing")]
+    pub ofile: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        help = "Format to write. Guessed from the output extension if missing"
+    )]
+    pub to: Option<SequenceFormat>,
+}
+
+// ----------------
+
+/// Available options for `CdsOptions`.
+#[derive(Parser)]
+#[command(
+    name = "coding sequence options",
+    about = "Extracts the CDS features of GenBank records as nucleotides or proteins",
+    rename_all = "kebab-case"
+)]
+pub struct CdsOptions {
+    #[arg(help = "GenBank file to read. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write the coding sequences to. Standard output if `-` or missing")]
+    pub ofile: Option<PathBuf>,
+
+    #[arg(short, long, help = "Write proteins instead of nucleotides")]
+    pub protein: bool,
+
+    #[arg(
+        short,
+        long,
+        default_value = "1",
+        help = "NCBI genetic code to translate features without a /transl_table with"
+    )]
+    pub table: GeneticCode,
+}
+
+// ----------------
+
+/// Available options for `FaidxOptions`.
+#[derive(Parser)]
+#[command(
+    name = "indexing options",
+    about = "Writes a samtools-compatible .fai index of a fasta file",
+    rename_all = "kebab-case"
+)]
+pub struct FaidxOptions {
+    #[arg(help = "Uncompressed or BGZF fasta file to index")]
+    pub file: PathBuf,
+}
+
+// ----------------
+
+/// Available options for `RegionOptions`.
+#[derive(Parser)]
+#[command(
+    name = "region options",
+    about = "Extracts regions from an indexed fasta file, indexing it first if needed",
+    rename_all = "kebab-case"
+)]
+pub struct RegionOptions {
+    #[arg(help = "Uncompressed or BGZF fasta file, or .2bit file, to read")]
     pub file: PathBuf,
+
+    #[arg(
+        required = true,
+        help = "Regions to extract, as name, name:start or name:start-end. 1-based, inclusive. Write names with ':' that also read as a range between braces, as {name} or {name}:start-end"
+    )]
+    pub regions: Vec<String>,
+
+    #[arg(
+        short,
+        long = "output",
+        help = "File to write to. Standard output if `-` or missing"
+    )]
+    pub output_file: Option<PathBuf>,
+}
+
+// ----------------
+
+/// Available options for `ValidateOptions`.
+#[derive(Parser)]
+#[command(
+    name = "validation options",
+    about = "Reports every character of the sequences that does not belong to their alphabet",
+    rename_all = "kebab-case"
+)]
+pub struct ValidateOptions {
+    #[arg(help = "File to validate. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+}
+
+// ----------------
+
+/// Available options for `OrfsOptions`.
+#[derive(Parser)]
+#[command(
+    name = "open reading frame options",
+    about = "Finds open reading frames in all six frames of every sequence",
+    rename_all = "kebab-case"
+)]
+pub struct OrfsOptions {
+    #[arg(help = "File to search. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write the frames to. Standard output if `-` or missing")]
+    pub ofile: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        default_value = "1",
+        help = "NCBI genetic code to find start and stop codons with"
+    )]
+    pub table: GeneticCode,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value_t = StartCodons::Atg,
+        help = "Codons frames may start with: ATG, any start codon of the table, or any codon"
+    )]
+    pub starts: StartCodons,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 30,
+        help = "Leave out frames of fewer aminoacids than this, stop not included"
+    )]
+    pub min_length: usize,
+
+    #[arg(
+        long,
+        help = "Keep frames starting inside longer ones, not just the longest"
+    )]
+    pub nested: bool,
+
+    #[arg(short, long, help = "Write proteins instead of nucleotides")]
+    pub protein: bool,
+
+    #[arg(short, long, help = "File to write the coordinates of every frame to")]
+    pub coordinates: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CoordinateFormat::Bed,
+        requires = "coordinates",
+        help = "Format of the coordinates"
+    )]
+    pub coordinates_format: CoordinateFormat,
+}
+
+impl OrfsOptions {
+    /// How to find the frames.
+    pub fn finder(&self) -> OrfFinder {
+        OrfFinder {
+            code: self.table.clone(),
+            starts: self.starts,
+            min_length: self.min_length,
+            nested: self.nested,
+        }
+    }
+}
+
+// ----------------
+
+/// Available options for `BacktranslateOptions`.
+#[derive(Parser)]
+#[command(
+    name = "back-translation options",
+    about = "Writes DNA sequences coding for every protein, with the standard genetic code",
+    rename_all = "kebab-case"
+)]
+pub struct BacktranslateOptions {
+    #[arg(help = "Protein file to back-translate. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write the DNA sequences to. Standard output if `-` or missing")]
+    pub ofile: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value_t = BackTranslationMode::MostFrequent,
+        help = "Codon to choose for every aminoacid: the most used one, one at random weighted by usage, or all of them with IUPAC codes"
+    )]
+    pub mode: BackTranslationMode,
+
+    #[arg(
+        short,
+        long,
+        help = "Codon usage table of the target organism, as tab-separated codons and usages, in the Kazusa format or as JSON written by codon-usage"
+    )]
+    pub usage: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Seed for choosing codons at random, to get the same sequences every run"
+    )]
+    pub seed: Option<u64>,
+}
+
+// ----------------
+
+/// Available options for `CodonUsageOptions`.
+#[derive(Parser)]
+#[command(
+    name = "codon usage options",
+    about = "Counts the codons of coding sequences, and computes the CAI, ENC and GC3 of every one",
+    rename_all = "kebab-case"
+)]
+pub struct CodonUsageOptions {
+    #[arg(
+        help = "File of coding sequences, read from their first base. Standard input if `-` or missing"
+    )]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write the codon usage table to. Standard output if `-` or missing")]
+    pub ofile: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        default_value = "1",
+        help = "NCBI genetic code telling synonymous codons apart"
+    )]
+    pub table: GeneticCode,
+
+    #[arg(
+        short,
+        long,
+        help = "File to write the CAI, ENC and GC3 of every sequence to"
+    )]
+    pub genes: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        requires = "genes",
+        help = "Codon usage table of highly expressed genes to compute the CAI against. The usage of all the sequences if missing"
+    )]
+    pub reference: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value_t = TableFormat::Tsv,
+        help = "Format of the tables"
+    )]
+    pub format: TableFormat,
+}
+
+// ----------------
+
+/// Available options for `OptimizeCodonsOptions`.
+#[derive(Parser)]
+#[command(
+    name = "codon optimization options",
+    about = "Rewrites coding sequences with the synonymous codons a host uses the most",
+    rename_all = "kebab-case"
+)]
+pub struct OptimizeCodonsOptions {
+    #[arg(
+        help = "File of coding sequences, read from their first base. Standard input if `-` or missing"
+    )]
+    pub file: Option<PathBuf>,
+
+    #[arg(help = "File to write the optimized sequences to. Standard output if `-` or missing")]
+    pub ofile: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        help = "Codon usage table of the host, as tab-separated codons and usages, in the Kazusa format or as JSON written by codon-usage"
+    )]
+    pub usage: PathBuf,
+
+    #[arg(
+        short,
+        long,
+        default_value = "1",
+        help = "NCBI genetic code telling synonymous codons apart"
+    )]
+    pub table: GeneticCode,
+
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated sites not to create on either strand, like restriction sites. IUPAC codes may be used"
+    )]
+    pub avoid: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Longest run of a single base allowed"
+    )]
+    pub max_homopolymer: usize,
+
+    #[arg(
+        long,
+        default_value_t = 50,
+        help = "Bases of the windows whose GC content is checked. 0 to not check it"
+    )]
+    pub gc_window: usize,
+
+    #[arg(
+        long,
+        default_value_t = 0.3,
+        help = "Lowest GC content allowed in any window"
+    )]
+    pub min_gc: f64,
+
+    #[arg(
+        long,
+        default_value_t = 0.7,
+        help = "Highest GC content allowed in any window"
+    )]
+    pub max_gc: f64,
+}
+
+impl OptimizeCodonsOptions {
+    /// How to optimize the sequences, with the usage table of the host.
+    pub fn optimizer(&self, usage: CodonUsage) -> CodonOptimizer {
+        CodonOptimizer {
+            code: self.table.clone(),
+            usage,
+            avoid: self.avoid.clone(),
+            max_homopolymer: self.max_homopolymer,
+            gc_window: self.gc_window,
+            min_gc: self.min_gc,
+            max_gc: self.max_gc,
+        }
+    }
 }
 
 // ----------------
@@ -125,13 +703,15 @@ pub struct AnalysisOptions {
 #[derive(Subcommand)]
 #[command(
     name = "sequence related operations",
-    about = "Get reverse, completary, reverse-complementary or transform to amioacids",
+    about = "Get reverse, completary, reverse-complementary, transcribe, reverse-transcribe or transform to amioacids",
     rename_all = "kebab-case"
 )]
 pub enum FastaOperation {
     Reverse(StrandOptions),
     Complementary(StrandOptions),
     Revcomp(StrandOptions),
+    Transcribe(TranscriptionOptions),
+    ReverseTranscribe(TranscriptionOptions),
     Amioacids(AAOptions),
 }
 
@@ -145,24 +725,140 @@ pub enum FastaOperation {
     rename_all = "kebab-case"
 )]
 pub struct StrandOptions {
-    #[arg(help = "File to read from")]
-    pub file: PathBuf,
-    #[arg(help = "File to write to")]
+    #[arg(help = "File to read from. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+    #[arg(help = "File to write to. Standard output if `-` or missing")]
     pub ofile: Option<PathBuf>,
 }
 
+// ----------------
+
+/// Available options for `TranscriptionOptions`.
+#[derive(Parser)]
+#[command(
+    name = "transcription options",
+    about = "Transcribes DNA to RNA, or reverse-transcribes RNA to DNA, turning T into U or back",
+    rename_all = "kebab-case"
+)]
+pub struct TranscriptionOptions {
+    #[arg(help = "File to read from. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+    #[arg(help = "File to write to. Standard output if `-` or missing")]
+    pub ofile: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value_t = Strand::Coding,
+        help = "DNA strand read, when transcribing, or written, when reverse-transcribing: coding, as the RNA, or template, reverse-complementary to it"
+    )]
+    pub strand: Strand,
+}
+
 /// Available options for `AAOptions`.
 #[derive(Parser)]
 #[command(
-    name = "transform rna to aa",
-    about = "Transform a RNA sequence to an Amioacid one.",
+    name = "transform dna or rna to aa",
+    about = "Transform a DNA or RNA sequence to an Amioacid one.",
     rename_all = "kebab-case"
 )]
 pub struct AAOptions {
-    #[arg(help = "File to read from")]
-    pub file: PathBuf,
-    #[arg(help = "File to write to")]
+    #[arg(help = "File to read from. Standard input if `-` or missing")]
+    pub file: Option<PathBuf>,
+    #[arg(help = "File to write to. Standard output if `-` or missing")]
     pub ofile: Option<PathBuf>,
     #[arg(short, long, help = "Protein in lowercase (default: false)")]
     pub lowercase: bool,
+    #[arg(
+        short,
+        long,
+        default_value_t = 60,
+        help = "Aminoacids per line. A single line if 0"
+    )]
+    pub width: usize,
+    #[arg(
+        short,
+        long,
+        default_value = "1",
+        help = "NCBI genetic code to translate with: 1 to 6, 9 to 16 or 21 to 33"
+    )]
+    pub table: GeneticCode,
+    #[arg(
+        long,
+        help = "Translate the first codon as methionine if it is a start codon of the table"
+    )]
+    pub cds: bool,
+    #[arg(
+        long,
+        conflicts_with = "rna",
+        help = "Sequences are DNA: codons with `U` are not translated"
+    )]
+    pub dna: bool,
+    #[arg(long, help = "Sequences are RNA: codons with `T` are not translated")]
+    pub rna: bool,
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        help = "Reading frames to translate, as in `1,2,3,-1,-2,-3`. One protein per frame"
+    )]
+    pub frames: Vec<Frame>,
+    #[arg(
+        long,
+        conflicts_with = "frames",
+        help = "Translate all six reading frames"
+    )]
+    pub six_frame: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = PartialCodon::Drop,
+        help = "Leave an incomplete last codon untranslated (drop) or translate it as `X` (unknown)"
+    )]
+    pub partial_codon: PartialCodon,
+    #[arg(
+        long,
+        help = "Start translating at the first `ATG`, skipping the bases before it"
+    )]
+    pub from_atg: bool,
+    #[arg(long, help = "Stop translating at the first stop codon")]
+    pub to_stop: bool,
+    #[arg(long, help = "Leave stop codons out, instead of writing them as `*`")]
+    pub omit_stops: bool,
+}
+
+impl AAOptions {
+    /// Reading frames to translate, none for just frame +1 with an untagged header.
+    pub fn frames(&self) -> Vec<Frame> {
+        match self.six_frame {
+            true => Frame::all(),
+            false => self.frames.clone(),
+        }
+    }
+
+    /// How to lay the proteins out.
+    pub fn layout(&self) -> ProteinLayout {
+        ProteinLayout {
+            width: self.width,
+            lowercase: self.lowercase,
+        }
+    }
+
+    /// How to translate the sequences.
+    pub fn translation_options(&self) -> TranslationOptions {
+        TranslationOptions {
+            code: self.table.clone(),
+            cds: self.cds,
+            alphabet: match (self.dna, self.rna) {
+                (true, _) => Some(Alphabet::Dna),
+                (_, true) => Some(Alphabet::Rna),
+                _ => None,
+            },
+            partial: self.partial_codon,
+            from_atg: self.from_atg,
+            to_stop: self.to_stop,
+            omit_stops: self.omit_stops,
+        }
+    }
 }
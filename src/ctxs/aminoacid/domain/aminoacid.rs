@@ -1,10 +1,19 @@
 use std::fmt::Display;
 
-use crate::{ctxs::codon::domain::codon::Codon, shared::utils::AMINOACID_TABLE};
+use crate::{
+    ctxs::{codon::domain::codon::Codon, fasta::domain::iupac},
+    shared::utils::AMINOACID_TABLE,
+};
 
 /// Basic aminoacid value representation
 type AminoacidValue = char;
 
+/// Letter for codons that can not be translated
+const UNKNOWN_AMINOACID: AminoacidValue = 'x';
+
+/// Letter for stop codons
+const STOP: AminoacidValue = '*';
+
 /// Representation a protein or aminoacid
 #[derive(Default, Clone)]
 pub struct Aminoacid {
@@ -40,21 +49,110 @@ impl From<(AminoacidValue, Vec<[char; 3]>)> for Aminoacid {
     }
 }
 
+/// Translates a DNA or RNA codon, in any case, with the standard genetic code. Codons with IUPAC ambiguity
+/// codes translate to the aminoacid all their expansions agree on, or to the unknown aminoacid `x`
+/// if they don't.
 impl From<[char; 3]> for Aminoacid {
     fn from(value: [char; 3]) -> Self {
-        let codon: Codon = Codon::from_chars(value);
-        let aminoacid = &AMINOACID_TABLE
-            .clone()
-            .into_iter()
-            .filter(|aminoacid| aminoacid.codons.contains(&codon))
-            .collect::<Vec<Aminoacid>>()[0];
+        Aminoacid::from_table(value, &AMINOACID_TABLE)
+    }
+}
+
+impl Aminoacid {
+    /// Aminoacid of a codon that can not be translated
+    pub fn unknown() -> Self {
         Aminoacid {
-            aminoacid: aminoacid.aminoacid,
-            codons: aminoacid.codons.clone(),
+            aminoacid: UNKNOWN_AMINOACID,
+            codons: Vec::new(),
+        }
+    }
+
+    /// The aminoacid a letter stands for, in any case, with its codons in the standard genetic code.
+    /// Letters outside the table have no codons.
+    pub fn from_letter(letter: AminoacidValue) -> Self {
+        let letter: AminoacidValue = letter.to_ascii_lowercase();
+        AMINOACID_TABLE
+            .iter()
+            .find(|aminoacid| aminoacid.aminoacid == letter)
+            .cloned()
+            .unwrap_or(Aminoacid {
+                aminoacid: letter,
+                codons: Vec::new(),
+            })
+    }
+
+    /// Whether this is the end of a protein rather than an aminoacid
+    pub fn is_stop(&self) -> bool {
+        self.aminoacid == STOP
+    }
+
+    /// Translates a DNA or RNA codon, in any case, with the aminoacids of `table`. Ambiguous codons
+    /// are translated as with `Aminoacid::from`.
+    pub fn from_table(codon: [char; 3], table: &[Aminoacid]) -> Self {
+        let codon: [char; 3] = to_rna_codon(codon);
+        if let Some(aminoacid) = lookup(&Codon::from_chars(codon), table) {
+            return aminoacid.clone();
+        }
+        let expansions: Vec<Codon> = expand_codon(codon);
+        let candidates: Vec<&Aminoacid> = expansions
+            .iter()
+            .filter_map(|codon| lookup(codon, table))
+            .collect();
+        match candidates.first() {
+            Some(first)
+                if candidates.len() == expansions.len()
+                    && candidates.iter().all(|aa| aa.aminoacid == first.aminoacid) =>
+            {
+                (*first).clone()
+            }
+            _ => Aminoacid::unknown(),
         }
     }
 }
 
+/// The lowercase RNA codon for a DNA or RNA codon.
+pub(crate) fn to_rna_codon(codon: [char; 3]) -> [char; 3] {
+    codon.map(|b| match b.to_ascii_lowercase() {
+        't' => 'u',
+        b => b,
+    })
+}
+
+/// Finds the aminoacid a codon codes for in `table`.
+fn lookup<'a>(codon: &Codon, table: &'a [Aminoacid]) -> Option<&'a Aminoacid> {
+    table
+        .iter()
+        .find(|aminoacid| aminoacid.codons.contains(codon))
+}
+
+/// All the RNA codons a codon with ambiguity codes may stand for. Empty if any base is not an
+/// IUPAC code.
+fn expand_codon(codon: [char; 3]) -> Vec<Codon> {
+    let to_rna = |bases: &[char]| -> Vec<char> {
+        bases
+            .iter()
+            .map(|&b| if b == 't' { 'u' } else { b })
+            .collect()
+    };
+    let (Some(first), Some(second), Some(third)) = (
+        iupac::expand(codon[0]),
+        iupac::expand(codon[1]),
+        iupac::expand(codon[2]),
+    ) else {
+        return Vec::new();
+    };
+    let (first, second, third) = (to_rna(first), to_rna(second), to_rna(third));
+    first
+        .iter()
+        .flat_map(|&a| {
+            second.iter().flat_map({
+                let third = &third;
+                move |&b| third.iter().map(move |&c| Codon::from_chars([a, b, c]))
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ctxs::{aminoacid::domain::aminoacid::Aminoacid, codon::domain::codon::Codon};
@@ -75,4 +173,26 @@ mod tests {
         assert_eq!(matched_aa.aminoacid, base_aa.aminoacid);
         assert_eq!(matched_aa.codons, base_aa.codons);
     }
+
+    #[test]
+    fn translate_ambiguous_codons() {
+        assert_eq!(Aminoacid::from(['g', 'c', 'n']).aminoacid, 'a');
+        assert_eq!(Aminoacid::from(['c', 'u', 'n']).aminoacid, 'l');
+        assert_eq!(Aminoacid::from(['u', 'a', 'r']).aminoacid, '*');
+        assert_eq!(Aminoacid::from(['a', 'y', 'g']).aminoacid, 'x');
+        assert_eq!(Aminoacid::from(['a', '-', 'g']).aminoacid, 'x');
+    }
+
+    #[test]
+    fn translate_masked_codons() {
+        assert_eq!(Aminoacid::from(['A', 'U', 'G']).aminoacid, 'm');
+        assert_eq!(Aminoacid::from(['G', 'c', 'N']).aminoacid, 'a');
+    }
+
+    #[test]
+    fn translate_dna_codons() {
+        assert_eq!(Aminoacid::from(['a', 't', 'g']).aminoacid, 'm');
+        assert_eq!(Aminoacid::from(['T', 'G', 'G']).aminoacid, 'w');
+        assert_eq!(Aminoacid::from(['t', 'a', 'r']).aminoacid, '*');
+    }
 }
@@ -0,0 +1,450 @@
+//! NCBI genetic codes: which aminoacid every codon codes for, and which codons may start a protein
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    ctxs::{
+        aminoacid::domain::aminoacid::{to_rna_codon, Aminoacid},
+        codon::domain::codon::Codon,
+        fasta::domain::errors::FastaError,
+    },
+    shared::utils::AMINOACID_TABLE,
+};
+
+/// Bases in the order NCBI lists codons: `uuu`, `uuc`, `uua`, `uug`, `ucu`...
+const NCBI_BASES: [char; 4] = ['u', 'c', 'a', 'g'];
+
+/// Id of the standard genetic code
+pub const STANDARD_CODE: u8 = 1;
+
+/// Every NCBI translation table: id, name, aminoacid of each codon and start codons (`M`), as
+/// NCBI lists them.
+const NCBI_TABLES: [(u8, &str, &str, &str); 27] = [
+    (
+        1,
+        "Standard",
+        "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M---------------M---------------M----------------------------",
+    ),
+    (
+        2,
+        "Vertebrate Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        "--------------------------------MMMM---------------M------------",
+    ),
+    (
+        3,
+        "Yeast Mitochondrial",
+        "FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------------------------------MM----------------------------",
+    ),
+    (
+        4,
+        "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--MM---------------M------------MMMM---------------M------------",
+    ),
+    (
+        5,
+        "Invertebrate Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        "---M----------------------------MMMM---------------M------------",
+    ),
+    (
+        6,
+        "Ciliate, Dasycladacean and Hexamita Nuclear",
+        "FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        9,
+        "Echinoderm and Flatworm Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        "-----------------------------------M---------------M------------",
+    ),
+    (
+        10,
+        "Euplotid Nuclear",
+        "FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        11,
+        "Bacterial, Archaeal and Plant Plastid",
+        "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M---------------M------------MMMM---------------M------------",
+    ),
+    (
+        12,
+        "Alternative Yeast Nuclear",
+        "FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-------------------M---------------M----------------------------",
+    ),
+    (
+        13,
+        "Ascidian Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        "---M------------------------------MM---------------M------------",
+    ),
+    (
+        14,
+        "Alternative Flatworm Mitochondrial",
+        "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        15,
+        "Blepharisma Nuclear",
+        "FFLLSSSSYY*QCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        16,
+        "Chlorophycean Mitochondrial",
+        "FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        21,
+        "Trematode Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        "-----------------------------------M---------------M------------",
+    ),
+    (
+        22,
+        "Scenedesmus obliquus Mitochondrial",
+        "FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        23,
+        "Thraustochytrium Mitochondrial",
+        "FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--------------------------------M--M---------------M------------",
+    ),
+    (
+        24,
+        "Rhabdopleuridae Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        "---M---------------M---------------M---------------M------------",
+    ),
+    (
+        25,
+        "Candidate Division SR1 and Gracilibacteria",
+        "FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M-------------------------------M---------------M------------",
+    ),
+    (
+        26,
+        "Pachysolen tannophilus Nuclear",
+        "FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-------------------M---------------M----------------------------",
+    ),
+    (
+        27,
+        "Karyorelict Nuclear",
+        "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        28,
+        "Condylostoma Nuclear",
+        "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        29,
+        "Mesodinium Nuclear",
+        "FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        30,
+        "Peritrich Nuclear",
+        "FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        31,
+        "Blastocrithidia Nuclear",
+        "FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        32,
+        "Balanophoraceae Plastid",
+        "FFLLSSSSYY*WCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M---------------M------------MMMM---------------M------------",
+    ),
+    (
+        33,
+        "Cephalodiscidae Mitochondrial",
+        "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        "---M---------------M---------------M---------------M------------",
+    ),
+];
+
+/// A genetic code, as one of the NCBI translation tables
+#[derive(Clone)]
+pub struct GeneticCode {
+    /// NCBI id of the table
+    pub id: u8,
+    /// NCBI name of the table
+    pub name: &'static str,
+    /// Aminoacids, with the codons that code for them in this table
+    aminoacids: Vec<Aminoacid>,
+    /// Codons that may start a protein
+    starts: Vec<Codon>,
+}
+
+impl Default for GeneticCode {
+    fn default() -> Self {
+        GeneticCode::standard()
+    }
+}
+
+impl Display for GeneticCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "table {} ({})", self.id, self.name)
+    }
+}
+
+impl FromStr for GeneticCode {
+    type Err = FastaError;
+
+    /// Parses the NCBI id of a table
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .and_then(GeneticCode::from_id)
+            .ok_or_else(|| FastaError::UnknownGeneticCode(value.to_string()))
+    }
+}
+
+impl GeneticCode {
+    /// The standard genetic code, NCBI table 1
+    pub fn standard() -> Self {
+        GeneticCode::from_id(STANDARD_CODE).unwrap_or_else(|| unreachable!())
+    }
+
+    /// The NCBI table with the given id, if there is one
+    pub fn from_id(id: u8) -> Option<Self> {
+        let (id, name, aminoacids, starts) =
+            NCBI_TABLES.iter().find(|(table_id, ..)| *table_id == id)?;
+        let aminoacids: Vec<Aminoacid> = match *id {
+            STANDARD_CODE => AMINOACID_TABLE.clone(),
+            _ => aminoacids_of(aminoacids),
+        };
+        let starts: Vec<Codon> = ncbi_codons()
+            .zip(starts.chars())
+            .filter(|(_, start)| *start == 'M')
+            .map(|(codon, _)| codon)
+            .collect();
+        Some(GeneticCode {
+            id: *id,
+            name,
+            aminoacids,
+            starts,
+        })
+    }
+
+    /// Translates a DNA or RNA codon, in any case. Ambiguous codons are translated as with
+    /// `Aminoacid::from`.
+    pub fn translate(&self, codon: [char; 3]) -> Aminoacid {
+        Aminoacid::from_table(codon, &self.aminoacids)
+    }
+
+    /// Every RNA codon coding for the same aminoacid, or stop, as a DNA or RNA codon, itself
+    /// included. In the order NCBI lists them.
+    pub fn synonyms(&self, codon: [char; 3]) -> Vec<Codon> {
+        let aminoacid: char = self.translate(codon).aminoacid;
+        ncbi_codons()
+            .filter(|synonym| self.translate(synonym.bases()).aminoacid == aminoacid)
+            .collect()
+    }
+
+    /// Whether a DNA or RNA codon may start a protein
+    pub fn is_start(&self, codon: [char; 3]) -> bool {
+        let codon: Codon = Codon::from_chars(to_rna_codon(codon));
+        self.starts.contains(&codon)
+    }
+}
+
+/// Every RNA codon, in the order NCBI lists them: `UUU`, `UUC`, `UUA`, `UUG`, `UCU`...
+pub fn ncbi_codons() -> impl Iterator<Item = Codon> {
+    (0..64).map(|i| {
+        Codon::from_chars([
+            NCBI_BASES[i / 16],
+            NCBI_BASES[(i / 4) % 4],
+            NCBI_BASES[i % 4],
+        ])
+    })
+}
+
+/// Groups the codons of a NCBI aminoacid line by the aminoacid they code for.
+fn aminoacids_of(line: &str) -> Vec<Aminoacid> {
+    let mut aminoacids: Vec<Aminoacid> = Vec::new();
+    for (codon, aminoacid) in ncbi_codons().zip(line.chars()) {
+        let aminoacid: char = aminoacid.to_ascii_lowercase();
+        match aminoacids.iter_mut().find(|aa| aa.aminoacid == aminoacid) {
+            Some(aa) => aa.codons.push(codon),
+            None => aminoacids.push(Aminoacid {
+                aminoacid,
+                codons: vec![codon],
+            }),
+        }
+    }
+    aminoacids
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::aminoacid::domain::{aminoacid::Aminoacid, genetic_code::GeneticCode};
+
+    const BASES: [char; 4] = ['u', 'c', 'a', 'g'];
+
+    /// Every codon, as a string, with its aminoacid in the standard code.
+    #[rustfmt::skip]
+    const STANDARD: [(&str, char); 64] = [
+        ("uuu", 'f'), ("uuc", 'f'), ("uua", 'l'), ("uug", 'l'),
+        ("ucu", 's'), ("ucc", 's'), ("uca", 's'), ("ucg", 's'),
+        ("uau", 'y'), ("uac", 'y'), ("uaa", '*'), ("uag", '*'),
+        ("ugu", 'c'), ("ugc", 'c'), ("uga", '*'), ("ugg", 'w'),
+        ("cuu", 'l'), ("cuc", 'l'), ("cua", 'l'), ("cug", 'l'),
+        ("ccu", 'p'), ("ccc", 'p'), ("cca", 'p'), ("ccg", 'p'),
+        ("cau", 'h'), ("cac", 'h'), ("caa", 'q'), ("cag", 'q'),
+        ("cgu", 'r'), ("cgc", 'r'), ("cga", 'r'), ("cgg", 'r'),
+        ("auu", 'i'), ("auc", 'i'), ("aua", 'i'), ("aug", 'm'),
+        ("acu", 't'), ("acc", 't'), ("aca", 't'), ("acg", 't'),
+        ("aau", 'n'), ("aac", 'n'), ("aaa", 'k'), ("aag", 'k'),
+        ("agu", 's'), ("agc", 's'), ("aga", 'r'), ("agg", 'r'),
+        ("guu", 'v'), ("guc", 'v'), ("gua", 'v'), ("gug", 'v'),
+        ("gcu", 'a'), ("gcc", 'a'), ("gca", 'a'), ("gcg", 'a'),
+        ("gau", 'd'), ("gac", 'd'), ("gaa", 'e'), ("gag", 'e'),
+        ("ggu", 'g'), ("ggc", 'g'), ("gga", 'g'), ("ggg", 'g'),
+    ];
+
+    /// Codons every table translates unlike the standard code, and its start codons.
+    #[rustfmt::skip]
+    #[allow(clippy::type_complexity)]
+    const TABLES: [(u8, &[(&str, char)], &[&str]); 27] = [
+        (1, &[], &["uug", "cug", "aug"]),
+        (2, &[("aga", '*'), ("agg", '*'), ("aua", 'm'), ("uga", 'w')], &["auu", "auc", "aua", "aug", "gug"]),
+        (3, &[("aua", 'm'), ("cuu", 't'), ("cuc", 't'), ("cua", 't'), ("cug", 't'), ("uga", 'w')], &["aua", "aug"]),
+        (4, &[("uga", 'w')], &["uua", "uug", "cug", "auu", "auc", "aua", "aug", "gug"]),
+        (5, &[("aga", 's'), ("agg", 's'), ("aua", 'm'), ("uga", 'w')], &["uug", "auu", "auc", "aua", "aug", "gug"]),
+        (6, &[("uaa", 'q'), ("uag", 'q')], &["aug"]),
+        (9, &[("aaa", 'n'), ("aga", 's'), ("agg", 's'), ("uga", 'w')], &["aug", "gug"]),
+        (10, &[("uga", 'c')], &["aug"]),
+        (11, &[], &["uug", "cug", "auu", "auc", "aua", "aug", "gug"]),
+        (12, &[("cug", 's')], &["cug", "aug"]),
+        (13, &[("aga", 'g'), ("agg", 'g'), ("aua", 'm'), ("uga", 'w')], &["uug", "aua", "aug", "gug"]),
+        (14, &[("aaa", 'n'), ("aga", 's'), ("agg", 's'), ("uaa", 'y'), ("uga", 'w')], &["aug"]),
+        (15, &[("uag", 'q')], &["aug"]),
+        (16, &[("uag", 'l')], &["aug"]),
+        (21, &[("uga", 'w'), ("aua", 'm'), ("aga", 's'), ("agg", 's'), ("aaa", 'n')], &["aug", "gug"]),
+        (22, &[("uca", '*'), ("uag", 'l')], &["aug"]),
+        (23, &[("uua", '*')], &["auu", "aug", "gug"]),
+        (24, &[("aga", 's'), ("agg", 'k'), ("uga", 'w')], &["uug", "cug", "aug", "gug"]),
+        (25, &[("uga", 'g')], &["uug", "aug", "gug"]),
+        (26, &[("cug", 'a')], &["cug", "aug"]),
+        (27, &[("uag", 'q'), ("uaa", 'q'), ("uga", 'w')], &["aug"]),
+        (28, &[("uaa", 'q'), ("uag", 'q'), ("uga", 'w')], &["aug"]),
+        (29, &[("uaa", 'y'), ("uag", 'y')], &["aug"]),
+        (30, &[("uaa", 'e'), ("uag", 'e')], &["aug"]),
+        (31, &[("uga", 'w'), ("uag", 'e'), ("uaa", 'e')], &["aug"]),
+        (32, &[("uag", 'w')], &["uug", "cug", "auu", "auc", "aua", "aug", "gug"]),
+        (33, &[("aga", 's'), ("agg", 'k'), ("uaa", 'y'), ("uga", 'w')], &["uug", "cug", "aug", "gug"]),
+    ];
+
+    fn chars(codon: &str) -> [char; 3] {
+        let bases: Vec<char> = codon.chars().collect();
+        [bases[0], bases[1], bases[2]]
+    }
+
+    #[test]
+    fn standard_table_codes_every_codon_once() {
+        for (codon, aminoacid) in STANDARD {
+            assert_eq!(
+                Aminoacid::from(chars(codon)).aminoacid,
+                aminoacid,
+                "{}",
+                codon
+            );
+        }
+        let codons: usize = GeneticCode::standard()
+            .aminoacids
+            .iter()
+            .map(|aa| aa.codons.len())
+            .sum();
+        assert_eq!(codons, 64);
+    }
+
+    #[test]
+    fn every_table_translates_every_codon() {
+        for (id, changes, starts) in TABLES {
+            let code: GeneticCode = GeneticCode::from_id(id).unwrap();
+            for (codon, standard) in STANDARD {
+                let expected: char = changes
+                    .iter()
+                    .find(|(changed, _)| *changed == codon)
+                    .map_or(standard, |(_, aminoacid)| *aminoacid);
+                let translated: Aminoacid = code.translate(chars(codon));
+                assert_eq!(translated.aminoacid, expected, "table {}, {}", id, codon);
+                assert_eq!(
+                    code.is_start(chars(codon)),
+                    starts.contains(&codon),
+                    "table {}, {}",
+                    id,
+                    codon
+                );
+            }
+        }
+        let tables: usize = (0..=u8::MAX).filter_map(GeneticCode::from_id).count();
+        assert_eq!(tables, 27);
+    }
+
+    #[test]
+    fn every_codon_is_listed() {
+        for a in BASES {
+            for b in BASES {
+                for c in BASES {
+                    let codon: String = [a, b, c].iter().collect();
+                    assert!(STANDARD.iter().any(|(listed, _)| *listed == codon));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_table_ids() {
+        assert_eq!(
+            "2".parse::<GeneticCode>().unwrap().name,
+            "Vertebrate Mitochondrial"
+        );
+        assert!("7".parse::<GeneticCode>().is_err());
+        assert!("mito".parse::<GeneticCode>().is_err());
+    }
+
+    #[test]
+    fn translate_with_any_table() {
+        let code: GeneticCode = GeneticCode::from_id(2).unwrap();
+        assert_eq!(code.translate(['U', 'G', 'A']).aminoacid, 'w');
+        assert_eq!(code.translate(['a', 'g', 'r']).aminoacid, '*');
+        assert_eq!(code.translate(['a', 'u', 'r']).aminoacid, 'm');
+    }
+
+    #[test]
+    fn find_synonymous_codons() {
+        let synonyms = |code: &GeneticCode, codon: [char; 3]| -> Vec<String> {
+            code.synonyms(codon).iter().map(|c| c.to_string()).collect()
+        };
+        let standard: GeneticCode = GeneticCode::standard();
+        assert_eq!(synonyms(&standard, ['T', 'G', 'G']), ["ugg"]);
+        assert_eq!(synonyms(&standard, ['u', 'a', 'a']), ["uaa", "uag", "uga"]);
+        assert_eq!(synonyms(&standard, ['a', 'g', 'a']).len(), 6);
+        let mitochondrial: GeneticCode = GeneticCode::from_id(2).unwrap();
+        assert_eq!(synonyms(&mitochondrial, ['u', 'g', 'g']), ["uga", "ugg"]);
+    }
+}
@@ -1 +1,2 @@
 pub mod aminoacid;
+pub mod genetic_code;
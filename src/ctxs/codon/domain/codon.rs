@@ -28,6 +28,11 @@ impl From<[char; 3]> for Codon {
 }
 
 impl Codon {
+    /// The bases of the codon
+    pub fn bases(&self) -> CodonValue {
+        self.codon
+    }
+
     /// Create a new Codon from an array of 3 chars
     pub fn from_chars(value: [char; 3]) -> Self {
         let value_binding = value;
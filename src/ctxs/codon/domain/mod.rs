@@ -1 +1,4 @@
 pub mod codon;
+pub mod optimization;
+pub mod statistics;
+pub mod usage;
@@ -0,0 +1,290 @@
+//! Codon optimization: rewriting coding sequences with the codons a host uses the most
+use crate::ctxs::{
+    aminoacid::domain::genetic_code::GeneticCode,
+    codon::domain::{codon::Codon, usage::CodonUsage},
+    fasta::domain::{errors::FastaError, fasta::Fasta, iupac},
+    protein::domain::protein::{Protein, TranslationOptions},
+};
+
+/// Settings for optimizing coding sequences for a host
+#[derive(Clone)]
+pub struct CodonOptimizer {
+    /// Genetic code telling synonymous codons apart
+    pub code: GeneticCode,
+    /// How often the host uses every codon
+    pub usage: CodonUsage,
+    /// Sites not to create on either strand, like restriction sites. IUPAC codes may be used.
+    pub avoid: Vec<String>,
+    /// Longest run of a single base allowed
+    pub max_homopolymer: usize,
+    /// Bases of the windows whose GC content is checked
+    pub gc_window: usize,
+    /// Lowest share of G and C allowed in any window
+    pub min_gc: f64,
+    /// Highest share of G and C allowed in any window
+    pub max_gc: f64,
+}
+
+impl Default for CodonOptimizer {
+    fn default() -> Self {
+        CodonOptimizer {
+            code: GeneticCode::default(),
+            usage: CodonUsage::default(),
+            avoid: Vec::new(),
+            max_homopolymer: 5,
+            gc_window: 50,
+            min_gc: 0.3,
+            max_gc: 0.7,
+        }
+    }
+}
+
+/// A coding sequence rewritten by a [CodonOptimizer]
+pub struct Optimization {
+    /// The rewritten sequence, with the header of the original one
+    pub fasta: Fasta,
+    /// Codons replaced by a synonym
+    pub changed: usize,
+    /// Codons chosen breaking some constraint, for every synonym broke some
+    pub unmet: usize,
+}
+
+impl CodonOptimizer {
+    /// Rewrites every codon of a coding sequence, read from its first base, with the synonym the
+    /// host uses the most that neither creates an avoided site, nor a long homopolymer, nor a
+    /// window of extreme GC content. If every synonym does, the one breaking the fewest constraints
+    /// is chosen. Codons that can not be translated, and an incomplete last codon, are kept as they
+    /// are. The result is uppercase DNA, and fails if it does not code for the same protein.
+    pub fn optimize(&self, fasta: &Fasta) -> Result<Optimization, FastaError> {
+        let bases: Vec<char> = fasta
+            .sequence
+            .get_chars()
+            .map(|base| match base.to_ascii_uppercase() {
+                'U' => 'T',
+                base => base,
+            })
+            .collect();
+        let sites: Vec<Vec<char>> = self.sites();
+        let mut optimized: Vec<char> = Vec::with_capacity(bases.len());
+        let (mut changed, mut unmet): (usize, usize) = (0, 0);
+        for codon in bases.chunks(3) {
+            let candidates: Vec<[char; 3]> = match codon {
+                &[first, second, third] => self.candidates([first, second, third]),
+                _ => Vec::new(),
+            };
+            let Some(&first) = candidates.first() else {
+                optimized.extend_from_slice(codon);
+                continue;
+            };
+            let (chosen, violations) = candidates
+                .iter()
+                .map(|&candidate| (candidate, self.violations(&optimized, candidate, &sites)))
+                .fold((first, usize::MAX), |best, candidate| {
+                    match candidate.1 < best.1 {
+                        true => candidate,
+                        false => best,
+                    }
+                });
+            if violations > 0 {
+                unmet += 1;
+            }
+            if chosen[..] != *codon {
+                changed += 1;
+            }
+            optimized.extend_from_slice(&chosen);
+        }
+        let optimized: Fasta = Fasta::from((
+            fasta.header.as_str().to_string(),
+            optimized.into_iter().collect::<String>(),
+        ));
+        self.check_protein(fasta, &optimized)?;
+        Ok(Optimization {
+            fasta: optimized,
+            changed,
+            unmet,
+        })
+    }
+
+    /// Uppercase DNA synonyms of a codon, the most used by the host first, the codon itself first
+    /// among those as used. None if the codon can not be translated.
+    fn candidates(&self, codon: [char; 3]) -> Vec<[char; 3]> {
+        let original: [char; 3] = to_dna(&Codon::from_chars(codon));
+        let mut synonyms: Vec<Codon> = self.code.synonyms(codon);
+        synonyms.sort_by(|a, b| {
+            let (a_usage, b_usage) = (self.usage.usage(a), self.usage.usage(b));
+            b_usage
+                .total_cmp(&a_usage)
+                .then_with(|| (to_dna(b) == original).cmp(&(to_dna(a) == original)))
+        });
+        synonyms.iter().map(to_dna).collect()
+    }
+
+    /// Constraints broken by the bases ending with `codon`, appended to `previous`. Only the tail of
+    /// `previous` that a site, a homopolymer or a GC window ending in `codon` can reach is checked.
+    fn violations(&self, previous: &[char], codon: [char; 3], sites: &[Vec<char>]) -> usize {
+        let reach: usize = sites
+            .iter()
+            .map(|site| site.len() + 2)
+            .chain([self.max_homopolymer + 3, self.gc_window])
+            .max()
+            .unwrap_or(0);
+        let tail: &[char] = &previous[previous.len().saturating_sub(reach)..];
+        let mut bases: Vec<char> = Vec::with_capacity(tail.len() + 3);
+        bases.extend_from_slice(tail);
+        bases.extend_from_slice(&codon);
+        let created_sites: usize = sites
+            .iter()
+            .filter(|site| {
+                let first: usize = bases.len().saturating_sub(site.len() + 2);
+                bases[first..]
+                    .windows(site.len())
+                    .any(|window| matches_site(window, site))
+            })
+            .count();
+        let long_run: bool = (bases.len().saturating_sub(3)..bases.len()).any(|end| {
+            let run: usize = bases[..=end]
+                .iter()
+                .rev()
+                .take_while(|&&base| base == bases[end])
+                .count();
+            run > self.max_homopolymer
+        });
+        let extreme_gc: bool = bases.len() >= self.gc_window
+            && self.gc_window > 0
+            && (bases.len() - 2..=bases.len())
+                .filter(|&end| end >= self.gc_window)
+                .any(|end| {
+                    let window: &[char] = &bases[end - self.gc_window..end];
+                    let gc: usize = window.iter().filter(|&&b| b == 'G' || b == 'C').count();
+                    let share: f64 = gc as f64 / self.gc_window as f64;
+                    share < self.min_gc || share > self.max_gc
+                });
+        created_sites + long_run as usize + extreme_gc as usize
+    }
+
+    /// Sites to avoid, uppercase, and their reverse-complementaries.
+    fn sites(&self) -> Vec<Vec<char>> {
+        let mut sites: Vec<Vec<char>> = Vec::new();
+        for site in self.avoid.iter().filter(|site| !site.is_empty()) {
+            let forward: Vec<char> = site.to_uppercase().chars().collect();
+            let reverse: Vec<char> = forward
+                .iter()
+                .rev()
+                .map(|&base| iupac::complement(base, false))
+                .collect();
+            if reverse != forward {
+                sites.push(reverse);
+            }
+            sites.push(forward);
+        }
+        sites
+    }
+
+    /// Fails if the optimized sequence does not code for the same protein as the original one.
+    fn check_protein(&self, original: &Fasta, optimized: &Fasta) -> Result<(), FastaError> {
+        let options: TranslationOptions = TranslationOptions {
+            code: self.code.clone(),
+            ..Default::default()
+        };
+        let translate = |fasta: &Fasta| Protein::translate(fasta, &options).to_string();
+        match translate(original) == translate(optimized) {
+            true => Ok(()),
+            false => Err(FastaError::ChangedProtein(
+                original.header.as_str().to_string(),
+            )),
+        }
+    }
+}
+
+/// The bases of a codon as uppercase DNA.
+fn to_dna(codon: &Codon) -> [char; 3] {
+    codon.bases().map(|base| match base.to_ascii_uppercase() {
+        'U' => 'T',
+        base => base,
+    })
+}
+
+/// Whether uppercase bases match a site, which may have IUPAC codes.
+fn matches_site(bases: &[char], site: &[char]) -> bool {
+    bases.iter().zip(site).all(|(&base, &code)| {
+        base == code
+            || iupac::expand(code).is_some_and(|codes| {
+                iupac::expand(base).is_some_and(|bases| bases.iter().all(|b| codes.contains(b)))
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::{
+        codon::domain::optimization::{CodonOptimizer, Optimization},
+        fasta::domain::fasta::Fasta,
+        protein::domain::protein::Protein,
+    };
+
+    fn optimizer(usage: &str) -> CodonOptimizer {
+        CodonOptimizer {
+            usage: usage.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn optimize(optimizer: &CodonOptimizer, sequence: &str) -> Optimization {
+        optimizer
+            .optimize(&Fasta::from(("cds1", sequence)))
+            .unwrap()
+    }
+
+    #[test]
+    fn optimize_with_most_used_codons() {
+        let optimizer: CodonOptimizer = optimizer("GCC 30 GCT 10 AAG 10 AAA 5 TAA 10 TGA 1");
+        let optimization: Optimization = optimize(&optimizer, "atgGCTAAaGCCtgaNNNgc");
+        assert_eq!(optimization.fasta.header.as_str(), "cds1");
+        assert_eq!(optimization.fasta.sequence.as_str(), "ATGGCCAAGGCCTAANNNGC");
+        assert_eq!((optimization.changed, optimization.unmet), (3, 0));
+    }
+
+    #[test]
+    fn optimize_avoiding_sites() {
+        // GAA TTC would make an EcoRI site
+        let mut optimizer: CodonOptimizer = optimizer("GAA 10 GAG 1 TTC 10 TTT 1");
+        assert_eq!(
+            optimize(&optimizer, "GAGTTT").fasta.sequence.as_str(),
+            "GAATTC"
+        );
+        optimizer.avoid = vec!["GAATTC".to_string()];
+        assert_eq!(
+            optimize(&optimizer, "GAGTTT").fasta.sequence.as_str(),
+            "GAATTT"
+        );
+        // BsaI, GGTCTC, found on the reverse strand as GAGACC
+        optimizer.usage = "GAG 10 GAA 1 ACC 10 ACT 1".parse().unwrap();
+        optimizer.avoid = vec!["GGTCTC".to_string()];
+        assert_eq!(
+            optimize(&optimizer, "GAAACT").fasta.sequence.as_str(),
+            "GAGACT"
+        );
+        optimizer.avoid = vec!["GRGACC".to_string()];
+        assert_eq!(
+            optimize(&optimizer, "GAAACT").fasta.sequence.as_str(),
+            "GAGACT"
+        );
+    }
+
+    #[test]
+    fn optimize_avoiding_homopolymers_and_extreme_gc() {
+        let mut optimizer: CodonOptimizer = optimizer("AAA 10 AAG 1");
+        optimizer.gc_window = 0;
+        let optimization: Optimization = optimize(&optimizer, "AAGAAGAAG");
+        assert_eq!(optimization.fasta.sequence.as_str(), "AAAAAGAAA");
+        optimizer.max_homopolymer = 1;
+        assert_eq!(optimize(&optimizer, "AAGAAG").unmet, 2);
+        let mut optimizer: CodonOptimizer = self::optimizer("GCC 10 GCG 5 GCT 1 GCA 1");
+        optimizer.gc_window = 6;
+        optimizer.max_gc = 0.85;
+        let optimization: Optimization = optimize(&optimizer, "GCAGCAGCA");
+        assert_eq!(optimization.fasta.sequence.as_str(), "GCCGCAGCC");
+        let protein = |sequence: &str| Protein::from(Fasta::from(("", sequence))).to_string();
+        assert_eq!(protein("GCCGCAGCC"), protein("GCAGCAGCA"));
+    }
+}
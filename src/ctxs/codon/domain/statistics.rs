@@ -0,0 +1,229 @@
+//! Codon usage statistics of genes: codon adaptation index, effective number of codons and GC3
+use crate::ctxs::{
+    aminoacid::domain::genetic_code::{ncbi_codons, GeneticCode},
+    codon::domain::{
+        codon::Codon,
+        usage::{format_optional, CodonUsage, TableFormat},
+    },
+};
+
+/// Weight in the codon adaptation index of codons the reference never uses, instead of 0, so that a
+/// single one does not bring the index down to 0
+const UNUSED_CODON_WEIGHT: f64 = 0.01;
+
+/// Codon usage statistics of a gene
+pub struct GeneStatistics {
+    /// First word of the header of the gene
+    pub id: String,
+    /// Codons counted, stops included
+    pub codons: usize,
+    /// Codon adaptation index (Sharp & Li, 1987) against a reference usage table
+    pub cai: Option<f64>,
+    /// Effective number of codons (Wright, 1990), from 20 to 61 in the standard code
+    pub enc: Option<f64>,
+    /// Share of sense codons with `G` or `C` at their third position
+    pub gc3: Option<f64>,
+}
+
+impl GeneStatistics {
+    /// Statistics of the codons of a gene, `usage`, against the usage of a reference set of highly
+    /// expressed genes, with the families of synonymous codons of `code`.
+    pub fn of(id: &str, usage: &CodonUsage, reference: &CodonUsage, code: &GeneticCode) -> Self {
+        GeneStatistics {
+            id: id.to_string(),
+            codons: usage.total() as usize,
+            cai: cai(usage, reference, code),
+            enc: enc(usage, code),
+            gc3: gc3(usage, code),
+        }
+    }
+
+    /// Statistics of every gene as a table.
+    pub fn table(genes: &[GeneStatistics], format: TableFormat) -> String {
+        let rows: Vec<Vec<(&str, String)>> = genes
+            .iter()
+            .map(|gene| {
+                vec![
+                    ("id", gene.id.clone()),
+                    ("codons", gene.codons.to_string()),
+                    ("cai", format_optional(gene.cai, 3)),
+                    ("enc", format_optional(gene.enc, 2)),
+                    ("gc3", format_optional(gene.gc3, 3)),
+                ]
+            })
+            .collect();
+        format.write(&rows)
+    }
+}
+
+/// Geometric mean of the relative adaptiveness of the codons of a gene: the usage of every codon
+/// in the reference over that of its most used synonym. Codons without synonyms, stops and those
+/// whose family the reference does not use are left out. `None` if no codon is left.
+fn cai(usage: &CodonUsage, reference: &CodonUsage, code: &GeneticCode) -> Option<f64> {
+    let (mut log_sum, mut count): (f64, f64) = (0_f64, 0_f64);
+    for (codon, times) in usage.codons() {
+        if code.translate(codon.bases()).is_stop() {
+            continue;
+        }
+        let synonyms: Vec<Codon> = code.synonyms(codon.bases());
+        let most_used: f64 = synonyms
+            .iter()
+            .map(|synonym| reference.usage(synonym))
+            .fold(0_f64, f64::max);
+        if synonyms.len() < 2 || most_used == 0_f64 {
+            continue;
+        }
+        let weight: f64 = match reference.usage(&codon) {
+            0_f64 => UNUSED_CODON_WEIGHT,
+            used => used / most_used,
+        };
+        log_sum += times * weight.ln();
+        count += times;
+    }
+    (count > 0_f64).then(|| (log_sum / count).exp())
+}
+
+/// Effective number of codons: the number of aminoacids coded by a single codon, plus, for every
+/// family size, the number of families of that size over their mean homozygosity. Families used
+/// fewer than twice are left out of the means, and a missing mean for families of three is
+/// estimated from those of two and four, as Wright does. Every size adds at most its number of
+/// codons. `None` if a mean is still missing.
+fn enc(usage: &CodonUsage, code: &GeneticCode) -> Option<f64> {
+    let families: Vec<Vec<Codon>> = families(code);
+    let mut sizes: Vec<usize> = families.iter().map(Vec::len).collect();
+    sizes.sort_unstable();
+    sizes.dedup();
+    let mean_homozygosity = |size: usize| -> Option<f64> {
+        let homozygosities: Vec<f64> = families
+            .iter()
+            .filter(|family| family.len() == size)
+            .filter_map(|family| homozygosity(usage, family))
+            .collect();
+        (!homozygosities.is_empty())
+            .then(|| homozygosities.iter().sum::<f64>() / homozygosities.len() as f64)
+    };
+    let mut enc: f64 = 0_f64;
+    for &size in &sizes {
+        let count: f64 = families
+            .iter()
+            .filter(|family| family.len() == size)
+            .count() as f64;
+        if size == 1 {
+            enc += count;
+            continue;
+        }
+        let homozygosity: f64 = match (size, mean_homozygosity(size)) {
+            (_, Some(homozygosity)) => homozygosity,
+            (3, None) => (mean_homozygosity(2)? + mean_homozygosity(4)?) / 2_f64,
+            _ => return None,
+        };
+        enc += (count / homozygosity).min(count * size as f64);
+    }
+    Some(enc)
+}
+
+/// Homozygosity of a family of synonymous codons, `None` if used fewer than twice.
+fn homozygosity(usage: &CodonUsage, family: &[Codon]) -> Option<f64> {
+    let counts: Vec<f64> = family.iter().map(|codon| usage.usage(codon)).collect();
+    let total: f64 = counts.iter().sum();
+    if total < 2_f64 {
+        return None;
+    }
+    let squares: f64 = counts.iter().map(|count| (count / total).powi(2)).sum();
+    Some((total * squares - 1_f64) / (total - 1_f64))
+}
+
+/// Share of sense codons with `G` or `C` at their third position. `None` if there are none.
+fn gc3(usage: &CodonUsage, code: &GeneticCode) -> Option<f64> {
+    let (mut gc, mut total): (f64, f64) = (0_f64, 0_f64);
+    for (codon, times) in usage.codons() {
+        if code.translate(codon.bases()).is_stop() {
+            continue;
+        }
+        if matches!(codon.bases()[2], 'g' | 'c') {
+            gc += times;
+        }
+        total += times;
+    }
+    (total > 0_f64).then(|| gc / total)
+}
+
+/// Families of sense codons coding for the same aminoacid in `code`.
+fn families(code: &GeneticCode) -> Vec<Vec<Codon>> {
+    let mut families: Vec<Vec<Codon>> = Vec::new();
+    for codon in ncbi_codons() {
+        let is_known: bool = families.iter().any(|family| family.contains(&codon));
+        if !is_known && !code.translate(codon.bases()).is_stop() {
+            families.push(code.synonyms(codon.bases()));
+        }
+    }
+    families
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::{
+        aminoacid::domain::genetic_code::{ncbi_codons, GeneticCode},
+        codon::domain::{
+            statistics::{families, GeneStatistics},
+            usage::{CodonUsage, TableFormat},
+        },
+        fasta::domain::fasta::Fasta,
+    };
+
+    fn usage(sequence: &str) -> CodonUsage {
+        CodonUsage::count(&Fasta::from(("", sequence)))
+    }
+
+    #[test]
+    fn codon_adaptation_index() {
+        let reference: CodonUsage = "GCC 30 GCT 10 AAG 10 AAA 0".parse().unwrap();
+        let code: GeneticCode = GeneticCode::standard();
+        let gene: GeneStatistics =
+            GeneStatistics::of("gene1", &usage("ATGGCCGCTAAAAAGTAA"), &reference, &code);
+        let expected: f64 = (1_f64 / 3_f64 * 0.01_f64).powf(0.25);
+        assert!((gene.cai.unwrap() - expected).abs() < 1e-9);
+        assert_eq!(gene.codons, 6);
+        assert_eq!(gene.gc3, Some(0.6));
+        let unrelated: GeneStatistics = GeneStatistics::of("", &usage("TTT"), &reference, &code);
+        assert_eq!(unrelated.cai, None);
+    }
+
+    #[test]
+    fn effective_number_of_codons() {
+        let code: GeneticCode = GeneticCode::standard();
+        let families: Vec<String> = families(&code)
+            .iter()
+            .map(|family| family[0].to_string().repeat(2))
+            .collect();
+        let biased: GeneStatistics = GeneStatistics::of(
+            "",
+            &usage(&families.concat()),
+            &CodonUsage::default(),
+            &code,
+        );
+        assert_eq!(biased.enc, Some(20_f64));
+        let every_codon: String = ncbi_codons().map(|codon| codon.to_string()).collect();
+        let even: GeneStatistics =
+            GeneStatistics::of("", &usage(&every_codon), &CodonUsage::default(), &code);
+        assert_eq!(even.enc, Some(61_f64));
+        let short: GeneStatistics =
+            GeneStatistics::of("", &usage("GCTGCC"), &CodonUsage::default(), &code);
+        assert_eq!(short.enc, None);
+    }
+
+    #[test]
+    fn write_gene_statistics() {
+        let gene: GeneStatistics = GeneStatistics {
+            id: "gene1".to_string(),
+            codons: 6,
+            cai: Some(0.24028),
+            enc: None,
+            gc3: Some(0.6),
+        };
+        assert_eq!(
+            GeneStatistics::table(&[gene], TableFormat::Tsv),
+            "id\tcodons\tcai\tenc\tgc3\ngene1\t6\t0.240\tnull\t0.600\n"
+        );
+    }
+}
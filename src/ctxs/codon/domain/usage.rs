@@ -0,0 +1,355 @@
+//! Codon usage tables: how often an organism uses every codon
+use std::{fmt::Write, str::FromStr};
+
+use clap::ValueEnum;
+
+use crate::ctxs::{
+    aminoacid::domain::{
+        aminoacid::to_rna_codon,
+        genetic_code::{ncbi_codons, GeneticCode},
+    },
+    codon::domain::codon::Codon,
+    fasta::domain::{errors::FastaError, fasta::Fasta},
+};
+
+/// Formats for codon usage tables and codon statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TableFormat {
+    /// Tab-separated values, with a header line
+    #[default]
+    Tsv,
+    /// A JSON array with an object per row
+    Json,
+}
+
+impl TableFormat {
+    /// Writes rows of `(column, value)` pairs in this format. Values are written as they are in
+    /// TSV, and quoted in JSON unless they are numbers or `null`.
+    pub fn write(&self, rows: &[Vec<(&str, String)>]) -> String {
+        let mut output: String = String::new();
+        match self {
+            TableFormat::Tsv => {
+                let columns: Vec<&str> = rows
+                    .first()
+                    .map(|row| row.iter().map(|(column, _)| *column).collect())
+                    .unwrap_or_default();
+                let _ = writeln!(output, "{}", columns.join("\t"));
+                for row in rows {
+                    let values: Vec<&str> = row.iter().map(|(_, value)| value.as_str()).collect();
+                    let _ = writeln!(output, "{}", values.join("\t"));
+                }
+            }
+            TableFormat::Json => {
+                let objects: Vec<String> = rows
+                    .iter()
+                    .map(|row| {
+                        let fields: Vec<String> = row
+                            .iter()
+                            .map(|(column, value)| format!("\"{}\": {}", column, json_value(value)))
+                            .collect();
+                        format!("  {{{}}}", fields.join(", "))
+                    })
+                    .collect();
+                let _ = writeln!(output, "[\n{}\n]", objects.join(",\n"));
+            }
+        }
+        output
+    }
+}
+
+/// A TSV value as a JSON one: numbers and `null` as they are, anything else quoted.
+fn json_value(value: &str) -> String {
+    match value == "null" || value.parse::<f64>().is_ok_and(f64::is_finite) {
+        true => value.to_string(),
+        false => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+/// A number with `decimals` decimals, or `null` if missing.
+pub fn format_optional(value: Option<f64>, decimals: usize) -> String {
+    match value {
+        Some(value) => format!("{:.*}", decimals, value),
+        None => "null".to_string(),
+    }
+}
+
+/// How often every codon is used, on any scale: counts, frequencies per thousand, fractions...
+#[derive(Debug, Clone, Default)]
+pub struct CodonUsage {
+    /// Lowercase RNA codons with their usage
+    usage: Vec<(Codon, f64)>,
+}
+
+/// Parses a codon usage table. Every codon, DNA or RNA in any case, must be followed by its usage,
+/// maybe after an aminoacid. Anything else is ignored, so tab-separated tables and the Kazusa
+/// database format (`UUU 17.6(714298)  UCU 15.2(618711) ...`) are both read. JSON arrays, as
+/// `TableFormat::Json` writes them, are read from the `codon` and `count` fields of every object.
+impl FromStr for CodonUsage {
+    type Err = FastaError;
+
+    fn from_str(table: &str) -> Result<Self, Self::Err> {
+        if table.trim_start().starts_with('[') {
+            return CodonUsage::from_json(table);
+        }
+        let tokens: Vec<&str> = table
+            .split(|c: char| c.is_whitespace() || "(),;".contains(c))
+            .filter(|token| !token.is_empty())
+            .collect();
+        let mut usage: CodonUsage = CodonUsage::default();
+        for (idx, token) in tokens.iter().enumerate() {
+            let Some(codon) = parse_codon(token) else {
+                continue;
+            };
+            let value: f64 = tokens[idx + 1..]
+                .iter()
+                .take(2)
+                .find_map(|value| value.parse::<f64>().ok())
+                .ok_or_else(|| {
+                    FastaError::InvalidCodonUsage(format!("codon {} has no usage", token))
+                })?;
+            usage.insert(token, codon, value)?;
+        }
+        usage.found()
+    }
+}
+
+impl CodonUsage {
+    /// Counts the codons of a coding sequence, read from its first base. Codons with bases other
+    /// than `A`, `C`, `G`, `T` or `U`, and an incomplete last codon, are left out.
+    pub fn count(sequence: &Fasta) -> Self {
+        let bases: Vec<char> = sequence.sequence.get_chars().collect();
+        let mut usage: CodonUsage = CodonUsage::default();
+        for codon in bases.chunks_exact(3) {
+            let codon: String = codon.iter().collect();
+            if let Some(codon) = parse_codon(&codon) {
+                usage.add_usage(codon, 1_f64);
+            }
+        }
+        usage
+    }
+
+    /// Adds the usage of every codon of another table to this one.
+    pub fn add(&mut self, other: &CodonUsage) {
+        for &(codon, value) in &other.usage {
+            self.add_usage(codon, value);
+        }
+    }
+
+    /// Codons in the table, as lowercase RNA, with their usage
+    pub fn codons(&self) -> impl Iterator<Item = (Codon, f64)> + '_ {
+        self.usage.iter().copied()
+    }
+
+    /// Usage of all the codons
+    pub fn total(&self) -> f64 {
+        self.usage.iter().map(|(_, value)| value).sum()
+    }
+
+    /// Relative synonymous codon usage: the usage of a codon over the mean usage of all the codons
+    /// coding for the same aminoacid in `code`. `None` if none of them are used.
+    pub fn rscu(&self, codon: &Codon, code: &GeneticCode) -> Option<f64> {
+        let synonyms: Vec<Codon> = code.synonyms(codon.bases());
+        let family: f64 = synonyms.iter().map(|synonym| self.usage(synonym)).sum();
+        match family > 0_f64 {
+            true => Some(self.usage(codon) * synonyms.len() as f64 / family),
+            false => None,
+        }
+    }
+
+    /// The usage of every codon, as a table with its aminoacid in `code`, its usage, its usage per
+    /// thousand codons and its RSCU. DNA codons, in the order NCBI lists them.
+    pub fn table(&self, code: &GeneticCode, format: TableFormat) -> String {
+        let total: f64 = self.total();
+        let rows: Vec<Vec<(&str, String)>> = ncbi_codons()
+            .map(|codon| {
+                let usage: f64 = self.usage(&codon);
+                let per_thousand: Option<f64> = (total > 0_f64).then(|| usage * 1000_f64 / total);
+                let dna: String = codon.to_string().replace('u', "t").to_uppercase();
+                let aminoacid: char = code.translate(codon.bases()).aminoacid;
+                vec![
+                    ("codon", dna),
+                    ("aminoacid", aminoacid.to_ascii_uppercase().to_string()),
+                    ("count", usage.to_string()),
+                    ("per_thousand", format_optional(per_thousand, 2)),
+                    ("rscu", format_optional(self.rscu(&codon, code), 3)),
+                ]
+            })
+            .collect();
+        format.write(&rows)
+    }
+
+    /// Usage of a codon. 0 if it is not in the table.
+    pub fn usage(&self, codon: &Codon) -> f64 {
+        self.get(&Codon::from_chars(to_rna_codon(codon.bases())))
+            .unwrap_or(0_f64)
+    }
+
+    /// Reads a JSON array of objects with, at least, a `codon` and a `count` field.
+    fn from_json(table: &str) -> Result<Self, FastaError> {
+        let mut usage: CodonUsage = CodonUsage::default();
+        // Objects are flat, so every one runs from a `{` to the next `}`.
+        for object in table.split('{').skip(1) {
+            let fields: Vec<(&str, &str)> = object
+                .split('}')
+                .next()
+                .unwrap_or("")
+                .split(',')
+                .filter_map(|field| field.split_once(':'))
+                .map(|(key, value)| (key.trim().trim_matches('"'), value.trim().trim_matches('"')))
+                .collect();
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|&(_, value)| value)
+            };
+            let Some(token) = field("codon") else {
+                continue;
+            };
+            let codon: Codon = parse_codon(token).ok_or_else(|| {
+                FastaError::InvalidCodonUsage(format!("{} is not a codon", token))
+            })?;
+            let value: f64 = field("count")
+                .and_then(|count| count.parse::<f64>().ok())
+                .ok_or_else(|| {
+                    FastaError::InvalidCodonUsage(format!("codon {} has no count", token))
+                })?;
+            usage.insert(token, codon, value)?;
+        }
+        usage.found()
+    }
+
+    /// Adds a codon read from a table, written as `token`, checking its usage.
+    fn insert(&mut self, token: &str, codon: Codon, value: f64) -> Result<(), FastaError> {
+        if !value.is_finite() || value < 0_f64 {
+            return Err(FastaError::InvalidCodonUsage(format!(
+                "codon {} has a usage of {}",
+                token, value
+            )));
+        }
+        if self.get(&codon).is_some() {
+            return Err(FastaError::InvalidCodonUsage(format!(
+                "codon {} is repeated",
+                token
+            )));
+        }
+        self.usage.push((codon, value));
+        Ok(())
+    }
+
+    /// The table read, if it has any codon
+    fn found(self) -> Result<Self, FastaError> {
+        match self.usage.is_empty() {
+            true => Err(FastaError::InvalidCodonUsage("no codons found".to_string())),
+            false => Ok(self),
+        }
+    }
+
+    fn add_usage(&mut self, codon: Codon, value: f64) {
+        match self.usage.iter_mut().find(|(known, _)| *known == codon) {
+            Some((_, usage)) => *usage += value,
+            None => self.usage.push((codon, value)),
+        }
+    }
+
+    fn get(&self, codon: &Codon) -> Option<f64> {
+        self.usage
+            .iter()
+            .find(|(known, _)| known == codon)
+            .map(|&(_, value)| value)
+    }
+}
+
+/// The lowercase RNA codon a token stands for, if it is one.
+fn parse_codon(token: &str) -> Option<Codon> {
+    let bases: Vec<char> = token.chars().collect();
+    match bases[..] {
+        [first, second, third]
+            if bases
+                .iter()
+                .all(|base| "acgtu".contains(base.to_ascii_lowercase())) =>
+        {
+            Some(Codon::from_chars(to_rna_codon([first, second, third])))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::{
+        aminoacid::domain::genetic_code::GeneticCode,
+        codon::domain::{
+            codon::Codon,
+            usage::{CodonUsage, TableFormat},
+        },
+        fasta::domain::fasta::Fasta,
+    };
+
+    #[test]
+    fn parse_kazusa_tables() {
+        let table: &str =
+            "UUU 17.6(714298)  UCU 15.2(618711)\nUUC 20.3(824692)  UCC 17.7(718892)\n";
+        let usage: CodonUsage = table.parse().unwrap();
+        assert_eq!(usage.usage(&Codon::from_chars(['u', 'u', 'c'])), 20.3);
+        assert_eq!(usage.usage(&Codon::from_chars(['T', 'C', 'T'])), 15.2);
+        assert_eq!(usage.usage(&Codon::from_chars(['a', 'u', 'g'])), 0_f64);
+    }
+
+    #[test]
+    fn parse_tab_separated_tables() {
+        let table: &str = "codon\taminoacid\tcount\nGCT\tA\t12\nGCC\tA\t30\n";
+        let usage: CodonUsage = table.parse().unwrap();
+        assert_eq!(usage.usage(&Codon::from_chars(['g', 'c', 'c'])), 30_f64);
+        assert!("GCT\tA\n".parse::<CodonUsage>().is_err());
+        assert!("GCT 1\nGCU 2\n".parse::<CodonUsage>().is_err());
+        assert!("codon count\n".parse::<CodonUsage>().is_err());
+    }
+
+    #[test]
+    fn count_codons() {
+        let mut usage: CodonUsage = CodonUsage::count(&Fasta::from(("", "ATGgcugcNGCCgcuTAAg")));
+        let gcu: Codon = Codon::from_chars(['g', 'c', 'u']);
+        assert_eq!(usage.usage(&gcu), 2_f64);
+        assert_eq!(usage.total(), 5_f64);
+        usage.add(&CodonUsage::count(&Fasta::from(("", "GCT"))));
+        assert_eq!(usage.usage(&gcu), 3_f64);
+        let code: GeneticCode = GeneticCode::standard();
+        assert_eq!(usage.rscu(&gcu, &code), Some(3_f64));
+        assert_eq!(
+            usage.rscu(&Codon::from_chars(['g', 'c', 'g']), &code),
+            Some(0_f64)
+        );
+        assert_eq!(usage.rscu(&Codon::from_chars(['u', 'u', 'u']), &code), None);
+    }
+
+    #[test]
+    fn write_usage_tables() {
+        let usage: CodonUsage = CodonUsage::count(&Fasta::from(("", "GCTGCTGCCTAA")));
+        let code: GeneticCode = GeneticCode::standard();
+        let tsv: String = usage.table(&code, TableFormat::Tsv);
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines.len(), 65);
+        assert_eq!(lines[0], "codon\taminoacid\tcount\tper_thousand\trscu");
+        assert_eq!(lines[1], "TTT\tF\t0\t0.00\tnull");
+        assert!(lines.contains(&"GCT\tA\t2\t500.00\t2.667"));
+        assert!(lines.contains(&"TAA\t*\t1\t250.00\t3.000"));
+        let json: String = usage.table(&code, TableFormat::Json);
+        assert!(json.starts_with("[\n  {\"codon\": \"TTT\", \"aminoacid\": \"F\", \"count\": 0, \"per_thousand\": 0.00, \"rscu\": null},\n"));
+        assert!(json.ends_with("}\n]\n"));
+    }
+
+    #[test]
+    fn read_written_tables() {
+        let usage: CodonUsage = CodonUsage::count(&Fasta::from(("", "GCTGCTGCCTAA")));
+        let code: GeneticCode = GeneticCode::standard();
+        for format in [TableFormat::Tsv, TableFormat::Json] {
+            let read: CodonUsage = usage.table(&code, format).parse().unwrap();
+            assert_eq!(read.usage(&Codon::from_chars(['g', 'c', 'u'])), 2_f64);
+            assert_eq!(read.total(), 4_f64);
+            assert_eq!(read.codons().count(), 64);
+        }
+        assert!("[{\"codon\": \"GCT\"}]".parse::<CodonUsage>().is_err());
+        assert!("[]".parse::<CodonUsage>().is_err());
+    }
+}
@@ -0,0 +1,60 @@
+//! Coding sequence extraction from GenBank records
+use anyhow::Result;
+use std::path::Path;
+
+use crate::{
+    ctxs::{
+        aminoacid::domain::genetic_code::GeneticCode,
+        fasta::{
+            domain::genbank::GenBankRecord,
+            infrastructure::{genbank::GenBankReader, writer::FastaWriter},
+        },
+        protein::domain::cds::CodingSequence,
+    },
+    shared::infrastructure::io::IoOptions,
+};
+
+/// Extracts every CDS feature of the GenBank records of `file`, and writes their bases to `ofile`,
+/// or their proteins if `as_protein`, translated with their `/transl_table`, or with `code` if
+/// they have none. Both default to the standard streams. Features with bases in other records are
+/// skipped.
+pub fn extract_cds(
+    file: Option<&Path>,
+    ofile: Option<&Path>,
+    as_protein: bool,
+    code: &GeneticCode,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(ofile, io.compression)?;
+    let mut records: usize = 0;
+    let mut extracted: usize = 0;
+    let mut skipped: usize = 0;
+    for record in GenBankReader::open(file)?.with_alphabet(io.alphabet) {
+        let record: GenBankRecord = record?;
+        records += 1;
+        let features = record.features.iter().filter(|feature| feature.is_cds());
+        for (number, feature) in features.enumerate() {
+            let Some(coding) = CodingSequence::of(&record, feature, number + 1)? else {
+                skipped += 1;
+                continue;
+            };
+            extracted += 1;
+            match as_protein {
+                true => writer.write(coding.protein(code))?,
+                false => writer.write(coding.bases)?,
+            }
+        }
+    }
+    writer.flush()?;
+    let mut result: String = format!(
+        "Extracted {} coding sequences from {} records",
+        extracted, records
+    );
+    if skipped > 0 {
+        result.push_str(&format!(
+            ". Skipped {} with bases in other records or past the end of their own",
+            skipped
+        ));
+    }
+    Ok(result)
+}
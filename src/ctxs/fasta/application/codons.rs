@@ -0,0 +1,108 @@
+//! Codon usage of coding sequences, and their optimization for a host
+use anyhow::{Context, Result};
+use std::{fs, io::Write, path::Path};
+
+use crate::{
+    ctxs::{
+        aminoacid::domain::genetic_code::GeneticCode,
+        codon::domain::{
+            optimization::{CodonOptimizer, Optimization},
+            statistics::GeneStatistics,
+            usage::{CodonUsage, TableFormat},
+        },
+        fasta::{
+            domain::{fasta::Fasta, record::SequenceRecord},
+            infrastructure::writer::FastaWriter,
+        },
+    },
+    shared::infrastructure::{
+        compression::Finish,
+        io::{open_output, IoOptions, Output},
+    },
+};
+
+use super::view;
+
+/// Counts the codons of every coding sequence of `file`, and writes their usage table, in `format`,
+/// to `ofile`. Both default to the standard streams. If `genes` is given, the CAI, ENC and GC3 of
+/// every sequence are written there, the CAI against the usage table in `reference`, or against
+/// the usage of all the sequences if missing.
+pub fn codon_usage(
+    file: Option<&Path>,
+    ofile: Option<&Path>,
+    code: &GeneticCode,
+    reference: Option<&Path>,
+    genes: Option<&Path>,
+    format: TableFormat,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut total: CodonUsage = CodonUsage::default();
+    let mut usages: Vec<(String, CodonUsage)> = Vec::new();
+    for record in view::cat_f(file, io)? {
+        let record: SequenceRecord = record?;
+        let id: String = record
+            .header()
+            .as_str()
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .to_string();
+        let usage: CodonUsage = CodonUsage::count(&record.into());
+        total.add(&usage);
+        usages.push((id, usage));
+    }
+    let mut table: Output = open_output(ofile, io.compression)?;
+    table.write_all(total.table(code, format).as_bytes())?;
+    table.finish()?;
+    if let Some(genes) = genes {
+        let reference: CodonUsage = match reference {
+            Some(reference) => read_usage(reference)?,
+            None => total.clone(),
+        };
+        let statistics: Vec<GeneStatistics> = usages
+            .iter()
+            .map(|(id, usage)| GeneStatistics::of(id, usage, &reference, code))
+            .collect();
+        let mut output: Output = open_output(Some(genes), io.compression)?;
+        output.write_all(GeneStatistics::table(&statistics, format).as_bytes())?;
+        output.finish()?;
+    }
+    let result: String = format!(
+        "Counted {} codons in {} sequences",
+        total.total(),
+        usages.len()
+    );
+    Ok(result)
+}
+
+/// Rewrites the codons of every coding sequence of `file` as `optimizer` tells, and writes them
+/// to `ofile`. Both default to the standard streams.
+pub fn optimize_codons(
+    file: Option<&Path>,
+    ofile: Option<&Path>,
+    optimizer: &CodonOptimizer,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(ofile, io.compression)?;
+    let (mut sequences, mut changed, mut unmet): (usize, usize, usize) = (0, 0, 0);
+    for record in view::cat_f(file, io)? {
+        let optimization: Optimization = optimizer.optimize(&Fasta::from(record?))?;
+        sequences += 1;
+        changed += optimization.changed;
+        unmet += optimization.unmet;
+        writer.write(optimization.fasta)?;
+    }
+    writer.flush()?;
+    let result: String = format!(
+        "Optimized {} sequences, changing {} codons. {} codons break some constraint, as all their synonyms do",
+        sequences, changed, unmet
+    );
+    Ok(result)
+}
+
+/// Reads a codon usage table from a file.
+pub(crate) fn read_usage(file: &Path) -> Result<CodonUsage> {
+    let table: String =
+        fs::read_to_string(file).with_context(|| format!("Could not open {}", file.display()))?;
+    Ok(table.parse()?)
+}
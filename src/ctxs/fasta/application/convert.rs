@@ -0,0 +1,75 @@
+//! Conversion between sequence formats
+use anyhow::Result;
+use std::{io::Write, path::Path};
+
+use crate::{
+    ctxs::fasta::{
+        domain::{fasta::Fasta, genbank::GenBankRecord},
+        infrastructure::{
+            genbank::GenBankReader, twobit::TwoBitWriter, writer::FastaWriter, SequenceFormat,
+        },
+    },
+    shared::infrastructure::{
+        compression::Finish,
+        io::{display_name, open_output, IoOptions, Output},
+    },
+};
+
+use super::view;
+
+/// Reads fasta, fastq, .2bit or GenBank records from `file` and writes them to `out_file` as
+/// `format`, or as the extension of `out_file` tells if `None`. Qualities of fastq records are
+/// dropped. GFF3 is written from the feature tables of GenBank records only.
+pub fn convert(
+    file: Option<&Path>,
+    out_file: Option<&Path>,
+    format: Option<SequenceFormat>,
+    io: &IoOptions,
+) -> Result<String> {
+    let format: SequenceFormat = format
+        .unwrap_or_else(|| out_file.map_or(SequenceFormat::Fasta, SequenceFormat::from_extension));
+    let mut converted: usize = 0;
+    match format {
+        SequenceFormat::Fasta => {
+            let mut writer = FastaWriter::create(out_file, io.compression)?;
+            for record in view::cat_f(file, io)? {
+                writer.write(Fasta::from(record?))?;
+                converted += 1;
+            }
+            writer.flush()?;
+        }
+        SequenceFormat::TwoBit => {
+            let mut writer = TwoBitWriter::create(out_file, io.compression)?;
+            for record in view::cat_f(file, io)? {
+                writer.write(Fasta::from(record?))?;
+                converted += 1;
+            }
+            writer.flush()?;
+        }
+        SequenceFormat::Gff3 => {
+            // The first record is read before creating the output, so files that are not
+            // GenBank leave none behind.
+            let mut records = GenBankReader::open(file)?;
+            let first: Option<GenBankRecord> = records.next().transpose()?;
+            let mut writer: Output = open_output(out_file, io.compression)?;
+            writer.write_all(b"##gff-version 3\n")?;
+            for record in first.into_iter().map(Ok).chain(records) {
+                writer.write_all(record?.gff3().as_bytes())?;
+                converted += 1;
+            }
+            writer.finish()?;
+        }
+    }
+    let result: String = format!(
+        "Converted {} records to {}. Read {}. Write {}",
+        converted,
+        match format {
+            SequenceFormat::Fasta => "fasta",
+            SequenceFormat::TwoBit => ".2bit",
+            SequenceFormat::Gff3 => "GFF3",
+        },
+        display_name(file, "standard input"),
+        display_name(out_file, "standard output")
+    );
+    Ok(result)
+}
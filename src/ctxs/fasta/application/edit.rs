@@ -1,45 +1,130 @@
 //! Fasta editing utilities
 use anyhow::Result;
-use std::path::PathBuf;
-use textwrap::fill;
+use std::path::Path;
 
-use crate::ctxs::fasta::domain::fasta::Fasta;
+use crate::{
+    ctxs::fasta::{
+        domain::{
+            fasta::{Case, Fasta, FastaSequence, MaskMode},
+            trim::Trimmer,
+        },
+        infrastructure::{twobit::TwoBitFile, writer::FastaWriter},
+    },
+    shared::infrastructure::io::{display_name, IoOptions},
+};
 
 use super::view;
 
-/// Reads a file, parses it as `Fasta` and cuts the sequence from given indices. This function will
-/// write the resulting cut sequence to a given file.
-pub fn cut(input_file: PathBuf, output_file: PathBuf, start: usize, end: usize) -> Result<String> {
-    let fasta: Fasta = match view::cat_f(&input_file) {
-        Ok(contents) => contents,
-        Err(e) => panic!("Could not read file!. Error {}", e),
-    };
-    let cut_fasta: Fasta = fasta.cut(start, end);
-    cut_fasta.save(&output_file)?;
+/// Reads a file, parses it as fasta or fastq records and cuts every sequence from given indices. This
+/// function will write the resulting cut sequences to a given file. From a .2bit file, only the
+/// bases cut are read.
+pub fn cut(
+    input_file: Option<&Path>,
+    output_file: Option<&Path>,
+    start: usize,
+    end: usize,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(output_file, io.compression)?;
+    match input_file.filter(|file| TwoBitFile::sniff(file)) {
+        Some(file) => {
+            let mut genome = TwoBitFile::open(file)?;
+            for name in genome.names() {
+                let (first, last) = match start <= end && end <= genome.length(&name)? {
+                    true => (start, end),
+                    false => (0, 0),
+                };
+                let bases: FastaSequence = genome.fetch(&name, first, last)?;
+                writer.write(Fasta::cut_from(&name, bases, start, end))?;
+            }
+        }
+        None => {
+            for record in view::cat_f(input_file, io)? {
+                writer.write(record?.cut(start, end))?;
+            }
+        }
+    }
+    writer.flush()?;
     let result: String = format!(
         "Cut from {} to {}. Read {}. Write {}",
         start,
         end,
-        input_file.display(),
-        output_file.display()
+        display_name(input_file, "standard input"),
+        display_name(output_file, "standard output")
     );
     Ok(result)
 }
 
-/// Formats a .fasta file, represented with the `Fasta` struct.
-pub fn format(file: PathBuf, is_upper: bool, out_file: PathBuf) -> Result<String> {
-    let fasta: Fasta = match view::cat_f(&file) {
-        Ok(contents) => contents,
-        Err(e) => panic!("Could not read file. Error {}", e),
-    };
+/// Formats a .fasta or .fastq file, one record at a time, writing sequences in the given `case`.
+/// Fasta sequences are wrapped at 60 bases; fastq ones are kept in a single line, as the format
+/// expects.
+pub fn format(
+    file: Option<&Path>,
+    case: Case,
+    out_file: Option<&Path>,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(out_file, io.compression)?;
+    for record in view::cat_f(file, io)? {
+        writer.write(record?.with_case(case))?;
+    }
+    writer.flush()?;
     let result: String = String::from("Format OK!");
-    let strip_seq: String = fasta.sequence.to_string().replace('\n', "");
-    let seq: String = fill(&strip_seq, 60);
-    let fmt_fasta: Fasta = Fasta::from((fasta.header.to_string(), seq));
-    let final_fasta: Fasta = match is_upper {
-        true => fmt_fasta.uppercase(),
-        false => fmt_fasta,
+    Ok(result)
+}
+
+/// Removes the soft-masking of every record of a .fasta or .fastq file, replacing masked bases with
+/// `N` or uppercasing them as `mode` tells.
+pub fn mask(
+    file: Option<&Path>,
+    out_file: Option<&Path>,
+    mode: MaskMode,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(out_file, io.compression)?;
+    let mut masked: usize = 0;
+    for record in view::cat_f(file, io)? {
+        let record = record?;
+        masked += record.sequence().masked();
+        writer.write(record.mask(mode))?;
+    }
+    writer.flush()?;
+    let result: String = format!("Unmasked {} soft-masked bases", masked);
+    Ok(result)
+}
+
+/// Trims every read of a fasta or fastq file, and writes the ones long enough to `out_file`, as
+/// fasta if `to_fasta` is set. Returns a summary of the kept and dropped reads.
+pub fn trim(
+    file: Option<&Path>,
+    out_file: Option<&Path>,
+    trimmer: &Trimmer,
+    to_fasta: bool,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(out_file, io.compression)?;
+    let mut total: usize = 0;
+    let mut kept: usize = 0;
+    for record in view::cat_f(file, io)? {
+        total += 1;
+        let Some(trimmed) = trimmer.trim(&record?) else {
+            continue;
+        };
+        kept += 1;
+        match to_fasta {
+            true => writer.write(Fasta::from(trimmed))?,
+            false => writer.write(trimmed)?,
+        }
+    }
+    writer.flush()?;
+    let dropped: usize = total - kept;
+    let kept_pct: f64 = match total {
+        0 => 0_f64,
+        _ => (kept as f64 * 100_f64) / total as f64,
     };
-    final_fasta.save(&out_file)?;
+    let result: String = format!(
+        "Reads: {}. Kept: {} ({:.2}%). Dropped: {}",
+        total, kept, kept_pct, dropped
+    );
     Ok(result)
 }
@@ -0,0 +1,162 @@
+//! Fasta indexing and random access
+use anyhow::{Context, Result};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ctxs::fasta::{
+        domain::{
+            fasta::{Fasta, FastaSequence},
+            region::Region,
+        },
+        infrastructure::{index::FastaIndex, twobit::TwoBitFile, writer::FastaWriter},
+    },
+    shared::infrastructure::{
+        compression::{BgzfReader, Compression, GziIndex},
+        io::IoOptions,
+    },
+};
+
+/// Indexes a fasta file, and writes the index next to it, as `<file>.fai`. BGZF files get their
+/// block index too, as `<file>.gzi`.
+pub fn faidx(file: &Path) -> Result<String> {
+    let index: FastaIndex = FastaIndex::build_from(file)?;
+    let index_file: PathBuf = FastaIndex::path_for(file);
+    index.save(&index_file)?;
+    let mut result: String = format!(
+        "Indexed {} sequences. Write {}",
+        index.entries.len(),
+        index_file.display()
+    );
+    if Compression::of_file(file) == Compression::Bgzf {
+        let gzi_file: PathBuf = GziIndex::path_for(file);
+        save_gzi(&build_gzi(file)?, &gzi_file)?;
+        result.push_str(&format!(" and {}", gzi_file.display()));
+    }
+    Ok(result)
+}
+
+/// Indexes the blocks of a BGZF file.
+fn build_gzi(file: &Path) -> Result<GziIndex> {
+    let reader = BufReader::new(
+        File::open(file).with_context(|| format!("Could not open {}", file.display()))?,
+    );
+    GziIndex::build(reader).with_context(|| format!("Could not read {}", file.display()))
+}
+
+fn save_gzi(index: &GziIndex, file: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(
+        File::create(file).with_context(|| format!("Could not create {}", file.display()))?,
+    );
+    index.write_to(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The block index of a BGZF file, read from `<file>.gzi`, or built and saved there if missing.
+fn load_gzi(file: &Path) -> Result<GziIndex> {
+    let gzi_file: PathBuf = GziIndex::path_for(file);
+    match gzi_file.exists() {
+        true => {
+            let reader = BufReader::new(
+                File::open(&gzi_file)
+                    .with_context(|| format!("Could not open {}", gzi_file.display()))?,
+            );
+            GziIndex::read_from(reader)
+                .with_context(|| format!("Malformed index {}", gzi_file.display()))
+        }
+        false => {
+            let index: GziIndex = build_gzi(file)?;
+            save_gzi(&index, &gzi_file)?;
+            Ok(index)
+        }
+    }
+}
+
+/// Extracts `regions` from an indexed fasta file, seeking straight to their bases. The file is
+/// indexed first if it has no index yet. BGZF files are read with their `.gzi` index too, and
+/// .2bit files carry their own index, and need no other. Regions are parsed as `Region::parse`
+/// does, with the names of the sequences of the file.
+pub fn region(
+    file: &Path,
+    regions: &[String],
+    out_file: Option<&Path>,
+    io: &IoOptions,
+) -> Result<String> {
+    if TwoBitFile::sniff(file) {
+        return two_bit_region(file, regions, out_file, io);
+    }
+    let index_file: PathBuf = FastaIndex::path_for(file);
+    let index: FastaIndex = match index_file.exists() {
+        true => FastaIndex::load(&index_file)?,
+        false => {
+            let index: FastaIndex = FastaIndex::build_from(file)?;
+            index.save(&index_file)?;
+            index
+        }
+    };
+    let names: HashSet<&str> = index
+        .entries
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    let regions: Vec<Region> = parse_regions(regions, &names)?;
+    let opened: File =
+        File::open(file).with_context(|| format!("Could not open {}", file.display()))?;
+    match Compression::of_file(file) {
+        Compression::Bgzf => {
+            let mut reader = BgzfReader::new(BufReader::new(opened), load_gzi(file)?);
+            fetch_regions(&index, &mut reader, &regions, out_file, io)
+        }
+        _ => fetch_regions(&index, &mut BufReader::new(opened), &regions, out_file, io),
+    }
+}
+
+fn parse_regions(regions: &[String], names: &HashSet<&str>) -> Result<Vec<Region>> {
+    let regions: Vec<Region> = regions
+        .iter()
+        .map(|region| Region::parse(region, |name| names.contains(name)))
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(regions)
+}
+
+/// Writes the bases of `regions`, read from `reader` with `index`, to `out_file`.
+fn fetch_regions<R: Read + Seek>(
+    index: &FastaIndex,
+    reader: &mut R,
+    regions: &[Region],
+    out_file: Option<&Path>,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(out_file, io.compression)?;
+    for region in regions {
+        let sequence: String = index.fetch(reader, region)?;
+        writer.write(Fasta::from((region.to_string(), sequence)))?;
+    }
+    writer.flush()?;
+    Ok(String::new())
+}
+
+/// Extracts `regions` from a .2bit file, reading only the bytes that hold their bases.
+fn two_bit_region(
+    file: &Path,
+    regions: &[String],
+    out_file: Option<&Path>,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut genome = TwoBitFile::open(file)?;
+    let names: Vec<String> = genome.names();
+    let regions: Vec<Region> = parse_regions(regions, &names.iter().map(String::as_str).collect())?;
+    let mut writer = FastaWriter::create(out_file, io.compression)?;
+    for region in &regions {
+        let (start, end) = region.range(genome.length(&region.name)?);
+        let sequence: FastaSequence = genome.fetch(&region.name, start, end)?;
+        writer.write(Fasta::from((region.to_string(), sequence)))?;
+    }
+    writer.flush()?;
+    Ok(String::new())
+}
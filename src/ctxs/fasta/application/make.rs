@@ -1,76 +1,161 @@
 use anyhow::Result;
+use rand::{rngs::StdRng, SeedableRng};
 use rayon::prelude::*;
-use std::path::PathBuf;
+use std::path::Path;
 
 use crate::{
     ctxs::{
-        fasta::domain::fasta::{Fasta, DNA_BASES, RNA_BASES},
-        protein::domain::protein::Protein,
+        codon::domain::usage::CodonUsage,
+        fasta::{
+            domain::{
+                errors::FastaError,
+                fasta::{Fasta, Strand, DNA_BASES, RNA_BASES},
+                record::SequenceRecord,
+            },
+            infrastructure::writer::FastaWriter,
+        },
+        protein::domain::{
+            backtranslation::{BackTranslationMode, BackTranslator},
+            frame::Frame,
+            protein::{Protein, TranslationOptions},
+        },
+    },
+    shared::{
+        infrastructure::{
+            io::{display_name, IoOptions},
+            ProteinLayout,
+        },
+        utils::select_rnd_str,
     },
-    shared::utils::select_rnd_str,
 };
 
+use super::{codons, view};
+
 pub enum FastaAllowedOperations {
     Reverse,
     Complement,
     Both,
+    Transcribe(Strand),
+    ReverseTranscribe(Strand),
 }
 
-/// Generates a RNA or DNA chain of N `bases` and saves it to `file`.
+/// Generates a RNA or DNA chain of N `bases` and saves it to `file`, or to the standard output if
+/// `file` is `-` or missing.
 ///
 /// The String returned is not actually the sequence, but a info message about how the generation went.
-pub fn generate(bases: usize, file: PathBuf, is_rna: bool) -> Result<String> {
+pub fn generate(bases: usize, file: Option<&Path>, is_rna: bool, io: &IoOptions) -> Result<String> {
     let atcg: [&str; 4] = match is_rna {
         true => RNA_BASES,
         false => DNA_BASES,
     };
     let header: String = format!("randomly generated sequence of {} bases", bases);
-    let sequence: String = match generate_bases(bases, atcg) {
-        Ok(seq) => seq.join(""),
-        Err(e) => return Err(anyhow::anyhow!("Could not generate bases. Error: {:?}", e)),
-    };
+    let sequence: String = generate_bases(bases, atcg).join("");
     let fsta = Fasta::from((header, sequence.clone()));
-    fsta.save(&file)?;
+    fsta.save(file, io.compression)?;
     let result: String = format!(
         "Generated file \"{}\" with {} bases",
-        file.display(),
+        display_name(file, "standard output"),
         sequence.len()
     );
     Ok(result)
 }
 
 /// Generates a random string chain given four different slices. Multithreaded if num_threads is bigger than one I guess
-fn generate_bases(num_bases: usize, bases: [&str; 4]) -> Result<Vec<String>> {
+fn generate_bases(num_bases: usize, bases: [&str; 4]) -> Vec<String> {
     let base_list: Vec<String> = bases.iter().map(|b| b.to_string()).collect();
-    let ray_seq: Vec<_> = (0..=num_bases)
+    (0..=num_bases)
         .into_par_iter()
         .map(|_| select_rnd_str(&base_list))
-        .collect();
-    Ok(ray_seq)
+        .collect()
 }
 
+/// Applies `operation` to every record of `file`, and writes the results to `ofile`. Both default
+/// to the standard streams.
 pub fn operate_on_chain(
-    file: PathBuf,
-    ofile: Option<PathBuf>,
+    file: Option<&Path>,
+    ofile: Option<&Path>,
     operation: FastaAllowedOperations,
+    io: &IoOptions,
 ) -> Result<String> {
-    let original_fasta: Fasta = crate::view::cat_f(&file)?;
-    let operated_fasta = match operation {
-        FastaAllowedOperations::Reverse => original_fasta.reverse(),
-        FastaAllowedOperations::Complement => original_fasta.complement(),
-        FastaAllowedOperations::Both => original_fasta.reverse().complement(),
-    };
-    if let Some(file) = ofile {
-        operated_fasta.save(&file)?
+    let mut writer = FastaWriter::create(ofile, io.compression)?;
+    for record in view::cat_f(file, io)? {
+        let original_record: SequenceRecord = record?;
+        let operated_record: SequenceRecord = match operation {
+            FastaAllowedOperations::Reverse => original_record.reverse(),
+            FastaAllowedOperations::Complement => original_record.complement(),
+            FastaAllowedOperations::Both => original_record.reverse_complement(),
+            FastaAllowedOperations::Transcribe(strand) => original_record.transcribe(strand),
+            FastaAllowedOperations::ReverseTranscribe(strand) => {
+                original_record.reverse_transcribe(strand)
+            }
+        };
+        writer.write(operated_record)?;
     }
+    writer.flush()?;
     Ok("".to_string())
 }
 
-pub fn to_aacids(file: PathBuf, ofile: Option<PathBuf>) -> Result<String, anyhow::Error> {
-    let fasta: Fasta = crate::view::cat_f(&file)?;
-    let aas: Protein = Protein::from(fasta);
-    if let Some(file) = ofile {
-        aas.save(&file)?
+/// Translates every record of `file` to aminoacids as `options` tell, and writes them to `ofile`,
+/// laid out as `layout` tells. Both default to the standard streams. With no `frames`, every record
+/// is translated in frame +1; else, one protein is written for every frame of every record, its
+/// header tagged with the frame.
+pub fn to_aacids(
+    file: Option<&Path>,
+    ofile: Option<&Path>,
+    options: &TranslationOptions,
+    frames: &[Frame],
+    layout: ProteinLayout,
+    io: &IoOptions,
+) -> Result<String, anyhow::Error> {
+    let mut writer = FastaWriter::create(ofile, io.compression)?;
+    for record in view::cat_f(file, io)? {
+        let fasta: Fasta = Fasta::from(record?);
+        if fasta.sequence.alphabet().is_protein() {
+            return Err(FastaError::NotNucleotides(fasta.header.as_str().to_string()).into());
+        }
+        match frames.is_empty() {
+            true => writer.write((Protein::translate(&fasta, options), layout))?,
+            false => {
+                for protein in Protein::translate_frames(&fasta, frames, options) {
+                    writer.write((protein, layout))?;
+                }
+            }
+        }
     }
+    writer.flush()?;
     Ok("".to_string())
 }
+
+/// Back-translates every protein of `file` to DNA, choosing codons as `mode` tells with the usage
+/// table in `usage`, and writes them to `ofile`. Both default to the standard streams. Codons are
+/// chosen at random from `seed`, or from entropy if missing.
+pub fn backtranslate(
+    file: Option<&Path>,
+    ofile: Option<&Path>,
+    mode: BackTranslationMode,
+    usage: Option<&Path>,
+    seed: Option<u64>,
+    io: &IoOptions,
+) -> Result<String> {
+    let usage: CodonUsage = match usage {
+        Some(usage) => codons::read_usage(usage)?,
+        None if mode == BackTranslationMode::MostFrequent => {
+            return Err(FastaError::MissingCodonUsage.into())
+        }
+        None => CodonUsage::default(),
+    };
+    let translator: BackTranslator = BackTranslator { mode, usage };
+    let mut rng: StdRng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut writer = FastaWriter::create(ofile, io.compression)?;
+    let mut count: usize = 0;
+    for record in view::cat_f(file, io)? {
+        let protein: Protein = Protein::read(&Fasta::from(record?));
+        writer.write(translator.backtranslate(&protein, &mut rng))?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(format!("Back-translated {} proteins", count))
+}
@@ -1,3 +1,8 @@
+pub mod cds;
+pub mod codons;
+pub mod convert;
 pub mod edit;
+pub mod index;
 pub mod make;
+pub mod orfs;
 pub mod view;
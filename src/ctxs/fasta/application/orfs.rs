@@ -0,0 +1,62 @@
+//! Open reading frame search
+use anyhow::Result;
+use std::{io::Write, path::Path};
+
+use crate::{
+    ctxs::{
+        fasta::{domain::fasta::Fasta, infrastructure::writer::FastaWriter},
+        protein::domain::orf::{CoordinateFormat, Orf, OrfFinder},
+    },
+    shared::infrastructure::{
+        compression::Finish,
+        io::{open_output, IoOptions, Output},
+    },
+};
+
+use super::view;
+
+/// Finds the open reading frames of every record of `file`, and writes them to `ofile` as
+/// nucleotides, or as proteins if `as_protein`. Both default to the standard streams. The
+/// coordinates of every frame are written to `coordinates`, if given, in its format.
+pub fn find_orfs(
+    file: Option<&Path>,
+    ofile: Option<&Path>,
+    finder: &OrfFinder,
+    as_protein: bool,
+    coordinates: Option<(&Path, CoordinateFormat)>,
+    io: &IoOptions,
+) -> Result<String> {
+    let mut writer = FastaWriter::create(ofile, io.compression)?;
+    let mut table: Option<(Output, CoordinateFormat)> = match coordinates {
+        Some((path, format)) => {
+            let mut table: Output = open_output(Some(path), io.compression)?;
+            if let Some(header) = format.header() {
+                table.write_all(header.as_bytes())?;
+            }
+            Some((table, format))
+        }
+        None => None,
+    };
+    let mut records: usize = 0;
+    let mut found: usize = 0;
+    for record in view::cat_f(file, io)? {
+        records += 1;
+        let orfs: Vec<Orf> = finder.find(&Fasta::from(record?));
+        found += orfs.len();
+        for orf in orfs {
+            if let Some((table, format)) = table.as_mut() {
+                table.write_all(orf.coordinates(*format).as_bytes())?;
+            }
+            match as_protein {
+                true => writer.write(orf.protein(&finder.code))?,
+                false => writer.write(orf.bases)?,
+            }
+        }
+    }
+    writer.flush()?;
+    if let Some((table, _)) = table.as_mut() {
+        table.finish()?;
+    }
+    let result: String = format!("Found {} open reading frames in {} records", found, records);
+    Ok(result)
+}
@@ -1,98 +1,195 @@
-//! Fasta file reading using `Fasta` struct.
+//! Fasta and fastq file reading using `SequenceRecord`.
 
-use std::{
-    collections::BTreeMap,
-    fmt::Write,
-    fs::File,
-    io::{prelude::Read, BufReader},
-    path::Path,
+use std::{collections::BTreeMap, fmt::Write, io::BufRead, path::Path};
+
+use crate::{
+    ctxs::{
+        fasta::{
+            domain::{
+                alphabet::{SequenceAlphabet, ValidationPolicy, Validator},
+                errors::FastaError,
+                fasta::Fasta,
+                packed::BaseCounts,
+                record::SequenceRecord,
+            },
+            infrastructure::{reader::RecordReader, writer::FastaWriter},
+        },
+        protein::domain::{
+            analysis::{ProteinAnalysis, STABILITY_THRESHOLD},
+            protein::Protein,
+        },
+    },
+    shared::infrastructure::io::IoOptions,
 };
 
-use crate::ctxs::fasta::domain::fasta::Fasta;
+/// A wrapper function around `cat_f()` that writes every record to the standard output.
+pub fn cat(file: Option<&Path>, io: &IoOptions) -> anyhow::Result<String, anyhow::Error> {
+    let mut writer = FastaWriter::create(None, None)?;
+    for record in cat_f(file, io)? {
+        writer.write(record?)?;
+    }
+    writer.flush()?;
+    Ok(String::new())
+}
 
-/// Reads files contents and returns them, independently of type.
-macro_rules! read2str {
-    ($path:ident) => {{
-        let file: File = File::open($path)?;
-        let mut reader: BufReader<File> = BufReader::new(file);
-        let mut contents: String = String::new();
-        reader.read_to_string(&mut contents)?;
-        contents
-    }};
+/// Opens a fasta or fastq file as a stream of records. The standard input is read if `file` is `-`
+/// or missing. With a strict policy, reading fails on the first illegal character.
+pub fn cat_f(
+    file: Option<&Path>,
+    io: &IoOptions,
+) -> Result<RecordReader<Box<dyn BufRead>>, anyhow::Error> {
+    let validator: Option<Validator> = match io.policy {
+        ValidationPolicy::Strict => Some(Validator::new(io.alphabet, ValidationPolicy::Strict)),
+        ValidationPolicy::Lenient => None,
+    };
+    Ok(RecordReader::open(file, validator)?.with_alphabet(io.alphabet))
 }
 
-/// A wrapper function around `cat_f()` that returns a string.
-pub fn cat(file: &Path) -> anyhow::Result<String, anyhow::Error> {
-    /* if let Ok(fasta) = cat_f(file) {
-        return Ok(fasta.to_string())
+/// Checks every sequence of a file against the alphabet in `io`, or against DNA and RNA if missing,
+/// and reports the record, line and column of every illegal character. With a strict policy, fails
+/// on the first one instead.
+pub fn validate(file: Option<&Path>, io: &IoOptions) -> Result<String, anyhow::Error> {
+    let validator: Validator = Validator::new(io.alphabet, io.policy);
+    let mut records: RecordReader<Box<dyn BufRead>> = RecordReader::open(file, Some(validator))?;
+    let mut result: String = String::new();
+    let mut record_count: usize = 0;
+    let mut issue_count: usize = 0;
+    while let Some(record) = records.next() {
+        record?;
+        record_count += 1;
+        let issues: Vec<FastaError> = records.take_issues();
+        issue_count += issues.len();
+        for issue in issues {
+            let _ = writeln!(result, "{}", issue);
+        }
     }
-    if let Err(fasta) = cat_f(file) {
-        return Err(fasta)
-    } else {
-
-    } */
-    Ok(cat_f(file)
-        .unwrap_or_else(|_| Fasta::from(("Could not open file!", "")))
-        .to_string())
+    let _ = write!(
+        result,
+        "Checked {} records against {}. Found {} illegal characters",
+        record_count,
+        validator.alphabet_name(),
+        issue_count
+    );
+    Ok(result)
 }
 
-/// Parses a file to `Fasta` struct, and returns it.
-pub fn cat_f(file: &Path) -> Result<Fasta, anyhow::Error> {
-    let contents: String = read2str!(file);
-    let reader_lines: std::str::Lines = contents.lines();
-    let reader_lines_copy: std::str::Lines = reader_lines.clone();
-    let header: String = reader_lines
-        .into_iter()
-        .filter(|line| line.starts_with('>'))
-        .map(|line| line.trim_start_matches("> ").to_owned())
-        .collect();
-    let sequence: String = reader_lines_copy
-        .into_iter()
-        .filter(|line| !(line.starts_with('>')))
-        .collect();
-    if sequence.is_empty() {
-        return Err(anyhow::anyhow!("Sequence is empty.").context("The file has an empty sequence"));
+/// Analizes the contents of every sequence in a file: as aminoacids if its alphabet is protein, and
+/// as nucleotides otherwise.
+pub fn analize(file: Option<&Path>, io: &IoOptions) -> Result<String, anyhow::Error> {
+    let records: RecordReader<Box<dyn BufRead>> = cat_f(file, io)?;
+    let mut result: String = String::new();
+    for record in records {
+        let record: SequenceRecord = record?;
+        let report: String = match record.sequence().alphabet().is_protein() {
+            true => analize_protein(
+                &Protein::read(&Fasta::from(record.clone())),
+                record.sequence().alphabet(),
+            ),
+            false => analize_record(&record),
+        };
+        let _ = write!(result, "{}{}", record.header(), report);
     }
 
-    let fasta: Fasta = Fasta::from((header, sequence));
-
-    Ok(fasta)
+    Ok(result)
 }
 
-/// Analizes the contents of a DNA or RNA sequence.
-pub fn analize(file: &Path) -> Result<String, anyhow::Error> {
-    let fasta: Fasta = match cat_f(file) {
-        Ok(seq) => seq,
-        Err(e) => return Err(anyhow::anyhow!("Can't read file. Error: {}", e)),
-    };
-    let t_chars: usize = fasta.sequence.get_chars().count();
-    let c_count: usize = fasta.sequence.get_chars().filter(|&c| c == 'c').count();
-    let g_count: usize = fasta.sequence.get_chars().filter(|&c| c == 'g').count();
-    let a_count: usize = fasta.sequence.get_chars().filter(|&c| c == 'a').count();
-    let t_count: usize = fasta
-        .sequence
-        .get_chars()
-        .filter(|&c| (c == 't' || c == 'u'))
-        .count();
+/// Analizes the contents of a single DNA or RNA sequence, and its qualities for fastq records.
+/// Soft-masked (lowercase) bases count as any other.
+fn analize_record(record: &SequenceRecord) -> String {
+    let sequence = record.sequence();
+    let counts: BaseCounts = sequence.base_counts();
+    let t_chars: usize = counts.total();
+    let (a_count, c_count, g_count, t_count) = (counts.a, counts.c, counts.g, counts.t);
+    let n_count: usize = counts.n;
+    let ambiguous_count: usize = counts.n + counts.ambiguous;
     let gc_pct: f64 = ((g_count + c_count) as f64 * 100_f64) / t_chars as f64;
+    let ambiguous_pct: f64 = (ambiguous_count as f64 * 100_f64) / t_chars as f64;
     let at_pct: f64 = ((a_count + t_count) as f64 * 100_f64) / t_chars as f64;
 
     let data: BTreeMap<String, String> = {
         let mut hm: BTreeMap<String, String> = BTreeMap::new();
         hm.insert("Nucleotides".to_string(), t_chars.to_string());
+        insert_alphabet(&mut hm, sequence.alphabet());
         hm.insert("AT Count".to_string(), (a_count + t_count).to_string());
         hm.insert("AT Percent".to_string(), at_pct.to_string());
         hm.insert("GC Count".to_string(), (c_count + g_count).to_string());
         hm.insert("GC Percent".to_string(), gc_pct.to_string());
+        hm.insert("N Count".to_string(), n_count.to_string());
+        hm.insert("Ambiguous Count".to_string(), ambiguous_count.to_string());
+        hm.insert("Ambiguous Percent".to_string(), ambiguous_pct.to_string());
+        if let Some(quality) = record.quality() {
+            let mean: f64 = quality.mean().unwrap_or(0_f64);
+            hm.insert("Mean Quality".to_string(), mean.to_string());
+            let q30: usize = quality.scores().iter().filter(|&&q| q >= 30).count();
+            let q30_pct: f64 = (q30 as f64 * 100_f64) / quality.len() as f64;
+            hm.insert("Q30 Percent".to_string(), q30_pct.to_string());
+        }
         hm
     };
 
-    let result: String = data
-        .into_iter()
+    format_report(data)
+}
+
+/// Analizes the aminoacids of a protein: its length, composition and physicochemical properties.
+fn analize_protein(protein: &Protein, alphabet: SequenceAlphabet) -> String {
+    let analysis: ProteinAnalysis = ProteinAnalysis::from(protein);
+    let length: usize = analysis.length();
+    let (reduced, cystines) = analysis.extinction_coefficients();
+    let instability: f64 = analysis.instability_index();
+
+    let data: BTreeMap<String, String> = {
+        let mut hm: BTreeMap<String, String> = BTreeMap::new();
+        hm.insert("Aminoacids".to_string(), length.to_string());
+        insert_alphabet(&mut hm, alphabet);
+        for (aminoacid, count) in analysis.composition() {
+            let pct: f64 = (count as f64 * 100_f64) / length as f64;
+            hm.insert(
+                format!("Composition {}", aminoacid.to_ascii_uppercase()),
+                format!("{} ({:.2}%)", count, pct),
+            );
+        }
+        hm.insert(
+            "Molecular Weight".to_string(),
+            format!("{:.2}", analysis.molecular_weight()),
+        );
+        hm.insert(
+            "Isoelectric Point".to_string(),
+            analysis.isoelectric_point().to_string(),
+        );
+        hm.insert("Extinction Coefficient".to_string(), reduced.to_string());
+        hm.insert(
+            "Extinction Coefficient Cystines".to_string(),
+            cystines.to_string(),
+        );
+        hm.insert("GRAVY".to_string(), format!("{:.3}", analysis.gravy()));
+        hm.insert(
+            "Instability Index".to_string(),
+            format!("{:.2}", instability),
+        );
+        hm.insert(
+            "Stable".to_string(),
+            (instability < STABILITY_THRESHOLD).to_string(),
+        );
+        hm
+    };
+
+    format_report(data)
+}
+
+/// Adds the alphabet of a sequence, and how sure it is, to its analysis.
+fn insert_alphabet(data: &mut BTreeMap<String, String>, alphabet: SequenceAlphabet) {
+    data.insert("Alphabet".to_string(), alphabet.to_string());
+    data.insert(
+        "Alphabet Confidence".to_string(),
+        format!("{:.2}", alphabet.confidence),
+    );
+}
+
+/// Writes an analysis as `key:\tvalue` lines.
+fn format_report(data: BTreeMap<String, String>) -> String {
+    data.into_iter()
         .fold(String::new(), |mut output, (key, value)| {
             let _ = writeln!(output, "{}:\t{}", key, value);
             output
-        });
-
-    Ok(result)
+        })
 }
@@ -0,0 +1,252 @@
+//! Sequence alphabets, and validation of sequences against them
+use core::fmt;
+use std::fmt::Display;
+
+use clap::ValueEnum;
+
+/// IUPAC nucleotide ambiguity codes, and the gap
+const AMBIGUOUS_NUCLEOTIDES: &str = "ryswkmbdhvn-";
+/// Aminoacids, ambiguity codes (B, Z, J, X), selenocysteine, pyrrolysine, stop and gap
+const AMINOACIDS: &str = "acdefghiklmnpqrstvwybzjxuo*-";
+/// Letters found in proteins but in no nucleotide alphabet, ambiguity codes included
+const PROTEIN_ONLY: &str = "efijlopqz*";
+/// Share of the letters of a sequence that must be `A`, `C`, `G`, `T`, `U` or `N` for it to be
+/// taken as nucleotides
+const NUCLEOTIDE_SHARE: f64 = 0.9;
+
+/// Kinds of sequence
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Alphabet {
+    Dna,
+    Rna,
+    Protein,
+}
+
+impl Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alphabet::Dna => write!(f, "DNA"),
+            Alphabet::Rna => write!(f, "RNA"),
+            Alphabet::Protein => write!(f, "protein"),
+        }
+    }
+}
+
+impl Alphabet {
+    /// Whether `base` belongs to the alphabet, in any case.
+    pub fn is_valid(&self, base: char) -> bool {
+        let base: char = base.to_ascii_lowercase();
+        match self {
+            Alphabet::Dna => "acgt".contains(base) || is_ambiguous_nucleotide(base),
+            Alphabet::Rna => "acgu".contains(base) || is_ambiguous_nucleotide(base),
+            Alphabet::Protein => AMINOACIDS.contains(base),
+        }
+    }
+}
+
+/// Alphabet of a sequence, given or guessed from its letters, and how sure it is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SequenceAlphabet {
+    /// The alphabet, `None` if unknown
+    pub alphabet: Option<Alphabet>,
+    /// How sure the alphabet is, from 0 to 1. Given alphabets are sure.
+    pub confidence: f64,
+}
+
+impl Default for SequenceAlphabet {
+    fn default() -> Self {
+        SequenceAlphabet {
+            alphabet: None,
+            confidence: 0_f64,
+        }
+    }
+}
+
+impl Display for SequenceAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.alphabet {
+            Some(alphabet) => write!(f, "{}", alphabet),
+            None => write!(f, "unknown"),
+        }
+    }
+}
+
+impl From<Alphabet> for SequenceAlphabet {
+    fn from(alphabet: Alphabet) -> Self {
+        SequenceAlphabet {
+            alphabet: Some(alphabet),
+            confidence: 1_f64,
+        }
+    }
+}
+
+impl SequenceAlphabet {
+    /// Guesses the alphabet of a sequence from its letters:
+    ///  - Protein if it has letters no nucleotide alphabet has, and less than 90% of its letters are
+    ///    `A`, `C`, `G`, `T`, `U` or `N`. As sure as the share of its letters that are not those.
+    ///  - Protein too if none of its letters are those, like `MKRHW`, though all of them are IUPAC
+    ///    nucleotide codes. Half as sure.
+    ///  - Else RNA if it has more `U` than `T`, and DNA if not. As sure as the share of its letters
+    ///    that are nucleotides, times the share of `T` (or `U`) among both. Half as sure if there
+    ///    are neither.
+    ///  - Unknown if it has no letters.
+    pub fn detect(sequence: &str) -> Self {
+        let letters: Vec<char> = sequence
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic() || *c == '*')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        if letters.is_empty() {
+            return SequenceAlphabet::default();
+        }
+        let count = |bases: &str| letters.iter().filter(|&&c| bases.contains(c)).count() as f64;
+        let share: f64 = count("acgtun") / letters.len() as f64;
+        let has_protein_letters: bool = letters.iter().any(|&c| PROTEIN_ONLY.contains(c));
+        if has_protein_letters && share < NUCLEOTIDE_SHARE {
+            return SequenceAlphabet {
+                alphabet: Some(Alphabet::Protein),
+                confidence: 1_f64 - share,
+            };
+        }
+        if share == 0_f64 {
+            return SequenceAlphabet {
+                alphabet: Some(Alphabet::Protein),
+                confidence: 0.5,
+            };
+        }
+        let (thymines, uracils): (f64, f64) = (count("t"), count("u"));
+        let (alphabet, majority): (Alphabet, f64) = match (thymines, uracils) {
+            (t, u) if t + u == 0_f64 => (Alphabet::Dna, 0.5),
+            (t, u) if u > t => (Alphabet::Rna, u / (t + u)),
+            (t, u) => (Alphabet::Dna, t / (t + u)),
+        };
+        SequenceAlphabet {
+            alphabet: Some(alphabet),
+            confidence: share * majority,
+        }
+    }
+
+    /// Whether the sequence is RNA
+    pub fn is_rna(&self) -> bool {
+        self.alphabet == Some(Alphabet::Rna)
+    }
+
+    /// Whether the sequence is a protein
+    pub fn is_protein(&self) -> bool {
+        self.alphabet == Some(Alphabet::Protein)
+    }
+}
+
+fn is_ambiguous_nucleotide(base: char) -> bool {
+    AMBIGUOUS_NUCLEOTIDES.contains(base)
+}
+
+/// What to do with characters outside of the alphabet
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ValidationPolicy {
+    /// Fail on the first illegal character
+    Strict,
+    /// Keep illegal characters as they are
+    #[default]
+    Lenient,
+}
+
+/// Checks sequences against an alphabet. Without an alphabet, anything that is either DNA or RNA
+/// is accepted, unless readers guess a record to be a protein.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Validator {
+    pub alphabet: Option<Alphabet>,
+    pub policy: ValidationPolicy,
+}
+
+impl Validator {
+    pub fn new(alphabet: Option<Alphabet>, policy: ValidationPolicy) -> Self {
+        Validator { alphabet, policy }
+    }
+
+    /// Whether `base` is legal for the alphabet.
+    pub fn is_valid(&self, base: char) -> bool {
+        match self.alphabet {
+            Some(alphabet) => alphabet.is_valid(base),
+            None => Alphabet::Dna.is_valid(base) || Alphabet::Rna.is_valid(base),
+        }
+    }
+
+    /// Name of the alphabet, for messages.
+    pub fn alphabet_name(&self) -> String {
+        match self.alphabet {
+            Some(alphabet) => alphabet.to_string(),
+            None => "DNA or RNA".to_string(),
+        }
+    }
+
+    /// Illegal characters of a piece of sequence, with their 0-based index.
+    pub fn illegal_characters<'a>(
+        &'a self,
+        sequence: &'a str,
+    ) -> impl Iterator<Item = (usize, char)> + 'a {
+        sequence
+            .chars()
+            .enumerate()
+            .filter(|(_, base)| !self.is_valid(*base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::domain::alphabet::{
+        Alphabet, SequenceAlphabet, ValidationPolicy, Validator,
+    };
+
+    #[test]
+    fn validate_alphabets() {
+        assert!(Alphabet::Dna.is_valid('A'));
+        assert!(Alphabet::Dna.is_valid('n'));
+        assert!(Alphabet::Dna.is_valid('-'));
+        assert!(!Alphabet::Dna.is_valid('u'));
+        assert!(!Alphabet::Dna.is_valid(' '));
+        assert!(Alphabet::Rna.is_valid('U'));
+        assert!(!Alphabet::Rna.is_valid('t'));
+        assert!(Alphabet::Protein.is_valid('L'));
+        assert!(!Alphabet::Protein.is_valid('1'));
+    }
+
+    #[test]
+    fn find_illegal_characters() {
+        let validator: Validator = Validator::new(None, ValidationPolicy::Strict);
+        let illegal: Vec<(usize, char)> = validator.illegal_characters("acgun.xt").collect();
+        assert_eq!(illegal, vec![(5, '.'), (6, 'x')]);
+    }
+
+    #[test]
+    fn detect_alphabets() {
+        let alphabet = |sequence: &str| SequenceAlphabet::detect(sequence).alphabet;
+        assert_eq!(alphabet("ACGTNNacgt-"), Some(Alphabet::Dna));
+        assert_eq!(alphabet("ACGUacgu"), Some(Alphabet::Rna));
+        assert_eq!(alphabet("acgtacgtacgtacgtacgr"), Some(Alphabet::Dna));
+        assert_eq!(alphabet("MKTAYIAKQRQISFVKSHFSRQ"), Some(Alphabet::Protein));
+        assert_eq!(alphabet("ACxT"), Some(Alphabet::Dna));
+        assert_eq!(alphabet("RYKMSWBDHVN"), Some(Alphabet::Dna));
+        assert_eq!(alphabet("MKRHW"), Some(Alphabet::Protein));
+        assert_eq!(alphabet("KMRWSY"), Some(Alphabet::Protein));
+        assert_eq!(
+            alphabet("acgtacgtacgtacgtacgtacgtacgE"),
+            Some(Alphabet::Dna)
+        );
+        assert_eq!(alphabet("--"), None);
+    }
+
+    #[test]
+    fn detect_alphabets_by_majority() {
+        let typo: SequenceAlphabet = SequenceAlphabet::detect("acgtacgtacgtacgtacgu");
+        assert_eq!(typo.alphabet, Some(Alphabet::Dna));
+        assert!((typo.confidence - 0.8).abs() < 1e-9);
+        let rna: SequenceAlphabet = SequenceAlphabet::detect("acguacguacgt");
+        assert_eq!(rna.alphabet, Some(Alphabet::Rna));
+        let without_either: SequenceAlphabet = SequenceAlphabet::detect("acgacg");
+        assert_eq!(without_either.alphabet, Some(Alphabet::Dna));
+        assert_eq!(without_either.confidence, 0.5);
+        assert_eq!(SequenceAlphabet::detect("acgt").confidence, 1_f64);
+        assert_eq!(SequenceAlphabet::from(Alphabet::Rna).confidence, 1_f64);
+    }
+}
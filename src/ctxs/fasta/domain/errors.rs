@@ -0,0 +1,74 @@
+//! Errors of reading, validating and indexing sequences
+use std::{io, path::PathBuf};
+
+use thiserror::Error;
+
+use super::fastq::PhredOffset;
+
+#[derive(Debug, Error)]
+pub enum FastaError {
+    #[error(
+        "Illegal character '{character}' for {alphabet} in record {record} (\"{header}\"), line {line}, column {column}"
+    )]
+    IllegalCharacter {
+        /// 1-based number of the record in the file
+        record: usize,
+        header: String,
+        /// 1-based line in the file
+        line: usize,
+        /// 1-based column in the line
+        column: usize,
+        character: char,
+        alphabet: String,
+    },
+    #[error("Expected a fastq header at line {line}")]
+    MissingFastqHeader { line: usize },
+    #[error("Fastq record \"{header}\" has no qualities")]
+    MissingQualities { header: String },
+    #[error("Fastq record \"{header}\" has {bases} bases but {qualities} qualities")]
+    QualityLength {
+        header: String,
+        bases: usize,
+        qualities: usize,
+    },
+    #[error(
+        "Qualities of fastq record \"{header}\" are not {offset}, as those of the first records"
+    )]
+    MixedQualities { header: String, offset: PhredOffset },
+    #[error("Sequence found before the first header")]
+    SequenceBeforeHeader,
+    #[error("Lines of {name} have different lengths. Format the file before indexing it")]
+    UnevenLines { name: String },
+    #[error("{} is not compressed with BGZF. Only uncompressed and BGZF files can be indexed. Compress it with bgzip", .0.display())]
+    CompressedIndex(PathBuf),
+    #[error("Malformed index line \"{0}\"")]
+    MalformedIndex(String),
+    #[error("Sequence {0} is not in the index")]
+    MissingSequence(String),
+    #[error("Invalid region \"{region}\": {reason}")]
+    InvalidRegion { region: String, reason: String },
+    #[error("No genetic code with id \"{0}\". NCBI tables are 1 to 6, 9 to 16 and 21 to 33")]
+    UnknownGeneticCode(String),
+    #[error("Invalid reading frame \"{0}\". Frames are 1, 2, 3, -1, -2 and -3")]
+    InvalidFrame(String),
+    #[error("Invalid codon usage table: {0}")]
+    InvalidCodonUsage(String),
+    #[error("Choosing the most frequent codons needs a codon usage table")]
+    MissingCodonUsage,
+    #[error("Optimizing the codons of \"{0}\" changed its protein")]
+    ChangedProtein(String),
+    #[error("Invalid .2bit file: {0}")]
+    InvalidTwoBit(String),
+    #[error("Invalid GenBank record at line {line}: {reason}")]
+    InvalidGenBank { line: usize, reason: String },
+    #[error("Invalid feature location \"{location}\": {reason}")]
+    InvalidLocation { location: String, reason: String },
+    #[error("Record \"{0}\" is a protein, not nucleotides")]
+    NotNucleotides(String),
+    #[error("Could not open {}", .path.display())]
+    Open { path: PathBuf, source: io::Error },
+    #[error("Could not create {}", .path.display())]
+    Create { path: PathBuf, source: io::Error },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
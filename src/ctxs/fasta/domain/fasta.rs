@@ -1,9 +1,16 @@
 //! Fasta file representation and basic methods for it
 use core::fmt;
-use std::{fmt::Display, str::Chars};
+use std::{borrow::Cow, fmt::Display};
 
+use clap::ValueEnum;
 use textwrap::fill;
 
+use super::{
+    alphabet::{Alphabet, SequenceAlphabet},
+    iupac,
+    packed::{BaseCounts, PackedSequence},
+};
+
 /// DNA allowed bases
 pub const DNA_BASES: [&str; 4] = ["a", "t", "c", "g"];
 /// RNA allowed bases
@@ -23,90 +30,293 @@ impl From<String> for FastaHeader {
     }
 }
 
+impl FastaHeader {
+    /// The header text, without the leading `>`
+    pub fn as_str(&self) -> &str {
+        &self.header
+    }
+}
+
 impl Display for FastaHeader {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "> {}", self.header)
     }
 }
 
-/// Object for the sequence
-#[derive(Clone, PartialEq)]
+/// Object for the sequence. The case of the bases is kept as read, so soft-masked regions (in
+/// lowercase) stay masked through every operation. Its alphabet is detected when it is read,
+/// unless given, and kept by every operation that does not change it. Nucleotides are held packed,
+/// two or four bits per base, and anything else as text
+#[derive(Clone)]
 pub struct FastaSequence {
-    sequence: String,
+    bases: SequenceBases,
+    alphabet: SequenceAlphabet,
+}
+
+/// Bases of a sequence: packed if they can be, as text otherwise
+#[derive(Clone)]
+enum SequenceBases {
+    Packed(PackedSequence),
+    Text(String),
+}
+
+impl PartialEq for FastaSequence {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
 }
 
 impl Display for FastaSequence {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{}", fill(&self.sequence, 60))
+        writeln!(f, "{}", fill(&self.as_str(), 60))
     }
 }
 
 impl From<String> for FastaSequence {
     fn from(value: String) -> Self {
+        let sequence: String = value.replace('\n', "");
+        let alphabet: SequenceAlphabet = SequenceAlphabet::detect(&sequence);
+        let packed: Option<PackedSequence> = match alphabet.is_protein() {
+            true => None,
+            false => PackedSequence::pack(&sequence),
+        };
+        FastaSequence {
+            bases: match packed {
+                Some(packed) => SequenceBases::Packed(packed),
+                None => SequenceBases::Text(sequence),
+            },
+            alphabet,
+        }
+    }
+}
+
+/// Sequences packed in two bits per base are DNA, as .2bit files hold them
+impl From<PackedSequence> for FastaSequence {
+    fn from(value: PackedSequence) -> Self {
         FastaSequence {
-            sequence: value.replace('\n', "").to_lowercase(),
+            bases: SequenceBases::Packed(value),
+            alphabet: SequenceAlphabet::from(Alphabet::Dna),
         }
     }
 }
 
 impl FastaSequence {
-    /// Returns the chars of the seuquence as Chars iterator
-    pub fn get_chars(&self) -> Chars {
-        self.sequence.chars()
+    /// The sequence, unwrapped
+    pub fn as_str(&self) -> Cow<'_, str> {
+        match &self.bases {
+            SequenceBases::Packed(packed) => Cow::Owned(packed.unpack()),
+            SequenceBases::Text(text) => Cow::Borrowed(text),
+        }
     }
 
-    /// Reverses itself
-    fn reverse(&self) -> Self {
-        FastaSequence {
-            sequence: self.sequence.chars().rev().collect(),
+    /// The alphabet of the sequence, and how sure it is
+    pub fn alphabet(&self) -> SequenceAlphabet {
+        self.alphabet
+    }
+
+    /// Number of bases
+    pub fn len(&self) -> usize {
+        match &self.bases {
+            SequenceBases::Packed(packed) => packed.len(),
+            SequenceBases::Text(text) => text.len(),
         }
     }
 
-    /// Complements itself
-    fn complement_dna(&self) -> Self {
-        FastaSequence {
-            sequence: self
-                .sequence
-                .chars()
-                .map(|b| match b {
-                    'a' => "t".to_string(),
-                    'u' => "a".to_string(),
-                    'c' => "g".to_string(),
-                    'g' => "c".to_string(),
-                    _ => "".to_string(),
-                })
-                .collect(),
+    /// Returns the chars of the seuquence as an iterator
+    pub fn get_chars(&self) -> Box<dyn Iterator<Item = char> + '_> {
+        match &self.bases {
+            SequenceBases::Packed(packed) => Box::new(packed.chars()),
+            SequenceBases::Text(text) => Box::new(text.chars()),
         }
     }
 
-    /// Complements itself (for RNA)
-    fn complement_rna(&self) -> Self {
-        FastaSequence {
-            sequence: self
-                .sequence
-                .chars()
-                .map(|b| match b {
-                    'a' => "u".to_string(),
-                    'u' => "a".to_string(),
-                    'c' => "g".to_string(),
-                    'g' => "c".to_string(),
-                    _ => "".to_string(),
-                })
-                .collect(),
+    /// Counts the bases by kind
+    pub fn base_counts(&self) -> BaseCounts {
+        match &self.bases {
+            SequenceBases::Packed(packed) => packed.counts(),
+            SequenceBases::Text(text) => BaseCounts::from(text.as_str()),
         }
     }
 
-    fn cut(&self, start: usize, end: usize) -> Self {
+    /// The sequence packed in two bits per base, as .2bit files hold it. See
+    /// `PackedSequence::pack_two_bit` for the bases that do not fit.
+    pub fn two_bit(&self) -> PackedSequence {
+        match &self.bases {
+            SequenceBases::Packed(packed) => packed.to_two_bit(),
+            SequenceBases::Text(text) => PackedSequence::pack_two_bit(text),
+        }
+    }
+
+    /// Number of soft-masked (lowercase) bases
+    pub fn masked(&self) -> usize {
+        match &self.bases {
+            SequenceBases::Packed(packed) => packed.masked(),
+            SequenceBases::Text(text) => text.chars().filter(|b| b.is_lowercase()).count(),
+        }
+    }
+
+    /// Position of the first occurrence of `bases`, in any case, `T` and `U` alike
+    pub fn find(&self, bases: &str) -> Option<usize> {
+        if let (SequenceBases::Packed(packed), Some(kmer)) =
+            (&self.bases, PackedSequence::kmer_of(bases))
+        {
+            return packed
+                .kmers(bases.len())
+                .find(|&(_, found)| found == kmer)
+                .map(|(position, _)| position);
+        }
+        let normalize = |base: char| match base.to_ascii_lowercase() {
+            'u' => 't',
+            base => base,
+        };
+        let bases: Vec<char> = bases.chars().map(normalize).collect();
+        let sequence: Vec<char> = self.get_chars().map(normalize).collect();
+        sequence
+            .windows(bases.len().max(1))
+            .position(|window| window == bases.as_slice())
+    }
+
+    /// New sequence with the packed bases, or the text, changed by `packed` or `text`, in the
+    /// same alphabet
+    fn map(
+        &self,
+        packed: impl FnOnce(&PackedSequence) -> PackedSequence,
+        text: impl FnOnce(&str) -> String,
+    ) -> Self {
         FastaSequence {
-            sequence: self.sequence.get(start..end).unwrap_or("").to_string(),
+            bases: match &self.bases {
+                SequenceBases::Packed(bases) => SequenceBases::Packed(packed(bases)),
+                SequenceBases::Text(bases) => SequenceBases::Text(text(bases)),
+            },
+            alphabet: self.alphabet,
         }
     }
 
-    fn to_uppercase(&self) -> Self {
+    /// Same sequence, in another alphabet
+    fn in_alphabet(self, alphabet: Alphabet) -> Self {
         FastaSequence {
-            sequence: self.sequence.to_uppercase(),
+            alphabet: SequenceAlphabet::from(alphabet),
+            ..self
         }
     }
+
+    /// Reverses itself
+    fn reverse(&self) -> Self {
+        self.map(PackedSequence::reverse, |text| text.chars().rev().collect())
+    }
+
+    /// Complements itself, IUPAC ambiguity codes included, adenine pairing with uracil if `is_rna`.
+    /// Characters without a complement, like gaps, are kept as they are
+    fn complement(&self, is_rna: bool) -> Self {
+        self.map(
+            |packed| packed.complement(is_rna),
+            |text| text.chars().map(|b| iupac::complement(b, is_rna)).collect(),
+        )
+    }
+
+    /// Reverses and complements itself at once. See `complement`
+    fn reverse_complement(&self, is_rna: bool) -> Self {
+        self.map(
+            |packed| packed.reverse_complement(is_rna),
+            |text| {
+                text.chars()
+                    .rev()
+                    .map(|b| iupac::complement(b, is_rna))
+                    .collect()
+            },
+        )
+    }
+
+    /// Replaces thymine with uracil, keeping the case. The result is RNA
+    fn transcribe(&self) -> Self {
+        self.map(
+            |packed| packed.with_rna(true),
+            |text| {
+                text.chars()
+                    .map(|b| match b {
+                        't' => 'u',
+                        'T' => 'U',
+                        b => b,
+                    })
+                    .collect()
+            },
+        )
+        .in_alphabet(Alphabet::Rna)
+    }
+
+    /// Replaces uracil with thymine, keeping the case. The result is DNA
+    fn reverse_transcribe(&self) -> Self {
+        self.map(
+            |packed| packed.with_rna(false),
+            |text| {
+                text.chars()
+                    .map(|b| match b {
+                        'u' => 't',
+                        'U' => 'T',
+                        b => b,
+                    })
+                    .collect()
+            },
+        )
+        .in_alphabet(Alphabet::Dna)
+    }
+
+    fn cut(&self, start: usize, end: usize) -> Self {
+        self.map(
+            |packed| packed.slice(start, end),
+            |text| text.get(start..end).unwrap_or("").to_string(),
+        )
+    }
+
+    fn to_uppercase(&self) -> Self {
+        self.map(|packed| packed.with_mask(false), str::to_uppercase)
+    }
+
+    fn to_lowercase(&self) -> Self {
+        self.map(|packed| packed.with_mask(true), str::to_lowercase)
+    }
+
+    /// Replaces every soft-masked (lowercase) base with `N`
+    fn hard_mask(&self) -> Self {
+        self.map(PackedSequence::hard_mask, |text| {
+            text.chars()
+                .map(|b| match b.is_lowercase() {
+                    true => 'N',
+                    false => b,
+                })
+                .collect()
+        })
+    }
+}
+
+/// Case of the sequences to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Case {
+    /// As read, soft-masking included
+    #[default]
+    Keep,
+    Lower,
+    Upper,
+}
+
+/// How to get rid of soft-masking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum MaskMode {
+    /// Soft-masked bases become `N`
+    #[default]
+    Hard,
+    /// Soft-masked bases are uppercased, as the rest
+    Strip,
+}
+
+/// Strand of DNA a sequence is, or is wanted as, when transcribing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Strand {
+    /// The coding (sense) strand, read as the RNA is
+    #[default]
+    Coding,
+    /// The template (antisense) strand, reverse-complementary to the RNA
+    Template,
 }
 
 /// Fasta representation in Rust. It has a header, and a sequence.
@@ -133,16 +343,22 @@ impl<T: ToString> From<(T, T)> for Fasta {
     }
 }
 
+impl From<(String, FastaSequence)> for Fasta {
+    fn from((header, sequence): (String, FastaSequence)) -> Fasta {
+        Fasta {
+            header: FastaHeader::from(header),
+            sequence,
+        }
+    }
+}
+
 impl Fasta {
-    /// New fasta with it's complementary chain
+    /// New fasta with it's complementary chain: paired as RNA if its alphabet is RNA, and as DNA
+    /// otherwise
     pub fn complement(&self) -> Self {
-        let original_sequence: Vec<char> = self.sequence.sequence.chars().collect();
-        let sequence: FastaSequence = match original_sequence.contains(&'u') {
-            true => self.sequence.complement_rna(),
-            false => self.sequence.complement_dna(),
-        };
+        let sequence: FastaSequence = self.sequence.complement(self.sequence.alphabet().is_rna());
         Fasta {
-            header: FastaHeader::from(format!("Complementary of {}", self.header.clone())),
+            header: FastaHeader::from(format!("Complementary of {}", self.header.header)),
             sequence,
         }
     }
@@ -150,14 +366,82 @@ impl Fasta {
     /// New fasta with it's reverse chain
     pub fn reverse(&self) -> Self {
         Fasta {
-            header: FastaHeader::from(format!("Reverse of {}", self.header.clone())),
+            header: FastaHeader::from(format!("Reverse of {}", self.header.header)),
             sequence: self.sequence.reverse(),
         }
     }
 
+    /// New fasta with it's reverse-complementary chain, as `reverse` and then `complement` give,
+    /// in a single pass
+    pub fn reverse_complement(&self) -> Self {
+        Fasta {
+            header: FastaHeader::from(format!(
+                "Complementary of Reverse of {}",
+                self.header.header
+            )),
+            sequence: self
+                .sequence
+                .reverse_complement(self.sequence.alphabet().is_rna()),
+        }
+    }
+
+    /// New fasta with the RNA transcribed from DNA, `T` becoming `U`. The template strand is
+    /// reverse-complemented first. Case and IUPAC codes are kept.
+    pub fn transcribe(&self, strand: Strand) -> Self {
+        let coding: FastaSequence = match strand {
+            Strand::Coding => self.sequence.clone(),
+            Strand::Template => self.sequence.reverse_complement(false),
+        };
+        Fasta {
+            header: FastaHeader::from(format!("Transcript of {}", self.header.header)),
+            sequence: coding.transcribe(),
+        }
+    }
+
+    /// New fasta with the DNA reverse-transcribed from RNA, `U` becoming `T`: the coding strand, or
+    /// the template one, that is, the first strand of cDNA, reverse-complemented. Case and IUPAC
+    /// codes are kept.
+    pub fn reverse_transcribe(&self, strand: Strand) -> Self {
+        let coding: FastaSequence = self.sequence.reverse_transcribe();
+        Fasta {
+            header: FastaHeader::from(format!("cDNA of {}", self.header.header)),
+            sequence: match strand {
+                Strand::Coding => coding,
+                Strand::Template => coding.reverse_complement(false),
+            },
+        }
+    }
+
+    /// New fasta with its sequence taken as `alphabet`, whatever it looks like
+    pub fn with_alphabet(&self, alphabet: Alphabet) -> Self {
+        Fasta {
+            header: self.header.to_owned(),
+            sequence: self.sequence.clone().in_alphabet(alphabet),
+        }
+    }
+
     pub fn cut(&self, start: usize, end: usize) -> Self {
+        Fasta::cut_from(
+            self.header.as_str(),
+            self.sequence.cut(start, end),
+            start,
+            end,
+        )
+    }
+
+    /// New fasta with the bases from `start` to `end` of the sequence `header` names, taken out of
+    /// it already, its header noting the cut as `cut` does
+    pub fn cut_from(header: &str, bases: FastaSequence, start: usize, end: usize) -> Self {
+        Fasta {
+            header: FastaHeader::from(format!("{}, cut {} - {}", header, start, end)),
+            sequence: bases,
+        }
+    }
+
+    /// Keeps the bases from `start` to `end`, without noting the cut in the header
+    pub fn slice(&self, start: usize, end: usize) -> Self {
         Fasta {
-            header: FastaHeader::from(format!("{}, cut {} - {}", self.header, start, end)),
+            header: self.header.to_owned(),
             sequence: self.sequence.cut(start, end),
         }
     }
@@ -168,11 +452,41 @@ impl Fasta {
             sequence: self.sequence.to_uppercase(),
         }
     }
+
+    pub fn lowercase(&self) -> Self {
+        Fasta {
+            header: self.header.to_owned(),
+            sequence: self.sequence.to_lowercase(),
+        }
+    }
+
+    /// New fasta in the given case
+    pub fn with_case(&self, case: Case) -> Self {
+        match case {
+            Case::Keep => self.clone(),
+            Case::Lower => self.lowercase(),
+            Case::Upper => self.uppercase(),
+        }
+    }
+
+    /// New fasta without soft-masking, either hard-masked or stripped as `mode` tells
+    pub fn mask(&self, mode: MaskMode) -> Self {
+        match mode {
+            MaskMode::Hard => Fasta {
+                header: self.header.to_owned(),
+                sequence: self.sequence.hard_mask(),
+            },
+            MaskMode::Strip => self.uppercase(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ctxs::fasta::domain::fasta::{Fasta, FastaHeader, FastaSequence};
+    use crate::ctxs::fasta::domain::{
+        alphabet::Alphabet,
+        fasta::{Case, Fasta, FastaHeader, FastaSequence, MaskMode, Strand},
+    };
 
     #[test]
     fn make_fasta_array() {
@@ -209,4 +523,104 @@ mod tests {
             fasta.sequence == FastaSequence::from("uagc".to_string())
         )
     }
+
+    #[test]
+    fn complement_dna_sequence() {
+        let fasta: Fasta = Fasta::from(("test header", "aattccgg")).complement();
+        assert_eq!(fasta.header.to_string(), "> Complementary of test header\n");
+        assert_eq!(fasta.sequence.to_string(), "ttaaggcc\n");
+    }
+
+    #[test]
+    fn complement_keeps_unknown_characters() {
+        let fasta: Fasta = Fasta::from(("test header", "an-t")).complement();
+        assert_eq!(fasta.sequence.to_string(), "tn-a\n");
+    }
+
+    #[test]
+    fn complement_ambiguous_bases() {
+        let fasta: Fasta = Fasta::from(("test header", "arykmbvdhswn")).complement();
+        assert_eq!(fasta.sequence.to_string(), "tyrmkvbhdswn\n");
+    }
+
+    #[test]
+    fn keep_soft_masking() {
+        let fasta: Fasta = Fasta::from(("test header", "ACgtN"));
+        assert_eq!(fasta.sequence.as_str(), "ACgtN");
+        assert_eq!(fasta.reverse().complement().sequence.as_str(), "NacGT");
+        let rna: Fasta = Fasta::from(("test header", "AUcg")).complement();
+        assert_eq!(rna.sequence.as_str(), "UAgc");
+    }
+
+    #[test]
+    fn complement_by_alphabet() {
+        let typo: Fasta = Fasta::from(("test header", "acgtacgtacgtacgtacgu"));
+        assert_eq!(typo.complement().sequence.as_str(), "tgcatgcatgcatgcatgca");
+        let rna: Fasta = Fasta::from(("test header", "acgacg")).with_alphabet(Alphabet::Rna);
+        assert_eq!(rna.complement().sequence.as_str(), "ugcugc");
+        assert!(rna.reverse().cut(0, 3).sequence.alphabet().is_rna());
+        let transcript: Fasta = Fasta::from(("test header", "acg")).transcribe(Strand::Coding);
+        assert!(transcript.complement().sequence.alphabet().is_rna());
+        assert_eq!(transcript.complement().sequence.as_str(), "ugc");
+    }
+
+    #[test]
+    fn packed_and_text_sequences_agree() {
+        for bases in ["ACgtNNNacGTa", "ACgtRYn-a", "ACgtXa*"] {
+            let fasta: Fasta = Fasta::from(("test header", bases));
+            let two_steps: Fasta = fasta.reverse().complement();
+            let one_step: Fasta = fasta.reverse_complement();
+            assert_eq!(one_step.header.as_str(), two_steps.header.as_str());
+            assert_eq!(one_step.sequence.as_str(), two_steps.sequence.as_str());
+            assert_eq!(fasta.sequence.len(), bases.len());
+            assert_eq!(fasta.cut(2, 6).sequence.as_str(), &bases[2..6]);
+        }
+    }
+
+    #[test]
+    fn find_bases() {
+        let fasta: Fasta = Fasta::from(("test header", "ccAUgNatg"));
+        assert_eq!(fasta.sequence.find("atg"), Some(2));
+        assert_eq!(fasta.sequence.find("gna"), Some(4));
+        assert_eq!(fasta.sequence.find("ttt"), None);
+        let text: Fasta = Fasta::from(("test header", "cc*xatg"));
+        assert_eq!(text.sequence.find("ATG"), Some(4));
+    }
+
+    #[test]
+    fn change_case() {
+        let fasta: Fasta = Fasta::from(("test header", "ACgt"));
+        assert_eq!(fasta.with_case(Case::Keep).sequence.as_str(), "ACgt");
+        assert_eq!(fasta.with_case(Case::Lower).sequence.as_str(), "acgt");
+        assert_eq!(fasta.with_case(Case::Upper).sequence.as_str(), "ACGT");
+    }
+
+    #[test]
+    fn remove_soft_masking() {
+        let fasta: Fasta = Fasta::from(("test header", "ACgtnN-"));
+        assert_eq!(fasta.mask(MaskMode::Hard).sequence.as_str(), "ACNNNN-");
+        assert_eq!(fasta.mask(MaskMode::Strip).sequence.as_str(), "ACGTNN-");
+    }
+
+    #[test]
+    fn transcribe_strands() {
+        let fasta: Fasta = Fasta::from(("gene", "ATGcgtNRy-"));
+        let coding: Fasta = fasta.transcribe(Strand::Coding);
+        assert_eq!(coding.header.as_str(), "Transcript of gene");
+        assert_eq!(coding.sequence.as_str(), "AUGcguNRy-");
+        let template: Fasta = fasta.transcribe(Strand::Template);
+        assert_eq!(template.sequence.as_str(), "-rYNacgCAU");
+    }
+
+    #[test]
+    fn reverse_transcribe_strands() {
+        let fasta: Fasta = Fasta::from(("mrna", "AUGcguNRy"));
+        let coding: Fasta = fasta.reverse_transcribe(Strand::Coding);
+        assert_eq!(coding.header.as_str(), "cDNA of mrna");
+        assert_eq!(coding.sequence.as_str(), "ATGcgtNRy");
+        let template: Fasta = fasta.reverse_transcribe(Strand::Template);
+        assert_eq!(template.sequence.as_str(), "rYNacgCAT");
+        let back: Fasta = template.transcribe(Strand::Template);
+        assert_eq!(back.sequence.as_str(), fasta.sequence.as_str());
+    }
 }
@@ -0,0 +1,314 @@
+//! Fastq representation: a fasta record with a quality score for each base
+use core::fmt;
+use std::fmt::Display;
+
+use super::{
+    alphabet::Alphabet,
+    fasta::{Case, Fasta, FastaHeader, FastaSequence, MaskMode, Strand},
+};
+
+/// Lowest printable quality character in Phred+64 files. Lower characters only appear in Phred+33.
+const PHRED64_MIN: u8 = b';';
+/// Highest quality character of Illumina 1.8+ Phred+33 files. Long read Phred+33 files go higher,
+/// so characters above it are only a hint of Phred+64.
+const PHRED33_MAX: u8 = b'J';
+/// Highest quality character in Phred+64 files (Q40). Higher ones only appear in Phred+33.
+const PHRED64_MAX: u8 = b'h';
+
+/// Offset of the ASCII encoding of quality scores
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhredOffset {
+    /// Sanger and Illumina 1.8+
+    Phred33 = 33,
+    /// Solexa and Illumina 1.3 to 1.7
+    Phred64 = 64,
+}
+
+impl Display for PhredOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Phred+{}", *self as u8)
+    }
+}
+
+impl PhredOffset {
+    /// Guesses the encoding of a sample of quality lines. Any character below `;` or above `h`
+    /// only fits Phred+33. Phred+64 is only told when no line has either, and some have
+    /// characters above `J`, which Illumina Phred+33 files never use. `None` if every line fits
+    /// both.
+    pub fn detect<'a>(qualities: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let mut seen_high: bool = false;
+        for quality in qualities {
+            for q in quality.bytes() {
+                if !(PHRED64_MIN..=PHRED64_MAX).contains(&q) {
+                    return Some(PhredOffset::Phred33);
+                }
+                seen_high |= q > PHRED33_MAX;
+            }
+        }
+        seen_high.then_some(PhredOffset::Phred64)
+    }
+
+    /// Whether every character of a quality line is in the encoding
+    pub fn fits(&self, quality: &str) -> bool {
+        let lowest: u8 = match self {
+            PhredOffset::Phred33 => b'!',
+            PhredOffset::Phred64 => PHRED64_MIN,
+        };
+        quality.bytes().all(|q| (lowest..=b'~').contains(&q))
+    }
+}
+
+/// Quality scores of a sequence, one per base
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FastqQuality {
+    scores: Vec<u8>,
+}
+
+impl From<Vec<u8>> for FastqQuality {
+    fn from(scores: Vec<u8>) -> Self {
+        FastqQuality { scores }
+    }
+}
+
+/// Always written as Phred+33
+impl Display for FastqQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded: String = self
+            .scores
+            .iter()
+            .map(|&score| (score.saturating_add(33).min(b'~')) as char)
+            .collect();
+        write!(f, "{}", encoded)
+    }
+}
+
+impl FastqQuality {
+    /// Decodes a quality line with the given offset.
+    pub fn from_ascii(quality: &str, offset: PhredOffset) -> Self {
+        FastqQuality {
+            scores: quality
+                .bytes()
+                .map(|q| q.saturating_sub(offset as u8))
+                .collect(),
+        }
+    }
+
+    /// The scores, one per base
+    pub fn scores(&self) -> &[u8] {
+        &self.scores
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Mean of all scores, `None` if there are none
+    pub fn mean(&self) -> Option<f64> {
+        match self.scores.is_empty() {
+            true => None,
+            false => {
+                Some(self.scores.iter().map(|&s| s as f64).sum::<f64>() / self.scores.len() as f64)
+            }
+        }
+    }
+
+    fn reverse(&self) -> Self {
+        FastqQuality {
+            scores: self.scores.iter().rev().copied().collect(),
+        }
+    }
+
+    fn cut(&self, start: usize, end: usize) -> Self {
+        FastqQuality {
+            scores: self.scores.get(start..end).unwrap_or(&[]).to_vec(),
+        }
+    }
+}
+
+/// Fastq representation in Rust. It has a header, a sequence and its qualities.
+#[derive(Clone)]
+pub struct Fastq {
+    /// The header
+    pub header: FastaHeader,
+    /// The sequence
+    pub sequence: FastaSequence,
+    /// One quality score per base of the sequence
+    pub quality: FastqQuality,
+}
+
+impl Display for Fastq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "@{}", self.header.as_str())?;
+        writeln!(f, "{}", self.sequence.as_str())?;
+        writeln!(f, "+")?;
+        writeln!(f, "{}", self.quality)
+    }
+}
+
+impl<T: ToString> From<(T, T, FastqQuality)> for Fastq {
+    fn from((header, sequence, quality): (T, T, FastqQuality)) -> Fastq {
+        let fasta: Fasta = Fasta::from((header, sequence));
+        Fastq {
+            header: fasta.header,
+            sequence: fasta.sequence,
+            quality,
+        }
+    }
+}
+
+/// Drops the qualities
+impl From<Fastq> for Fasta {
+    fn from(value: Fastq) -> Self {
+        Fasta {
+            header: value.header,
+            sequence: value.sequence,
+        }
+    }
+}
+
+impl Fastq {
+    /// Rebuilds a fastq from a fasta that went through some operation, and its new qualities.
+    fn with_fasta(fasta: Fasta, quality: FastqQuality) -> Self {
+        Fastq {
+            header: fasta.header,
+            sequence: fasta.sequence,
+            quality,
+        }
+    }
+
+    /// A copy of the record as fasta, without qualities
+    pub fn to_fasta(&self) -> Fasta {
+        Fasta::from(self.clone())
+    }
+
+    /// New fastq with it's complementary chain. Qualities stay in place.
+    pub fn complement(&self) -> Self {
+        Fastq::with_fasta(self.to_fasta().complement(), self.quality.clone())
+    }
+
+    /// New fastq with it's reverse chain and reversed qualities
+    pub fn reverse(&self) -> Self {
+        Fastq::with_fasta(self.to_fasta().reverse(), self.quality.reverse())
+    }
+
+    /// New fastq with it's reverse-complementary chain and reversed qualities
+    pub fn reverse_complement(&self) -> Self {
+        Fastq::with_fasta(self.to_fasta().reverse_complement(), self.quality.reverse())
+    }
+
+    /// New fastq with the RNA transcribed from DNA. Qualities are reversed with the template strand.
+    pub fn transcribe(&self, strand: Strand) -> Self {
+        Fastq::with_fasta(self.to_fasta().transcribe(strand), self.quality_for(strand))
+    }
+
+    /// New fastq with the DNA reverse-transcribed from RNA. Qualities are reversed with the
+    /// template strand.
+    pub fn reverse_transcribe(&self, strand: Strand) -> Self {
+        Fastq::with_fasta(
+            self.to_fasta().reverse_transcribe(strand),
+            self.quality_for(strand),
+        )
+    }
+
+    /// Qualities following the bases of a strand
+    fn quality_for(&self, strand: Strand) -> FastqQuality {
+        match strand {
+            Strand::Coding => self.quality.clone(),
+            Strand::Template => self.quality.reverse(),
+        }
+    }
+
+    pub fn cut(&self, start: usize, end: usize) -> Self {
+        Fastq::with_fasta(
+            self.to_fasta().cut(start, end),
+            self.quality.cut(start, end),
+        )
+    }
+
+    /// Keeps the bases, and qualities, from `start` to `end`, without noting the cut in the header
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        Fastq::with_fasta(
+            self.to_fasta().slice(start, end),
+            self.quality.cut(start, end),
+        )
+    }
+
+    pub fn with_case(&self, case: Case) -> Self {
+        Fastq::with_fasta(self.to_fasta().with_case(case), self.quality.clone())
+    }
+
+    pub fn mask(&self, mode: MaskMode) -> Self {
+        Fastq::with_fasta(self.to_fasta().mask(mode), self.quality.clone())
+    }
+
+    /// New fastq with its sequence taken as `alphabet`, whatever it looks like
+    pub fn with_alphabet(&self, alphabet: Alphabet) -> Self {
+        Fastq::with_fasta(
+            self.to_fasta().with_alphabet(alphabet),
+            self.quality.clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::domain::{
+        fasta::Strand,
+        fastq::{Fastq, FastqQuality, PhredOffset},
+    };
+
+    #[test]
+    fn detect_offset() {
+        assert_eq!(PhredOffset::detect(["!#5?I"]), Some(PhredOffset::Phred33));
+        assert_eq!(PhredOffset::detect(["BBhhf"]), Some(PhredOffset::Phred64));
+        assert_eq!(PhredOffset::detect(["@ABCD"]), None);
+        // High qualities of long reads only fit Phred+33 once any line goes low or past Q40.
+        assert_eq!(
+            PhredOffset::detect(["KLMN", "<5I"]),
+            Some(PhredOffset::Phred33)
+        );
+        assert_eq!(
+            PhredOffset::detect(["KLMN", "~~~"]),
+            Some(PhredOffset::Phred33)
+        );
+        assert!(!PhredOffset::Phred64.fits("h5"));
+        assert!(PhredOffset::Phred33.fits("h5"));
+    }
+
+    #[test]
+    fn phred64_written_as_phred33() {
+        let quality: FastqQuality = FastqQuality::from_ascii("@Ah", PhredOffset::Phred64);
+        assert_eq!(quality.scores(), &[0, 1, 40]);
+        assert_eq!(quality.to_string(), "!\"I");
+    }
+
+    #[test]
+    fn revcomp_reverses_qualities() {
+        let quality: FastqQuality = FastqQuality::from_ascii("!+5?", PhredOffset::Phred33);
+        let fastq: Fastq = Fastq::from(("read", "aacg", quality))
+            .reverse()
+            .complement();
+        assert_eq!(fastq.sequence.as_str(), "cgtt");
+        assert_eq!(fastq.quality.to_string(), "?5+!");
+    }
+
+    #[test]
+    fn cut_keeps_qualities_in_sync() {
+        let quality: FastqQuality = FastqQuality::from_ascii("!+5?", PhredOffset::Phred33);
+        let fastq: Fastq = Fastq::from(("read", "aacg", quality)).cut(1, 3);
+        assert_eq!(fastq.sequence.as_str(), "ac");
+        assert_eq!(fastq.quality.to_string(), "+5");
+        assert_eq!(fastq.to_string(), "@read, cut 1 - 3\nac\n+\n+5\n");
+    }
+
+    #[test]
+    fn transcribe_template_reverses_qualities() {
+        let quality: FastqQuality = FastqQuality::from_ascii("!+5?", PhredOffset::Phred33);
+        let fastq: Fastq = Fastq::from(("read", "aacg", quality)).transcribe(Strand::Template);
+        assert_eq!(fastq.sequence.as_str(), "cguu");
+        assert_eq!(fastq.quality.to_string(), "?5+!");
+        let cdna: Fastq = fastq.reverse_transcribe(Strand::Coding);
+        assert_eq!(cdna.sequence.as_str(), "cgtt");
+        assert_eq!(cdna.quality.to_string(), "?5+!");
+    }
+}
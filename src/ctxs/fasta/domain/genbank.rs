@@ -0,0 +1,478 @@
+//! GenBank records: a sequence and its feature table
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use super::{errors::FastaError, fasta::Fasta};
+
+/// A run of bases of a feature location. Positions are 1-based and inclusive, as GenBank writes
+/// them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    /// Whether it is on the reverse strand
+    pub reverse: bool,
+    /// Whether the feature goes on before `start` (`<`)
+    pub before_start: bool,
+    /// Whether the feature goes on after `end` (`>`)
+    pub after_end: bool,
+    /// Accession of the record the span is in, if it is another one
+    pub remote: Option<String>,
+}
+
+impl Span {
+    pub fn len(&self) -> usize {
+        self.end - self.start + 1
+    }
+
+    /// The span as GenBank writes it, strand left out
+    fn range(&self) -> String {
+        let remote: String = self
+            .remote
+            .as_ref()
+            .map_or(String::new(), |accession| format!("{}:", accession));
+        match self.start == self.end && !self.before_start && !self.after_end {
+            true => format!("{}{}", remote, self.start),
+            false => format!(
+                "{}{}{}..{}{}",
+                remote,
+                if self.before_start { "<" } else { "" },
+                self.start,
+                if self.after_end { ">" } else { "" },
+                self.end
+            ),
+        }
+    }
+}
+
+/// Where a feature is: its spans, in the order its bases are read, each on its own strand.
+/// `complement(join(1..10,20..30))` is `20..30` then `1..10`, both on the reverse strand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub spans: Vec<Span>,
+}
+
+impl FromStr for Location {
+    type Err = FastaError;
+
+    /// Parses a location as the feature tables of GenBank write them: single bases, ranges with
+    /// `<` and `>` for partial ends, `complement`, `join` and `order`, and spans of other records
+    /// (`J00194.1:100..202`). Sites between two bases (`12^13`) are taken as the base before them.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let location: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+        let spans: Vec<Span> =
+            parse_spans(&location).map_err(|reason| FastaError::InvalidLocation {
+                location: location.clone(),
+                reason,
+            })?;
+        Ok(Location { spans })
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let is_reverse: bool = !self.spans.is_empty() && self.spans.iter().all(|s| s.reverse);
+        let spans: Vec<String> = match is_reverse {
+            true => self.spans.iter().rev().map(Span::range).collect(),
+            false => self
+                .spans
+                .iter()
+                .map(|span| match span.reverse {
+                    true => format!("complement({})", span.range()),
+                    false => span.range(),
+                })
+                .collect(),
+        };
+        let joined: String = match spans.len() {
+            1 => spans.concat(),
+            _ => format!("join({})", spans.join(",")),
+        };
+        match is_reverse {
+            true => write!(f, "complement({})", joined),
+            false => write!(f, "{}", joined),
+        }
+    }
+}
+
+impl Location {
+    /// Whether the first base of the feature is missing: `<` on the forward strand, `>` on the
+    /// reverse one
+    pub fn is_partial_start(&self) -> bool {
+        self.spans.first().is_some_and(|span| match span.reverse {
+            true => span.after_end,
+            false => span.before_start,
+        })
+    }
+
+    /// Bases of the location in `record`, each span on its own strand. `None` if any span is in
+    /// another record or past the end of the sequence.
+    pub fn bases(&self, record: &Fasta) -> Option<String> {
+        let mut bases: String = String::new();
+        for span in &self.spans {
+            if span.remote.is_some() || span.end > record.sequence.len() {
+                return None;
+            }
+            let piece: Fasta = record.slice(span.start - 1, span.end);
+            let piece: Fasta = match span.reverse {
+                true => piece.reverse_complement(),
+                false => piece,
+            };
+            bases.push_str(&piece.sequence.as_str());
+        }
+        Some(bases)
+    }
+}
+
+/// Parses the spans of a location, without whitespace.
+fn parse_spans(location: &str) -> Result<Vec<Span>, String> {
+    if let Some(inner) = enclosed(location, "complement") {
+        let mut spans: Vec<Span> = parse_spans(inner)?;
+        spans.reverse();
+        spans
+            .iter_mut()
+            .for_each(|span| span.reverse = !span.reverse);
+        return Ok(spans);
+    }
+    if let Some(inner) = enclosed(location, "join").or_else(|| enclosed(location, "order")) {
+        let mut spans: Vec<Span> = Vec::new();
+        for part in split_parts(inner)? {
+            spans.extend(parse_spans(part)?);
+        }
+        return Ok(spans);
+    }
+    parse_span(location).map(|span| vec![span])
+}
+
+/// What is inside `operator(...)`, if `location` is that
+fn enclosed<'a>(location: &'a str, operator: &str) -> Option<&'a str> {
+    location
+        .strip_prefix(operator)?
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+/// Splits the arguments of `join` or `order` at the commas outside of parentheses.
+fn split_parts(arguments: &str) -> Result<Vec<&str>, String> {
+    let mut parts: Vec<&str> = Vec::new();
+    let mut depth: usize = 0;
+    let mut start: usize = 0;
+    for (index, character) in arguments.char_indices() {
+        match character {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or("it has unbalanced parentheses")?
+            }
+            ',' if depth == 0 => {
+                parts.push(&arguments[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err("it has unbalanced parentheses".to_string());
+    }
+    parts.push(&arguments[start..]);
+    Ok(parts)
+}
+
+fn parse_span(span: &str) -> Result<Span, String> {
+    let (remote, range) = match span.split_once(':') {
+        Some((accession, range)) => (Some(accession.to_string()), range),
+        None => (None, span),
+    };
+    let (start, end, is_site) = match range.split_once("..") {
+        Some((start, end)) => (start, end, false),
+        None => match range.split_once('^').or_else(|| range.split_once('.')) {
+            Some((start, end)) => (start, end, range.contains('^')),
+            None => (range, range, false),
+        },
+    };
+    let before_start: bool = start.starts_with('<');
+    let after_end: bool = end.starts_with('>');
+    let start: usize = parse_position(start.trim_start_matches(['<', '>']))?;
+    let end: usize = match is_site {
+        true => start,
+        false => parse_position(end.trim_start_matches(['<', '>']))?,
+    };
+    if end < start {
+        return Err(format!("{} ends before it starts", span));
+    }
+    Ok(Span {
+        start,
+        end,
+        reverse: false,
+        before_start,
+        after_end,
+        remote,
+    })
+}
+
+fn parse_position(position: &str) -> Result<usize, String> {
+    match position.parse::<usize>() {
+        Ok(0) => Err("positions start at 1".to_string()),
+        Ok(position) => Ok(position),
+        Err(_) => Err(format!("\"{}\" is not a position", position)),
+    }
+}
+
+/// A feature of a GenBank record: its key (`gene`, `CDS`, `source`...), where it is, and its
+/// qualifiers, in order. Qualifiers without a value, like `/pseudo`, have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature {
+    pub kind: String,
+    pub location: Location,
+    pub qualifiers: Vec<(String, Option<String>)>,
+}
+
+impl Feature {
+    /// First value of a qualifier, if the feature has it with a value
+    pub fn qualifier(&self, name: &str) -> Option<&str> {
+        self.qualifiers
+            .iter()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn is_cds(&self) -> bool {
+        self.kind == "CDS"
+    }
+
+    /// The gene the feature belongs to, by its `/locus_tag`, or by its `/gene` if it has none
+    pub fn gene(&self) -> Option<&str> {
+        self.qualifier("locus_tag")
+            .or_else(|| self.qualifier("gene"))
+    }
+}
+
+/// A GenBank record: its sequence, named after its accession and version and described by its
+/// definition, and its feature table.
+pub struct GenBankRecord {
+    pub fasta: Fasta,
+    pub features: Vec<Feature>,
+}
+
+impl GenBankRecord {
+    /// Accession and version of the record, the first word of its header
+    pub fn seqid(&self) -> &str {
+        self.fasta
+            .header
+            .as_str()
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+    }
+
+    /// The feature table as GFF3 lines, after a `##sequence-region` line. Every span of a feature
+    /// is a line, all sharing its ID, with the phase of every span of a CDS. Features of a gene
+    /// point to it with `Parent`. `/translation` is left out.
+    pub fn gff3(&self) -> String {
+        let seqid: &str = self.seqid();
+        let ids: Vec<String> = self.feature_ids();
+        let genes: HashMap<&str, &str> = self
+            .features
+            .iter()
+            .zip(&ids)
+            .filter(|(feature, _)| feature.kind == "gene")
+            .filter_map(|(feature, id)| Some((feature.gene()?, id.as_str())))
+            .collect();
+        let mut lines: String = format!(
+            "##sequence-region {} 1 {}\n",
+            seqid,
+            self.fasta.sequence.len()
+        );
+        for (feature, id) in self.features.iter().zip(&ids) {
+            let parent: Option<&str> = match feature.kind.as_str() {
+                "gene" | "source" => None,
+                _ => feature.gene().and_then(|gene| genes.get(gene).copied()),
+            };
+            let attributes: String = gff3_attributes(feature, id, parent);
+            let codon_start: usize = feature
+                .qualifier("codon_start")
+                .and_then(|start| start.parse().ok())
+                .unwrap_or(1);
+            let mut preceding: usize = 0;
+            for span in &feature.location.spans {
+                let phase: String = match feature.is_cds() {
+                    true => ((codon_start as i64 - 1 - preceding as i64).rem_euclid(3)).to_string(),
+                    false => ".".to_string(),
+                };
+                preceding += span.len();
+                if span.remote.is_some() {
+                    continue;
+                }
+                lines.push_str(&format!(
+                    "{}\tGenBank\t{}\t{}\t{}\t.\t{}\t{}\t{}\n",
+                    seqid,
+                    match feature.kind.as_str() {
+                        "source" => "region",
+                        kind => kind,
+                    },
+                    span.start,
+                    span.end,
+                    if span.reverse { '-' } else { '+' },
+                    phase,
+                    attributes
+                ));
+            }
+        }
+        lines
+    }
+
+    /// An ID for every feature, as its key and the name of what it stands for, numbered if taken
+    fn feature_ids(&self) -> Vec<String> {
+        let mut taken: HashMap<String, usize> = HashMap::new();
+        self.features
+            .iter()
+            .map(|feature| {
+                let name: &str = match feature.is_cds() {
+                    true => feature.qualifier("protein_id").or_else(|| feature.gene()),
+                    false => feature.gene(),
+                }
+                .unwrap_or(self.seqid());
+                let id: String = format!("{}-{}", feature.kind.to_lowercase(), name);
+                let count: &mut usize = taken.entry(id.clone()).or_default();
+                *count += 1;
+                match count {
+                    1 => id,
+                    _ => format!("{}-{}", id, count),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The attributes column of a feature: its ID, name and parent, and its qualifiers, those of the
+/// same name joined by commas
+fn gff3_attributes(feature: &Feature, id: &str, parent: Option<&str>) -> String {
+    let mut attributes: Vec<(String, Vec<String>)> = vec![(String::from("ID"), vec![id.into()])];
+    if let Some(name) = feature.qualifier("gene").or_else(|| feature.gene()) {
+        attributes.push((String::from("Name"), vec![name.to_string()]));
+    }
+    if let Some(parent) = parent {
+        attributes.push((String::from("Parent"), vec![parent.to_string()]));
+    }
+    for (key, value) in &feature.qualifiers {
+        let key: &str = match key.as_str() {
+            "translation" => continue,
+            "db_xref" => "Dbxref",
+            "note" => "Note",
+            key => key,
+        };
+        let value: String = value.clone().unwrap_or_else(|| String::from("true"));
+        match attributes.iter_mut().find(|(name, _)| name == key) {
+            Some((_, values)) => values.push(value),
+            None => attributes.push((key.to_string(), vec![value])),
+        }
+    }
+    attributes
+        .iter()
+        .map(|(key, values)| {
+            let values: Vec<String> = values.iter().map(|value| gff3_escape(value)).collect();
+            format!("{}={}", gff3_escape(key), values.join(","))
+        })
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+/// Escapes the characters GFF3 gives a meaning to in attributes
+fn gff3_escape(value: &str) -> String {
+    value
+        .chars()
+        .map(|character| match character {
+            '%' | ';' | '=' | '&' | ',' | '\t' | '\n' | '\r' => {
+                format!("%{:02X}", character as u32)
+            }
+            character => character.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::domain::{
+        fasta::Fasta,
+        genbank::{Feature, GenBankRecord, Location},
+    };
+
+    fn location(value: &str) -> Location {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_locations() {
+        let spans: Vec<(usize, usize, bool)> = location("complement(join(1..10, 20..>30))")
+            .spans
+            .iter()
+            .map(|span| (span.start, span.end, span.reverse))
+            .collect();
+        assert_eq!(spans, [(20, 30, true), (1, 10, true)]);
+        assert!(location("complement(<1..>30)").is_partial_start());
+        assert!(!location("<1..>30").spans[0].reverse);
+        assert_eq!(
+            location("J00194.1:100..202").spans[0].remote.as_deref(),
+            Some("J00194.1")
+        );
+        assert_eq!(location("12^13").spans[0].end, 12);
+        for invalid in ["0..10", "10..5", "join(1..5", "1..x", ""] {
+            assert!(invalid.parse::<Location>().is_err(), "{}", invalid);
+        }
+        for written in [
+            "complement(join(1..10,20..>30))",
+            "join(complement(1..10),20..30)",
+            "<5..100",
+            "7",
+            "J00194.1:100..202",
+        ] {
+            assert_eq!(location(written).to_string(), written);
+        }
+    }
+
+    #[test]
+    fn extract_location_bases() {
+        let record: Fasta = Fasta::from(("seq", "AACCGGTTAC"));
+        assert_eq!(location("join(1..2,9..10)").bases(&record).unwrap(), "AAAC");
+        assert_eq!(
+            location("complement(join(1..2,9..10))")
+                .bases(&record)
+                .unwrap(),
+            "GTTT"
+        );
+        assert_eq!(location("9..11").bases(&record), None);
+    }
+
+    #[test]
+    fn write_gff3() {
+        let feature = |kind: &str, at: &str, qualifiers: &[(&str, &str)]| Feature {
+            kind: kind.to_string(),
+            location: location(at),
+            qualifiers: qualifiers
+                .iter()
+                .map(|(key, value)| (key.to_string(), Some(value.to_string())))
+                .collect(),
+        };
+        let record: GenBankRecord = GenBankRecord {
+            fasta: Fasta::from(("NC_1.1 test", "ACGTACGTACGTACGTACGT")),
+            features: vec![
+                feature("gene", "complement(2..20)", &[("locus_tag", "b1")]),
+                feature(
+                    "CDS",
+                    "complement(join(2..5,10..20))",
+                    &[("locus_tag", "b1"), ("note", "a; b"), ("translation", "M")],
+                ),
+            ],
+        };
+        assert_eq!(
+            record.gff3(),
+            "##sequence-region NC_1.1 1 20\n\
+             NC_1.1\tGenBank\tgene\t2\t20\t.\t-\t.\tID=gene-b1;Name=b1;locus_tag=b1\n\
+             NC_1.1\tGenBank\tCDS\t10\t20\t.\t-\t0\tID=cds-b1;Name=b1;Parent=gene-b1;locus_tag=b1;Note=a%3B b\n\
+             NC_1.1\tGenBank\tCDS\t2\t5\t.\t-\t1\tID=cds-b1;Name=b1;Parent=gene-b1;locus_tag=b1;Note=a%3B b\n"
+        );
+    }
+}
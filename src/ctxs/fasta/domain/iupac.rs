@@ -0,0 +1,118 @@
+//! IUPAC nucleotide codes: complements and expansion of ambiguity codes
+/// Bases each IUPAC code stands for, in DNA letters
+const IUPAC_CODES: [(char, &[char]); 15] = [
+    ('a', &['a']),
+    ('c', &['c']),
+    ('g', &['g']),
+    ('t', &['t']),
+    ('u', &['t']),
+    ('r', &['a', 'g']),
+    ('y', &['c', 't']),
+    ('s', &['c', 'g']),
+    ('w', &['a', 't']),
+    ('k', &['g', 't']),
+    ('m', &['a', 'c']),
+    ('b', &['c', 'g', 't']),
+    ('d', &['a', 'g', 't']),
+    ('h', &['a', 'c', 't']),
+    ('v', &['a', 'c', 'g']),
+];
+
+/// Bases that `base` stands for, in lowercase DNA letters. `n` stands for any base. `None` if it
+/// is not an IUPAC nucleotide code.
+pub fn expand(base: char) -> Option<&'static [char]> {
+    let base: char = base.to_ascii_lowercase();
+    if base == 'n' {
+        return Some(&['a', 'c', 'g', 't']);
+    }
+    IUPAC_CODES
+        .iter()
+        .find(|(code, _)| *code == base)
+        .map(|(_, bases)| *bases)
+}
+
+/// The IUPAC code, in lowercase DNA letters, that stands for exactly the bases of `bases`. Any
+/// base may be an ambiguity code itself. `None` if there are no bases, or any is not an IUPAC code.
+pub fn code_for(bases: &[char]) -> Option<char> {
+    let mut expanded: Vec<char> = Vec::new();
+    for &base in bases {
+        expanded.extend_from_slice(expand(base)?);
+    }
+    expanded.sort_unstable();
+    expanded.dedup();
+    if expanded.len() == 4 {
+        return Some('n');
+    }
+    IUPAC_CODES
+        .iter()
+        .filter(|(code, _)| *code != 'u')
+        .find(|(_, codes)| *codes == expanded.as_slice())
+        .map(|(code, _)| *code)
+}
+
+/// Whether `base` is an ambiguity code, that is, stands for more than one base.
+pub fn is_ambiguous(base: char) -> bool {
+    expand(base).is_some_and(|bases| bases.len() > 1)
+}
+
+/// Complement of a nucleotide code, keeping its case. Adenine pairs with uracil instead of thymine
+/// if `is_rna`. Characters that are not IUPAC codes, like gaps, are their own complement.
+pub fn complement(base: char, is_rna: bool) -> char {
+    let complement: char = match base.to_ascii_lowercase() {
+        'a' if is_rna => 'u',
+        'a' => 't',
+        't' | 'u' => 'a',
+        'c' => 'g',
+        'g' => 'c',
+        'r' => 'y',
+        'y' => 'r',
+        'k' => 'm',
+        'm' => 'k',
+        'b' => 'v',
+        'v' => 'b',
+        'd' => 'h',
+        'h' => 'd',
+        _ => return base,
+    };
+    match base.is_ascii_uppercase() {
+        true => complement.to_ascii_uppercase(),
+        false => complement,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::domain::iupac::{code_for, complement, expand, is_ambiguous};
+
+    #[test]
+    fn complement_iupac_codes() {
+        let complemented: String = "acgtrykmbvdhswn-"
+            .chars()
+            .map(|b| complement(b, false))
+            .collect();
+        assert_eq!(complemented, "tgcayrmkvbhdswn-");
+        assert_eq!(complement('A', true), 'U');
+        assert_eq!(complement('R', false), 'Y');
+    }
+
+    #[test]
+    fn expand_iupac_codes() {
+        assert_eq!(expand('R'), Some(&['a', 'g'][..]));
+        assert_eq!(expand('n').map(|bases| bases.len()), Some(4));
+        assert_eq!(expand('u'), Some(&['t'][..]));
+        assert_eq!(expand('x'), None);
+        assert!(is_ambiguous('n'));
+        assert!(!is_ambiguous('a'));
+    }
+
+    #[test]
+    fn find_iupac_codes() {
+        assert_eq!(code_for(&['a']), Some('a'));
+        assert_eq!(code_for(&['u', 'C']), Some('y'));
+        assert_eq!(code_for(&['g', 'a', 'g']), Some('r'));
+        assert_eq!(code_for(&['r', 'c']), Some('v'));
+        assert_eq!(code_for(&['r', 'y']), Some('n'));
+        assert_eq!(code_for(&[]), None);
+        assert_eq!(code_for(&['a', 'x']), None);
+    }
+}
@@ -1 +1,10 @@
+pub mod alphabet;
+pub mod errors;
 pub mod fasta;
+pub mod fastq;
+pub mod genbank;
+pub mod iupac;
+pub mod packed;
+pub mod record;
+pub mod region;
+pub mod trim;
@@ -0,0 +1,788 @@
+//! Packed nucleotide sequences, to hold whole genomes in a fraction of the memory. Sequences of
+//! `A`, `C`, `G`, `T` (or `U`) and `N` take two bits per base, their runs of `N` kept aside, and
+//! sequences with other IUPAC ambiguity codes or gaps take four. Runs of soft-masked (lowercase)
+//! bases are kept aside too, so sequences unpack just as they were read.
+//!
+//! Complementing, reversing, counting, unpacking and k-mer extraction work on whole bytes with
+//! bit operations, without branching on the bases, so they can be vectorized. Reading the bases
+//! one by one, and hard-masking four-bit sequences, go base by base.
+use std::borrow::Cow;
+
+use super::iupac;
+
+/// Bases of the two-bit codes, as UCSC .2bit files have them: `T`, `C`, `A` and `G`.
+/// Complementing a base flips its high bit.
+const TWO_BIT_BASES: [u8; 4] = *b"TCAG";
+/// IUPAC codes of the four-bit codes: one bit for each base they stand for, `A` being the lowest
+/// and `T` the highest. Complementing a code reverses its bits. The gap stands for no base.
+const FOUR_BIT_BASES: [u8; 16] = *b"-ACMGRSVTWYHKDBN";
+/// Four-bit code of the gap
+const GAP: u8 = 0;
+/// Four-bit code of `N`
+const ANY_BASE: u8 = 15;
+
+/// Runs of bases, as their start and length, sorted and apart from each other
+type Runs = Vec<(usize, usize)>;
+
+/// The bases, packed as tightly as they allow. The first base takes the highest bits of the first
+/// byte, and the bits after the last base are zero.
+#[derive(Debug, Clone, PartialEq)]
+enum PackedBases {
+    TwoBit(Vec<u8>),
+    FourBit(Vec<u8>),
+}
+
+/// A nucleotide sequence, packed. See the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedSequence {
+    bases: PackedBases,
+    length: usize,
+    /// Runs of `N`, stored as `T` in two-bit packing. Always empty in four-bit packing.
+    n_runs: Runs,
+    /// Runs of soft-masked (lowercase) bases
+    mask_runs: Runs,
+    /// Whether `T` is written as `U`
+    is_rna: bool,
+}
+
+/// Bases of a sequence, by kind. `T` and `U` count alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BaseCounts {
+    pub a: usize,
+    pub c: usize,
+    pub g: usize,
+    pub t: usize,
+    pub n: usize,
+    /// IUPAC ambiguity codes other than `N`
+    pub ambiguous: usize,
+    pub gaps: usize,
+    /// Characters that are not IUPAC codes nor gaps
+    pub other: usize,
+}
+
+impl BaseCounts {
+    /// Every character counted
+    pub fn total(&self) -> usize {
+        self.a + self.c + self.g + self.t + self.n + self.ambiguous + self.gaps + self.other
+    }
+}
+
+/// Counts the characters of an unpacked sequence
+impl From<&str> for BaseCounts {
+    fn from(sequence: &str) -> Self {
+        let mut counts: BaseCounts = BaseCounts::default();
+        for base in sequence.chars() {
+            let count: &mut usize = match base.to_ascii_lowercase() {
+                'a' => &mut counts.a,
+                'c' => &mut counts.c,
+                'g' => &mut counts.g,
+                't' | 'u' => &mut counts.t,
+                'n' => &mut counts.n,
+                '-' => &mut counts.gaps,
+                base if iupac::is_ambiguous(base) => &mut counts.ambiguous,
+                _ => &mut counts.other,
+            };
+            *count += 1;
+        }
+        counts
+    }
+}
+
+impl PackedSequence {
+    /// Packs a sequence, in two bits per base if it only has `A`, `C`, `G`, `T` (or `U`) and `N`,
+    /// and in four bits if it has other IUPAC codes or gaps, in any case. `None` if it has any
+    /// other character, or both `T` and `U`, as it could not be unpacked as it was.
+    pub fn pack(sequence: &str) -> Option<Self> {
+        let sequence: &[u8] = sequence.as_bytes();
+        let has = |base: u8| sequence.iter().any(|b| b.eq_ignore_ascii_case(&base));
+        let is_rna: bool = has(b'u');
+        if is_rna && has(b't') {
+            return None;
+        }
+        let is_two_bit: bool = sequence
+            .iter()
+            .all(|b| b"ACGTUN".contains(&b.to_ascii_uppercase()));
+        let bases: PackedBases = match is_two_bit {
+            true => PackedBases::TwoBit(pack_codes(sequence, 2, two_bit_code_of)),
+            false => {
+                if !sequence.iter().all(|&b| four_bit_code_of(b).is_some()) {
+                    return None;
+                }
+                PackedBases::FourBit(pack_codes(sequence, 4, |b| {
+                    four_bit_code_of(b).unwrap_or(GAP)
+                }))
+            }
+        };
+        let n_runs: Runs = match is_two_bit {
+            true => runs_of(sequence, |b| b.eq_ignore_ascii_case(&b'n')),
+            false => Vec::new(),
+        };
+        Some(PackedSequence {
+            bases,
+            length: sequence.len(),
+            n_runs,
+            mask_runs: runs_of(sequence, |b| b.is_ascii_lowercase()),
+            is_rna,
+        })
+    }
+
+    /// Packs a sequence in two bits per base, whatever it has: any base other than `A`, `C`, `G`,
+    /// `T` or `U` becomes `N`, and uracil is stored as thymine. The case is kept.
+    pub fn pack_two_bit(sequence: &str) -> Self {
+        let sequence: String = sequence
+            .chars()
+            .map(|base| match base {
+                'A' | 'C' | 'G' | 'T' | 'a' | 'c' | 'g' | 't' => base,
+                'U' => 'T',
+                'u' => 't',
+                base if base.is_lowercase() => 'n',
+                _ => 'N',
+            })
+            .collect();
+        PackedSequence::pack(&sequence).unwrap_or_else(|| PackedSequence {
+            bases: PackedBases::TwoBit(Vec::new()),
+            length: 0,
+            n_runs: Vec::new(),
+            mask_runs: Vec::new(),
+            is_rna: false,
+        })
+    }
+
+    /// Bases packed in two bits, as in .2bit files, from the base at `offset` of a sequence on,
+    /// `length` of them. Runs of `N` and of soft-masked bases are those of the whole sequence, in
+    /// any order, and are clipped to the bases given.
+    pub fn from_two_bit(
+        mut bytes: Vec<u8>,
+        offset: usize,
+        length: usize,
+        n_runs: &[(usize, usize)],
+        mask_runs: &[(usize, usize)],
+    ) -> Self {
+        bytes.resize(length.div_ceil(4), 0);
+        clear_padding(&mut bytes, length, 2);
+        PackedSequence {
+            bases: PackedBases::TwoBit(bytes),
+            length,
+            n_runs: merge_runs(&slice_runs(n_runs, offset, offset + length), &[]),
+            mask_runs: merge_runs(&slice_runs(mask_runs, offset, offset + length), &[]),
+            is_rna: false,
+        }
+    }
+
+    /// The same sequence in two-bit packing, as `pack_two_bit` packs it
+    pub fn to_two_bit(&self) -> Self {
+        match &self.bases {
+            PackedBases::TwoBit(_) => self.with_rna(false),
+            PackedBases::FourBit(_) => PackedSequence::pack_two_bit(&self.unpack()),
+        }
+    }
+
+    /// The bases in two bits, `N` stored as `T`. `None` if they are packed in four.
+    pub fn two_bit_bytes(&self) -> Option<&[u8]> {
+        match &self.bases {
+            PackedBases::TwoBit(bytes) => Some(bytes),
+            PackedBases::FourBit(_) => None,
+        }
+    }
+
+    /// Runs of `N` of a two-bit sequence, as their start and length
+    pub fn n_runs(&self) -> &[(usize, usize)] {
+        &self.n_runs
+    }
+
+    /// Runs of soft-masked bases, as their start and length
+    pub fn mask_runs(&self) -> &[(usize, usize)] {
+        &self.mask_runs
+    }
+
+    /// Number of bases
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// The bases, as they were read
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        let mut in_n = covered(&self.n_runs);
+        let mut in_mask = covered(&self.mask_runs);
+        (0..self.length).map(move |position| {
+            let base: u8 = match (&self.bases, in_n(position)) {
+                (_, true) => b'N',
+                (PackedBases::TwoBit(bytes), false) => {
+                    TWO_BIT_BASES[code_at(bytes, position, 2) as usize]
+                }
+                (PackedBases::FourBit(bytes), false) => {
+                    FOUR_BIT_BASES[code_at(bytes, position, 4) as usize]
+                }
+            };
+            let base: u8 = match (base, self.is_rna) {
+                (b'T', true) => b'U',
+                (base, _) => base,
+            };
+            match in_mask(position) {
+                true => base.to_ascii_lowercase() as char,
+                false => base as char,
+            }
+        })
+    }
+
+    /// The bases, as they were read. Whole bytes are decoded at once, and runs written over them.
+    pub fn unpack(&self) -> String {
+        let mut bases: Vec<u8> = match &self.bases {
+            PackedBases::TwoBit(bytes) => bytes
+                .iter()
+                .flat_map(|&byte| {
+                    [6, 4, 2, 0].map(|shift| TWO_BIT_BASES[(byte >> shift & 3) as usize])
+                })
+                .collect(),
+            PackedBases::FourBit(bytes) => bytes
+                .iter()
+                .flat_map(|&byte| {
+                    [byte >> 4, byte & 0x0f].map(|code| FOUR_BIT_BASES[code as usize])
+                })
+                .collect(),
+        };
+        bases.truncate(self.length);
+        if self.is_rna {
+            bases
+                .iter_mut()
+                .filter(|base| **base == b'T')
+                .for_each(|base| *base = b'U');
+        }
+        for &(start, length) in &self.n_runs {
+            bases[start..start + length].fill(b'N');
+        }
+        for &(start, length) in &self.mask_runs {
+            bases[start..start + length].make_ascii_lowercase();
+        }
+        // Every code decodes to an ASCII letter
+        String::from_utf8(bases).unwrap_or_default()
+    }
+
+    /// Counts the bases by kind
+    pub fn counts(&self) -> BaseCounts {
+        match &self.bases {
+            PackedBases::TwoBit(bytes) => {
+                let mut codes: [usize; 4] = count_two_bit(bytes, 0, self.length);
+                for &(start, length) in &self.n_runs {
+                    let in_run: [usize; 4] = count_two_bit(bytes, start, start + length);
+                    codes
+                        .iter_mut()
+                        .zip(in_run)
+                        .for_each(|(code, n)| *code -= n);
+                }
+                BaseCounts {
+                    t: codes[0],
+                    c: codes[1],
+                    a: codes[2],
+                    g: codes[3],
+                    n: self.n_runs.iter().map(|(_, length)| length).sum(),
+                    ..BaseCounts::default()
+                }
+            }
+            PackedBases::FourBit(bytes) => {
+                let mut codes: [usize; 16] = [0; 16];
+                for byte in bytes {
+                    codes[(byte >> 4) as usize] += 1;
+                    codes[(byte & 0x0f) as usize] += 1;
+                }
+                codes[GAP as usize] -= bytes.len() * 2 - self.length;
+                let single: usize = codes[1] + codes[2] + codes[4] + codes[8];
+                let ambiguous: usize = self.length - single - codes[GAP as usize];
+                BaseCounts {
+                    a: codes[1],
+                    c: codes[2],
+                    g: codes[4],
+                    t: codes[8],
+                    n: codes[ANY_BASE as usize],
+                    ambiguous: ambiguous - codes[ANY_BASE as usize],
+                    gaps: codes[GAP as usize],
+                    other: 0,
+                }
+            }
+        }
+    }
+
+    /// Number of soft-masked bases
+    pub fn masked(&self) -> usize {
+        self.mask_runs.iter().map(|(_, length)| length).sum()
+    }
+
+    /// The k-mers of the sequence, `k` from 1 to 32, as their position and their bases in two-bit
+    /// codes, the first base the highest. K-mers with any base other than `A`, `C`, `G` or `T`
+    /// are left out. Bases are rolled in a whole byte, four of them, at a time, and the k-mers
+    /// ending in it are shifted out of the bases rolled in so far.
+    pub fn kmers(&self, k: usize) -> impl Iterator<Item = (usize, u64)> + '_ {
+        assert!((1..=32).contains(&k), "k-mers are 1 to 32 bases long");
+        let mask: u128 = (u64::MAX >> (64 - 2 * k)) as u128;
+        // Other bases than `A`, `C`, `G` and `T` are runs of `N` in two-bit packing
+        let sequence: Cow<PackedSequence> = match &self.bases {
+            PackedBases::TwoBit(_) => Cow::Borrowed(self),
+            PackedBases::FourBit(_) => Cow::Owned(self.to_two_bit()),
+        };
+        let (mut rolled, mut clean_from, mut next_run): (u128, usize, usize) = (0, 0, 0);
+        (0..self.length.div_ceil(4))
+            .flat_map(move |index| {
+                let byte: u8 = sequence.two_bit_bytes().unwrap_or_default()[index];
+                rolled = (rolled << 8) | byte as u128;
+                [0, 1, 2, 3].map(|offset| {
+                    let end: usize = index * 4 + offset;
+                    while let Some(&(start, length)) =
+                        sequence.n_runs.get(next_run).filter(|(start, _)| *start <= end)
+                    {
+                        clean_from = start + length;
+                        next_run += 1;
+                    }
+                    let kmer: u64 = ((rolled >> (6 - 2 * offset)) & mask) as u64;
+                    (end < self.length && end + 1 >= clean_from + k).then(|| (end + 1 - k, kmer))
+                })
+            })
+            .flatten()
+    }
+
+    /// The k-mer of some bases, in two-bit codes as `kmers` gives them. `None` if it has any base
+    /// other than `A`, `C`, `G`, `T` or `U`, or is not 1 to 32 bases long.
+    pub fn kmer_of(bases: &str) -> Option<u64> {
+        if !(1..=32).contains(&bases.len()) {
+            return None;
+        }
+        bases
+            .bytes()
+            .try_fold(0_u64, |kmer, base| match base.to_ascii_uppercase() {
+                b'A' | b'C' | b'G' | b'T' | b'U' => {
+                    Some((kmer << 2) | two_bit_code_of(base) as u64)
+                }
+                _ => None,
+            })
+    }
+
+    /// Complementary sequence, pairing adenine with uracil if `is_rna` and with thymine otherwise
+    pub fn complement(&self, is_rna: bool) -> Self {
+        let bases: PackedBases = match &self.bases {
+            PackedBases::TwoBit(bytes) => {
+                let mut bytes: Vec<u8> = bytes.iter().map(|byte| byte ^ 0xaa).collect();
+                clear_padding(&mut bytes, self.length, 2);
+                PackedBases::TwoBit(bytes)
+            }
+            PackedBases::FourBit(bytes) => PackedBases::FourBit(
+                bytes
+                    .iter()
+                    .map(|byte| byte.reverse_bits().rotate_left(4))
+                    .collect(),
+            ),
+        };
+        PackedSequence {
+            bases,
+            is_rna,
+            ..self.clone()
+        }
+    }
+
+    /// Reverse sequence
+    pub fn reverse(&self) -> Self {
+        match &self.bases {
+            PackedBases::TwoBit(bytes) => self.reversed(PackedBases::TwoBit(reverse_bytes(
+                bytes,
+                self.length,
+                2,
+                reverse_two_bit,
+            ))),
+            PackedBases::FourBit(bytes) => self.reversed(PackedBases::FourBit(reverse_bytes(
+                bytes,
+                self.length,
+                4,
+                |byte| byte.rotate_left(4),
+            ))),
+        }
+    }
+
+    /// Reverse-complementary sequence, pairing adenine with uracil if `is_rna`
+    pub fn reverse_complement(&self, is_rna: bool) -> Self {
+        let reversed: PackedSequence = match &self.bases {
+            PackedBases::TwoBit(bytes) => self.reversed(PackedBases::TwoBit(reverse_bytes(
+                bytes,
+                self.length,
+                2,
+                |byte| reverse_two_bit(byte) ^ 0xaa,
+            ))),
+            PackedBases::FourBit(bytes) => self.reversed(PackedBases::FourBit(reverse_bytes(
+                bytes,
+                self.length,
+                4,
+                u8::reverse_bits,
+            ))),
+        };
+        PackedSequence { is_rna, ..reversed }
+    }
+
+    /// Same sequence, `T` written as `U` if `is_rna`, and as `T` otherwise
+    pub fn with_rna(&self, is_rna: bool) -> Self {
+        PackedSequence {
+            is_rna,
+            ..self.clone()
+        }
+    }
+
+    /// The bases from `start` to `end`. Empty if they are not in the sequence.
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        let (start, end) = match start <= end && end <= self.length {
+            true => (start, end),
+            false => (0, 0),
+        };
+        let length: usize = end - start;
+        let bases: PackedBases = match &self.bases {
+            PackedBases::TwoBit(bytes) => PackedBases::TwoBit(slice_bytes(bytes, start, length, 2)),
+            PackedBases::FourBit(bytes) => {
+                PackedBases::FourBit(slice_bytes(bytes, start, length, 4))
+            }
+        };
+        PackedSequence {
+            bases,
+            length,
+            n_runs: slice_runs(&self.n_runs, start, end),
+            mask_runs: slice_runs(&self.mask_runs, start, end),
+            is_rna: self.is_rna,
+        }
+    }
+
+    /// Same sequence, every letter soft-masked if `lowercase`, and none otherwise
+    pub fn with_mask(&self, lowercase: bool) -> Self {
+        let mask_runs: Runs = match (lowercase, &self.bases) {
+            (false, _) => Vec::new(),
+            (true, PackedBases::TwoBit(_)) if self.length > 0 => vec![(0, self.length)],
+            (true, PackedBases::TwoBit(_)) => Vec::new(),
+            (true, PackedBases::FourBit(_)) => {
+                let codes: Vec<u8> = self.codes().collect();
+                runs_of(&codes, |&code| code != GAP)
+            }
+        };
+        PackedSequence {
+            mask_runs,
+            ..self.clone()
+        }
+    }
+
+    /// Same sequence, its soft-masked bases replaced with `N`
+    pub fn hard_mask(&self) -> Self {
+        let mut bases: PackedBases = self.bases.clone();
+        let n_runs: Runs = match &mut bases {
+            PackedBases::TwoBit(_) => merge_runs(&self.n_runs, &self.mask_runs),
+            PackedBases::FourBit(bytes) => {
+                for &(start, length) in &self.mask_runs {
+                    for position in start..start + length {
+                        let shift: u32 = ((1 - position % 2) * 4) as u32;
+                        let byte: &mut u8 = &mut bytes[position / 2];
+                        *byte = (*byte & !(0x0f << shift)) | (ANY_BASE << shift);
+                    }
+                }
+                Vec::new()
+            }
+        };
+        PackedSequence {
+            bases,
+            n_runs,
+            mask_runs: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Codes of every base
+    fn codes(&self) -> impl Iterator<Item = u8> + '_ {
+        let (bytes, bits) = match &self.bases {
+            PackedBases::TwoBit(bytes) => (bytes, 2),
+            PackedBases::FourBit(bytes) => (bytes, 4),
+        };
+        (0..self.length).map(move |position| code_at(bytes, position, bits))
+    }
+
+    /// The sequence with these reversed bases, and its runs reversed
+    fn reversed(&self, bases: PackedBases) -> Self {
+        let reverse = |runs: &Runs| -> Runs {
+            runs.iter()
+                .rev()
+                .map(|&(start, length)| (self.length - start - length, length))
+                .collect()
+        };
+        PackedSequence {
+            bases,
+            length: self.length,
+            n_runs: reverse(&self.n_runs),
+            mask_runs: reverse(&self.mask_runs),
+            is_rna: self.is_rna,
+        }
+    }
+}
+
+/// Two-bit code of `A`, `C`, `G` and `T` or `U`, in any case. Anything else is coded as `T`.
+fn two_bit_code_of(base: u8) -> u8 {
+    match base.to_ascii_uppercase() {
+        b'C' => 1,
+        b'A' => 2,
+        b'G' => 3,
+        _ => 0,
+    }
+}
+
+/// Four-bit code of an IUPAC code or a gap, in any case, `U` coded as `T`
+fn four_bit_code_of(base: u8) -> Option<u8> {
+    let base: u8 = match base.to_ascii_uppercase() {
+        b'U' => b'T',
+        base => base,
+    };
+    FOUR_BIT_BASES
+        .iter()
+        .position(|&code| code == base)
+        .map(|code| code as u8)
+}
+
+/// Packs the codes of some bases, `bits` bits each, the first base the highest
+fn pack_codes(sequence: &[u8], bits: usize, code_of: impl Fn(u8) -> u8) -> Vec<u8> {
+    let per_byte: usize = 8 / bits;
+    sequence
+        .chunks(per_byte)
+        .map(|chunk| {
+            chunk.iter().enumerate().fold(0_u8, |byte, (idx, &base)| {
+                byte | (code_of(base) << (8 - bits * (idx + 1)))
+            })
+        })
+        .collect()
+}
+
+/// Code of the base at `position`, of `bits` bits
+fn code_at(bytes: &[u8], position: usize, bits: usize) -> u8 {
+    let per_byte: usize = 8 / bits;
+    let shift: usize = 8 - bits * (position % per_byte + 1);
+    (bytes[position / per_byte] >> shift) & (0xff >> (8 - bits))
+}
+
+/// Number of each two-bit code from `start` to `end`. Whole bytes are counted at once.
+fn count_two_bit(bytes: &[u8], start: usize, end: usize) -> [usize; 4] {
+    let mut counts: [usize; 4] = [0; 4];
+    let mut position: usize = start;
+    while position < end {
+        if position.is_multiple_of(4) && position + 4 <= end {
+            let byte: u8 = bytes[position / 4];
+            for (code, count) in counts.iter_mut().enumerate() {
+                // Bases of this code xor to 00, which leaves their low bit set here
+                let xored: u8 = byte ^ (code as u8 * 0x55);
+                *count += (!(xored | (xored >> 1)) & 0x55).count_ones() as usize;
+            }
+            position += 4;
+        } else {
+            counts[code_at(bytes, position, 2) as usize] += 1;
+            position += 1;
+        }
+    }
+    counts
+}
+
+/// Reverses the order of the four two-bit codes of a byte
+fn reverse_two_bit(byte: u8) -> u8 {
+    let byte: u8 = byte.rotate_left(4);
+    ((byte & 0x33) << 2) | ((byte & 0xcc) >> 2)
+}
+
+/// Reverses packed bases: the bytes are reversed, each with `reverse_byte`, and shifted back to
+/// the highest bits, the padding after the last base being at the start now.
+fn reverse_bytes(
+    bytes: &[u8],
+    length: usize,
+    bits: usize,
+    reverse_byte: impl Fn(u8) -> u8,
+) -> Vec<u8> {
+    let reversed: Vec<u8> = bytes.iter().rev().map(|&byte| reverse_byte(byte)).collect();
+    let padding: usize = (bytes.len() * 8 - length * bits) % 8;
+    let mut shifted: Vec<u8> = shift_left(&reversed, padding);
+    clear_padding(&mut shifted, length, bits);
+    shifted
+}
+
+/// Packed bases from `start`, `length` of them
+fn slice_bytes(bytes: &[u8], start: usize, length: usize, bits: usize) -> Vec<u8> {
+    let per_byte: usize = 8 / bits;
+    let first: usize = start / per_byte;
+    let last: usize = (first + length.div_ceil(per_byte) + 1).min(bytes.len());
+    let mut sliced: Vec<u8> = shift_left(&bytes[first.min(last)..last], (start % per_byte) * bits);
+    sliced.truncate(length.div_ceil(per_byte));
+    clear_padding(&mut sliced, length, bits);
+    sliced
+}
+
+/// Shifts every bit `shift` places towards the start, less than a byte
+fn shift_left(bytes: &[u8], shift: usize) -> Vec<u8> {
+    match shift {
+        0 => bytes.to_vec(),
+        shift => (0..bytes.len())
+            .map(|idx| {
+                let next: u8 = bytes.get(idx + 1).copied().unwrap_or(0);
+                (bytes[idx] << shift) | (next >> (8 - shift))
+            })
+            .collect(),
+    }
+}
+
+/// Zeroes the bits after the last base, and drops the bytes past it
+fn clear_padding(bytes: &mut Vec<u8>, length: usize, bits: usize) {
+    bytes.truncate((length * bits).div_ceil(8));
+    let used: usize = (length * bits) % 8;
+    if let (Some(last), true) = (bytes.last_mut(), used > 0) {
+        *last &= 0xff << (8 - used);
+    }
+}
+
+/// Runs of the items that fulfill `predicate`
+fn runs_of<T>(items: &[T], predicate: impl Fn(&T) -> bool) -> Runs {
+    let mut runs: Runs = Vec::new();
+    for (position, item) in items.iter().enumerate() {
+        if !predicate(item) {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((start, length)) if *start + *length == position => *length += 1,
+            _ => runs.push((position, 1)),
+        }
+    }
+    runs
+}
+
+/// Tells, for increasing positions, whether they are in any of the runs
+fn covered(runs: &[(usize, usize)]) -> impl FnMut(usize) -> bool + '_ {
+    let mut runs = runs.iter().peekable();
+    move |position| {
+        while runs
+            .next_if(|&&(start, length)| start + length <= position)
+            .is_some()
+        {}
+        runs.peek().is_some_and(|&&(start, _)| start <= position)
+    }
+}
+
+/// The parts of the runs from `start` to `end`, from `start` on
+fn slice_runs(runs: &[(usize, usize)], start: usize, end: usize) -> Runs {
+    runs.iter()
+        .filter_map(|&(run_start, length)| {
+            let (from, to) = (run_start.max(start), (run_start + length).min(end));
+            (from < to).then(|| (from - start, to - from))
+        })
+        .collect()
+}
+
+/// Runs covering the bases in either `first` or `second`
+fn merge_runs(first: &[(usize, usize)], second: &[(usize, usize)]) -> Runs {
+    let mut all: Runs = first.iter().chain(second).copied().collect();
+    all.sort_unstable();
+    let mut merged: Runs = Vec::new();
+    for (start, length) in all {
+        match merged.last_mut() {
+            Some((last_start, last_length)) if *last_start + *last_length >= start => {
+                *last_length = (*last_length).max(start + length - *last_start);
+            }
+            _ => merged.push((start, length)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::domain::packed::{BaseCounts, PackedSequence};
+
+    #[test]
+    fn pack_and_unpack() {
+        for sequence in ["ACGTNNacgtnA", "acguuNN", "ACGRYkm-sWbdhvN", "", "ACGTA"] {
+            let packed: PackedSequence = PackedSequence::pack(sequence).unwrap();
+            assert_eq!(packed.unpack(), sequence);
+            assert_eq!(packed.len(), sequence.len());
+        }
+        assert!(PackedSequence::pack("ACGTU").is_none());
+        assert!(PackedSequence::pack("MKWVTFE*").is_none());
+    }
+
+    #[test]
+    fn pack_in_two_bits() {
+        let packed: PackedSequence = PackedSequence::pack("ACGRAN-acgtn").unwrap().to_two_bit();
+        assert_eq!(packed.unpack(), "ACGNANNacgtn");
+        assert_eq!(packed.n_runs(), [(3, 1), (5, 2), (11, 1)]);
+        assert_eq!(packed.mask_runs(), [(7, 5)]);
+
+        let bytes: Vec<u8> = packed.two_bit_bytes().unwrap().to_vec();
+        let window: PackedSequence = PackedSequence::from_two_bit(
+            bytes[1..].to_vec(),
+            4,
+            6,
+            packed.n_runs(),
+            packed.mask_runs(),
+        );
+        assert_eq!(window.unpack(), "ANNacg");
+    }
+
+    #[test]
+    fn reverse_and_complement() {
+        let packed: PackedSequence = PackedSequence::pack("ACGTNNacgtnAC").unwrap();
+        assert_eq!(packed.reverse().unpack(), "CAntgcaNNTGCA");
+        assert_eq!(packed.complement(false).unpack(), "TGCANNtgcanTG");
+        assert_eq!(packed.complement(true).unpack(), "UGCANNugcanUG");
+        assert_eq!(packed.reverse_complement(false).unpack(), "GTnacgtNNACGT");
+        let ambiguous: PackedSequence = PackedSequence::pack("ARYkm-bdN").unwrap();
+        assert_eq!(ambiguous.complement(false).unpack(), "TYRmk-vhN");
+        assert_eq!(ambiguous.reverse_complement(false).unpack(), "Nhv-kmRYT");
+    }
+
+    #[test]
+    fn slice_and_mask() {
+        let packed: PackedSequence = PackedSequence::pack("ACGTNNacgtnAC").unwrap();
+        assert_eq!(packed.slice(3, 9).unpack(), "TNNacg");
+        assert_eq!(packed.slice(5, 20).unpack(), "");
+        assert_eq!(packed.with_mask(false).unpack(), "ACGTNNACGTNAC");
+        assert_eq!(packed.with_mask(true).unpack(), "acgtnnacgtnac");
+        assert_eq!(packed.hard_mask().unpack(), "ACGTNNNNNNNAC");
+        let gapped: PackedSequence = PackedSequence::pack("AC-gr").unwrap();
+        assert_eq!(gapped.with_mask(true).hard_mask().unpack(), "NN-NN");
+        let ambiguous: PackedSequence = PackedSequence::pack("ARYkm-bdN").unwrap();
+        assert_eq!(ambiguous.slice(1, 6).unpack(), "RYkm-");
+        assert_eq!(ambiguous.slice(3, 9).reverse().unpack(), "Ndb-mk");
+    }
+
+    #[test]
+    fn count_bases() {
+        let packed: PackedSequence = PackedSequence::pack("ACGTNNacgtnAAAAAC").unwrap();
+        let counts: BaseCounts = packed.counts();
+        assert_eq!((counts.a, counts.c, counts.g, counts.t), (7, 3, 2, 2));
+        assert_eq!(counts.n, 3);
+        assert_eq!(counts, packed.complement(false).complement(false).counts());
+        let ambiguous: PackedSequence = PackedSequence::pack("ARYN-u").unwrap();
+        assert_eq!(ambiguous.counts(), BaseCounts::from("ARYN-u"));
+        assert_eq!(BaseCounts::from("ARYN-ux").other, 1);
+    }
+
+    #[test]
+    fn extract_kmers() {
+        let packed: PackedSequence = PackedSequence::pack("ACGTNACGa").unwrap();
+        let kmers: Vec<(usize, u64)> = packed.kmers(3).collect();
+        let expected: Vec<(usize, u64)> = [(0, "ACG"), (1, "CGT"), (5, "ACG"), (6, "CGA")]
+            .into_iter()
+            .map(|(position, bases)| (position, PackedSequence::kmer_of(bases).unwrap()))
+            .collect();
+        assert_eq!(kmers, expected);
+        let ambiguous: PackedSequence = PackedSequence::pack("AUGRAUG").unwrap();
+        let positions: Vec<usize> = ambiguous.kmers(3).map(|(position, _)| position).collect();
+        assert_eq!(positions, [0, 4]);
+        assert_eq!(
+            PackedSequence::kmer_of("AUG"),
+            PackedSequence::kmer_of("atg")
+        );
+        assert_eq!(PackedSequence::kmer_of("ANG"), None);
+        let long: &str = "ACGTTGCAacgtNNNNCCGGTTAAGCTAGCTAGGATCCAAGTCNACGTTGCATGCATGCAAGCTTGGA";
+        for k in [1, 2, 5, 8, 13, 32] {
+            let expected: Vec<(usize, u64)> = (0..=long.len().saturating_sub(k))
+                .filter_map(|start| {
+                    PackedSequence::kmer_of(&long[start..start + k]).map(|kmer| (start, kmer))
+                })
+                .collect();
+            let packed: PackedSequence = PackedSequence::pack(long).unwrap();
+            assert_eq!(packed.kmers(k).collect::<Vec<_>>(), expected);
+            let ambiguous: String = long.replace("TCN", "TCR");
+            let packed: PackedSequence = PackedSequence::pack(&ambiguous).unwrap();
+            assert_eq!(packed.kmers(k).collect::<Vec<_>>(), expected);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Any sequence record the toolkit reads: fasta or fastq
+use super::{
+    fasta::{Case, Fasta, FastaHeader, FastaSequence, MaskMode, Strand},
+    fastq::{Fastq, FastqQuality},
+};
+
+/// A record as read from a file, keeping qualities if the file had them.
+#[derive(Clone)]
+pub enum SequenceRecord {
+    Fasta(Fasta),
+    Fastq(Fastq),
+}
+
+impl From<Fasta> for SequenceRecord {
+    fn from(value: Fasta) -> Self {
+        SequenceRecord::Fasta(value)
+    }
+}
+
+impl From<Fastq> for SequenceRecord {
+    fn from(value: Fastq) -> Self {
+        SequenceRecord::Fastq(value)
+    }
+}
+
+/// Drops the qualities, if any
+impl From<SequenceRecord> for Fasta {
+    fn from(value: SequenceRecord) -> Self {
+        match value {
+            SequenceRecord::Fasta(fasta) => fasta,
+            SequenceRecord::Fastq(fastq) => Fasta::from(fastq),
+        }
+    }
+}
+
+impl SequenceRecord {
+    pub fn header(&self) -> &FastaHeader {
+        match self {
+            SequenceRecord::Fasta(fasta) => &fasta.header,
+            SequenceRecord::Fastq(fastq) => &fastq.header,
+        }
+    }
+
+    pub fn sequence(&self) -> &FastaSequence {
+        match self {
+            SequenceRecord::Fasta(fasta) => &fasta.sequence,
+            SequenceRecord::Fastq(fastq) => &fastq.sequence,
+        }
+    }
+
+    /// Qualities of the record, if it is fastq
+    pub fn quality(&self) -> Option<&FastqQuality> {
+        match self {
+            SequenceRecord::Fasta(_) => None,
+            SequenceRecord::Fastq(fastq) => Some(&fastq.quality),
+        }
+    }
+
+    pub fn complement(&self) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.complement()),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.complement()),
+        }
+    }
+
+    pub fn reverse(&self) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.reverse()),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.reverse()),
+        }
+    }
+
+    pub fn reverse_complement(&self) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.reverse_complement()),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.reverse_complement()),
+        }
+    }
+
+    pub fn transcribe(&self, strand: Strand) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.transcribe(strand)),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.transcribe(strand)),
+        }
+    }
+
+    pub fn reverse_transcribe(&self, strand: Strand) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.reverse_transcribe(strand)),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.reverse_transcribe(strand)),
+        }
+    }
+
+    pub fn cut(&self, start: usize, end: usize) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.cut(start, end)),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.cut(start, end)),
+        }
+    }
+
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.slice(start, end)),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.slice(start, end)),
+        }
+    }
+
+    pub fn with_case(&self, case: Case) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.with_case(case)),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.with_case(case)),
+        }
+    }
+
+    pub fn mask(&self, mode: MaskMode) -> Self {
+        match self {
+            SequenceRecord::Fasta(fasta) => SequenceRecord::Fasta(fasta.mask(mode)),
+            SequenceRecord::Fastq(fastq) => SequenceRecord::Fastq(fastq.mask(mode)),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Regions of a sequence, as in `chr1:1000-2000`
+use core::fmt;
+use std::{fmt::Display, str::FromStr};
+
+use super::errors::FastaError;
+
+/// A range of bases of a named sequence. Positions are 1-based and inclusive, as samtools takes
+/// them. Missing positions mean the start or end of the sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub name: String,
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+}
+
+impl FromStr for Region {
+    type Err = FastaError;
+
+    /// Parses a region without knowing the names of the sequences, as `Region::parse` does when
+    /// none of them is the region. Names with `:` are taken as ranges, unless they are written
+    /// between braces.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Region::parse(value, |_| false)
+    }
+}
+
+impl Region {
+    /// Parses `name`, `name:start`, `name:start-` or `name:start-end`, as samtools does. Thousands
+    /// separators (`,`) are allowed in positions. Names may have `:` themselves, as in
+    /// `HLA-A*01:01:01:01`: the whole region is taken as a name if `is_name` tells it is one, and
+    /// is split at its last `:` otherwise, if it ends in a range. Regions that are both a name
+    /// and a range of another are an error; `{name}` and `{name}:start-end` write them apart.
+    pub fn parse(value: &str, is_name: impl Fn(&str) -> bool) -> Result<Self, FastaError> {
+        let value: &str = value.trim();
+        let invalid = |reason: String| FastaError::InvalidRegion {
+            region: value.to_string(),
+            reason,
+        };
+        if value.is_empty() {
+            return Err(invalid("it is empty".to_string()));
+        }
+        if let Some((name, rest)) = value
+            .strip_prefix('{')
+            .and_then(|braced| braced.split_once('}'))
+        {
+            let (start, end) = match rest {
+                "" => (None, None),
+                _ => match rest.strip_prefix(':').and_then(parse_range) {
+                    Some(range) => {
+                        let (start, end) = range.map_err(invalid)?;
+                        (Some(start), end)
+                    }
+                    None => return Err(invalid(format!("\"{}\" is not a range", rest))),
+                },
+            };
+            return Ok(Region::new(name, start, end));
+        }
+        let ranged = value
+            .rsplit_once(':')
+            .and_then(|(name, range)| Some((name, parse_range(range)?)));
+        match (is_name(value), ranged) {
+            (true, Some((name, Ok(_)))) if is_name(name) => Err(invalid(format!(
+                "it is both a sequence and a range of {}. Write {{{}}} for the sequence",
+                name, value
+            ))),
+            (false, Some((name, range))) => {
+                let (start, end) = range.map_err(invalid)?;
+                Ok(Region::new(name, Some(start), end))
+            }
+            _ => Ok(Region::new(value, None, None)),
+        }
+    }
+
+    /// The 0-based, end-exclusive range of bases this region covers in a sequence of `length`
+    /// bases. Empty if the region falls outside of the sequence.
+    pub fn range(&self, length: usize) -> (usize, usize) {
+        let start: usize = self.start.map_or(0, |start| start - 1).min(length);
+        let end: usize = self.end.unwrap_or(length).min(length);
+        (start, end.max(start))
+    }
+
+    fn new(name: &str, start: Option<usize>, end: Option<usize>) -> Self {
+        Region {
+            name: name.to_string(),
+            start,
+            end,
+        }
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => write!(f, ":{}-{}", start, end),
+            (Some(start), None) => write!(f, ":{}", start),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Start of a range, and its end, if it has one
+type Range = (usize, Option<usize>);
+
+/// Parses `start`, `start-` or `start-end`. `None` if it does not look like a range at all, and an
+/// error if it does, but its positions are not valid.
+fn parse_range(range: &str) -> Option<Result<Range, String>> {
+    let range: String = range.replace(',', "");
+    let (start, end) = range.split_once('-').unwrap_or((&range, ""));
+    let is_number = |position: &str| {
+        !position.is_empty() && position.bytes().all(|digit| digit.is_ascii_digit())
+    };
+    if !is_number(start) || !(end.is_empty() || is_number(end)) {
+        return None;
+    }
+    let start: Result<usize, String> = parse_position(start);
+    let end: Result<Option<usize>, String> = match end {
+        "" => Ok(None),
+        end => parse_position(end).map(Some),
+    };
+    Some(match (start, end) {
+        (Ok(start), Ok(Some(end))) if end < start => Err("it ends before it starts".to_string()),
+        (Ok(start), Ok(end)) => Ok((start, end)),
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    })
+}
+
+fn parse_position(position: &str) -> Result<usize, String> {
+    match position.parse::<usize>() {
+        Ok(0) => Err("positions start at 1".to_string()),
+        Ok(position) => Ok(position),
+        Err(_) => Err(format!("\"{}\" is not a position", position)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::domain::region::Region;
+
+    #[test]
+    fn parse_regions() {
+        let whole: Region = "chr1".parse().unwrap();
+        assert_eq!((whole.start, whole.end), (None, None));
+        let range: Region = "chr1:1,000-2,000".parse().unwrap();
+        assert_eq!(range.name, "chr1");
+        assert_eq!((range.start, range.end), (Some(1000), Some(2000)));
+        let open: Region = "HLA-A*01:01:5-".parse().unwrap();
+        assert_eq!(open.name, "HLA-A*01:01");
+        assert_eq!((open.start, open.end), (Some(5), None));
+        assert!("chr1:0-10".parse::<Region>().is_err());
+        assert!("chr1:20-10".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn parse_names_with_colons() {
+        let names: [&str; 3] = ["HLA-A*01:01:01:01", "chr1", "chr1:1-5"];
+        let parse = |region: &str| Region::parse(region, |name| names.contains(&name));
+        let whole: Region = parse("HLA-A*01:01:01:01").unwrap();
+        assert_eq!(whole.name, "HLA-A*01:01:01:01");
+        assert_eq!((whole.start, whole.end), (None, None));
+        let range: Region = parse("HLA-A*01:01:01:01:3-8").unwrap();
+        assert_eq!(range.name, "HLA-A*01:01:01:01");
+        assert_eq!((range.start, range.end), (Some(3), Some(8)));
+        assert!(parse("chr1:1-5").is_err());
+        let braced: Region = parse("{chr1:1-5}").unwrap();
+        assert_eq!((braced.name.as_str(), braced.start), ("chr1:1-5", None));
+        let braced: Region = parse("{chr1:1-5}:2").unwrap();
+        assert_eq!((braced.name.as_str(), braced.start), ("chr1:1-5", Some(2)));
+        let missing: Region = parse("chrX:abc").unwrap();
+        assert_eq!((missing.name.as_str(), missing.start), ("chrX:abc", None));
+    }
+
+    #[test]
+    fn region_range() {
+        let region: Region = "chr1:3-6".parse().unwrap();
+        assert_eq!(region.range(10), (2, 6));
+        assert_eq!(region.range(4), (2, 4));
+        assert_eq!(region.range(1), (1, 1));
+        assert_eq!(region.to_string(), "chr1:3-6");
+    }
+}
@@ -0,0 +1,174 @@
+//! Adapter and quality trimming of reads
+use super::record::SequenceRecord;
+
+/// Settings for trimming reads. Steps run in this order: adapter removal, leading and trailing
+/// quality trimming, sliding window trimming and, last, length filtering. Quality steps are
+/// skipped for records without qualities.
+#[derive(Debug, Clone, Default)]
+pub struct Trimmer {
+    /// Adapters to remove, along with anything after them
+    pub adapters: Vec<String>,
+    /// Minimum number of bases of an adapter that must match at the end of a read to be removed
+    pub min_overlap: usize,
+    /// Bases at the start of the read under this quality are removed
+    pub leading: Option<u8>,
+    /// Bases at the end of the read under this quality are removed
+    pub trailing: Option<u8>,
+    /// Window size and mean quality: the read is cut where the mean quality of a window first
+    /// drops under the threshold
+    pub window: Option<(usize, f64)>,
+    /// Reads shorter than this after trimming are dropped
+    pub min_length: usize,
+}
+
+impl Trimmer {
+    /// Trims a record. `None` if the record is dropped for being too short.
+    pub fn trim(&self, record: &SequenceRecord) -> Option<SequenceRecord> {
+        let (start, end) = self.keep_range(
+            &record.sequence().as_str(),
+            record.quality().map(|quality| quality.scores()),
+        );
+        if end - start < self.min_length.max(1) {
+            return None;
+        }
+        match (start, end) == (0, record.sequence().len()) {
+            true => Some(record.clone()),
+            false => Some(record.slice(start, end)),
+        }
+    }
+
+    /// Range of bases that survive trimming
+    fn keep_range(&self, sequence: &str, quality: Option<&[u8]>) -> (usize, usize) {
+        let mut start: usize = 0;
+        let mut end: usize = self.adapter_start(sequence);
+        let Some(quality) = quality else {
+            return (start, end);
+        };
+        if let Some(leading) = self.leading {
+            while start < end && quality[start] < leading {
+                start += 1;
+            }
+        }
+        if let Some(trailing) = self.trailing {
+            while end > start && quality[end - 1] < trailing {
+                end -= 1;
+            }
+        }
+        if let Some((size, threshold)) = self.window {
+            if size > 0 && end - start >= size {
+                let low_window: Option<usize> = (start..=end - size).find(|&i| {
+                    let sum: u32 = quality[i..i + size].iter().map(|&q| q as u32).sum();
+                    (sum as f64 / size as f64) < threshold
+                });
+                if let Some(i) = low_window {
+                    end = i;
+                }
+            }
+        }
+        (start, end)
+    }
+
+    /// Position where the first adapter starts, either whole inside the read or partially at its
+    /// end. The length of the sequence if there is none.
+    fn adapter_start(&self, sequence: &str) -> usize {
+        // Compared as bytes, so that no adapter is cut inside a character.
+        let sequence: String = sequence.to_ascii_lowercase();
+        let sequence: &[u8] = sequence.as_bytes();
+        self.adapters
+            .iter()
+            .filter(|adapter| !adapter.is_empty())
+            .filter_map(|adapter| {
+                let adapter: String = adapter.to_ascii_lowercase();
+                let adapter: &[u8] = adapter.as_bytes();
+                if let Some(position) = sequence
+                    .windows(adapter.len())
+                    .position(|window| window == adapter)
+                {
+                    return Some(position);
+                }
+                let longest: usize = (adapter.len() - 1).min(sequence.len());
+                (self.min_overlap.max(1)..=longest)
+                    .rev()
+                    .find(|&overlap| sequence.ends_with(&adapter[..overlap]))
+                    .map(|overlap| sequence.len() - overlap)
+            })
+            .min()
+            .unwrap_or(sequence.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::domain::{
+        fasta::Fasta,
+        fastq::{Fastq, FastqQuality, PhredOffset},
+        record::SequenceRecord,
+        trim::Trimmer,
+    };
+
+    fn read(sequence: &str, quality: &str) -> SequenceRecord {
+        let quality: FastqQuality = FastqQuality::from_ascii(quality, PhredOffset::Phred33);
+        SequenceRecord::from(Fastq::from(("read", sequence, quality)))
+    }
+
+    #[test]
+    fn trim_leading_and_trailing() {
+        let trimmer: Trimmer = Trimmer {
+            leading: Some(20),
+            trailing: Some(20),
+            ..Default::default()
+        };
+        let trimmed = trimmer.trim(&read("acgtacgt", "##IIII##")).unwrap();
+        assert_eq!(trimmed.sequence().as_str(), "gtac");
+        assert_eq!(trimmed.quality().unwrap().to_string(), "IIII");
+        assert_eq!(trimmed.header().as_str(), "read");
+    }
+
+    #[test]
+    fn trim_sliding_window() {
+        let trimmer: Trimmer = Trimmer {
+            window: Some((2, 20_f64)),
+            ..Default::default()
+        };
+        let trimmed = trimmer.trim(&read("acgtacgt", "IIII5#II")).unwrap();
+        assert_eq!(trimmed.sequence().as_str(), "acgt");
+    }
+
+    #[test]
+    fn remove_adapters() {
+        let trimmer: Trimmer = Trimmer {
+            adapters: vec!["AGATCGGAAG".to_string()],
+            min_overlap: 3,
+            ..Default::default()
+        };
+        let whole = SequenceRecord::from(Fasta::from(("read", "ccccagatcggaagtttt")));
+        assert_eq!(trimmer.trim(&whole).unwrap().sequence().as_str(), "cccc");
+        let partial = SequenceRecord::from(Fasta::from(("read", "ccccagatc")));
+        assert_eq!(trimmer.trim(&partial).unwrap().sequence().as_str(), "cccc");
+        let too_short = SequenceRecord::from(Fasta::from(("read", "ccccag")));
+        assert_eq!(
+            trimmer.trim(&too_short).unwrap().sequence().as_str(),
+            "ccccag"
+        );
+        let accented: Trimmer = Trimmer {
+            adapters: vec!["ágatc".to_string()],
+            min_overlap: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            accented.trim(&partial).unwrap().sequence().as_str(),
+            "ccccagatc"
+        );
+    }
+
+    #[test]
+    fn drop_short_reads() {
+        let trimmer: Trimmer = Trimmer {
+            trailing: Some(20),
+            min_length: 5,
+            ..Default::default()
+        };
+        assert!(trimmer.trim(&read("acgtacgt", "IIII####")).is_none());
+        assert!(trimmer.trim(&read("acgtacgt", "IIIII###")).is_some());
+    }
+}
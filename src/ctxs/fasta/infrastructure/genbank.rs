@@ -0,0 +1,351 @@
+//! GenBank flat files, as NCBI writes them: records of a header, a feature table and the bases
+//! after `ORIGIN`, each ending with `//`.
+use std::{io::BufRead, path::Path};
+
+use crate::{
+    ctxs::fasta::domain::{
+        alphabet::Alphabet,
+        errors::FastaError,
+        fasta::Fasta,
+        genbank::{Feature, GenBankRecord, Location},
+    },
+    shared::infrastructure::io::open_input,
+};
+
+type Result<T> = std::result::Result<T, FastaError>;
+
+/// Whether some bytes are the start of a GenBank file
+pub fn is_genbank(bytes: &[u8]) -> bool {
+    bytes.trim_ascii_start().starts_with(b"LOCUS ")
+}
+
+/// Section of a record a line belongs to, by the keyword it starts with
+enum Section {
+    Definition,
+    Features,
+    Origin,
+    Other,
+}
+
+/// A feature being read, its location and qualifiers as written
+struct PendingFeature {
+    kind: String,
+    location: String,
+    qualifiers: Vec<(String, Option<String>)>,
+    /// Line the feature starts at
+    line: usize,
+}
+
+impl PendingFeature {
+    /// Whether the last qualifier is a quoted value missing its closing quote
+    fn is_open(&self) -> bool {
+        match self.qualifiers.last() {
+            Some((_, Some(value))) => {
+                value.starts_with('"') && (value.len() == 1 || value.matches('"').count() % 2 == 1)
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds a line to the location or to the last qualifier
+    fn continue_with(&mut self, text: &str) {
+        match self.qualifiers.last_mut() {
+            Some((key, Some(value))) => {
+                if key != "translation" {
+                    value.push(' ');
+                }
+                value.push_str(text);
+            }
+            Some((_, None)) => {}
+            None => self.location.push_str(text),
+        }
+    }
+
+    fn finish(self) -> Result<Feature> {
+        let location: Location =
+            self.location
+                .parse()
+                .map_err(|e: FastaError| FastaError::InvalidGenBank {
+                    line: self.line,
+                    reason: e.to_string(),
+                })?;
+        let qualifiers: Vec<(String, Option<String>)> = self
+            .qualifiers
+            .into_iter()
+            .map(|(key, value)| (key, value.map(|value| unquote(&value))))
+            .collect();
+        Ok(Feature {
+            kind: self.kind,
+            location,
+            qualifiers,
+        })
+    }
+}
+
+/// A qualifier value without its quotes, quotes inside it unescaped
+fn unquote(value: &str) -> String {
+    match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => value.to_string(),
+    }
+}
+
+/// Reads GenBank records one at a time from any buffered source. The sequence of every record is
+/// named after its accession and version, described by its definition, and written in uppercase.
+pub struct GenBankReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    /// Alphabet to take every sequence as, instead of the one its letters look like
+    alphabet: Option<Alphabet>,
+    done: bool,
+}
+
+impl GenBankReader<Box<dyn BufRead>> {
+    /// Opens a file for reading, or the standard input if `file` is `-` or missing.
+    pub fn open(file: Option<&Path>) -> anyhow::Result<Self> {
+        Ok(GenBankReader::new(open_input(file)?))
+    }
+}
+
+impl<R: BufRead> GenBankReader<R> {
+    pub fn new(reader: R) -> Self {
+        GenBankReader {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            alphabet: None,
+            done: false,
+        }
+    }
+
+    /// Takes every sequence as `alphabet`, if given.
+    pub fn with_alphabet(mut self, alphabet: Option<Alphabet>) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Reads the next line into `self.line`, without its line break. `false` at the end.
+    fn read_line(&mut self) -> Result<bool> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(false);
+        }
+        self.line_number += 1;
+        let length: usize = self.line.trim_end_matches(['\n', '\r']).len();
+        self.line.truncate(length);
+        Ok(true)
+    }
+
+    fn invalid(&self, reason: &str) -> FastaError {
+        FastaError::InvalidGenBank {
+            line: self.line_number,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<GenBankRecord>> {
+        let locus: String = loop {
+            if !self.read_line()? {
+                return Ok(None);
+            }
+            if self.line.trim().is_empty() {
+                continue;
+            }
+            match self.line.strip_prefix("LOCUS ") {
+                Some(locus) => break locus.split_whitespace().next().unwrap_or("").to_string(),
+                None => return Err(self.invalid("expected a LOCUS line")),
+            }
+        };
+        let mut definition: String = String::new();
+        let mut accession: Option<String> = None;
+        let mut version: Option<String> = None;
+        let mut features: Vec<Feature> = Vec::new();
+        let mut pending: Option<PendingFeature> = None;
+        let mut sequence: String = String::new();
+        let mut section: Section = Section::Other;
+        loop {
+            if !self.read_line()? {
+                return Err(self.invalid("the record does not end with //"));
+            }
+            let line: &str = self.line.as_str();
+            if line.starts_with("//") {
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !line.starts_with(' ') {
+                let (keyword, value) = line.split_once(' ').unwrap_or((line, ""));
+                let value: &str = value.trim();
+                section = match keyword {
+                    "DEFINITION" => Section::Definition,
+                    "FEATURES" => Section::Features,
+                    "ORIGIN" => Section::Origin,
+                    _ => Section::Other,
+                };
+                match keyword {
+                    "DEFINITION" => definition = value.to_string(),
+                    "ACCESSION" => accession = value.split_whitespace().next().map(String::from),
+                    "VERSION" => version = value.split_whitespace().next().map(String::from),
+                    _ => {}
+                }
+                continue;
+            }
+            match section {
+                Section::Definition => {
+                    definition.push(' ');
+                    definition.push_str(line.trim());
+                }
+                Section::Features => {
+                    let text: &str = line.trim();
+                    // Keys start at the sixth column, and qualifiers further right.
+                    let is_key: bool = line.bytes().position(|b| b != b' ') == Some(5);
+                    let is_open: bool = pending.as_ref().is_some_and(PendingFeature::is_open);
+                    if is_key && !is_open {
+                        if let Some(feature) = pending.take() {
+                            features.push(feature.finish()?);
+                        }
+                        let (kind, location) = text.split_once(' ').unwrap_or((text, ""));
+                        pending = Some(PendingFeature {
+                            kind: kind.to_string(),
+                            location: location.trim().to_string(),
+                            qualifiers: Vec::new(),
+                            line: self.line_number,
+                        });
+                        continue;
+                    }
+                    let Some(feature) = pending.as_mut() else {
+                        return Err(self.invalid("expected a feature key"));
+                    };
+                    match text.strip_prefix('/') {
+                        Some(qualifier) if !is_open => {
+                            let (key, value) = match qualifier.split_once('=') {
+                                Some((key, value)) => (key, Some(value.to_string())),
+                                None => (qualifier, None),
+                            };
+                            feature.qualifiers.push((key.to_string(), value));
+                        }
+                        _ => feature.continue_with(text),
+                    }
+                }
+                Section::Origin => sequence.extend(
+                    line.chars()
+                        .filter(|c| c.is_ascii_alphabetic())
+                        .map(|c| c.to_ascii_uppercase()),
+                ),
+                Section::Other => {}
+            }
+        }
+        if let Some(feature) = pending.take() {
+            features.push(feature.finish()?);
+        }
+        let name: String = version.or(accession).unwrap_or(locus);
+        let definition: &str = definition.trim_end_matches('.');
+        let header: String = match definition.is_empty() {
+            true => name,
+            false => format!("{} {}", name, definition),
+        };
+        let fasta: Fasta = Fasta::from((header, sequence));
+        Ok(Some(GenBankRecord {
+            fasta: match self.alphabet {
+                Some(alphabet) => fasta.with_alphabet(alphabet),
+                None => fasta,
+            },
+            features,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for GenBankReader<R> {
+    type Item = Result<GenBankRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record: Result<Option<GenBankRecord>> = self.read_record();
+        self.done = !matches!(record, Ok(Some(_)));
+        record.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::{
+        domain::genbank::GenBankRecord,
+        infrastructure::genbank::{is_genbank, GenBankReader},
+    };
+
+    const RECORD: &str = "\
+LOCUS       TEST01                    24 bp    DNA     linear   BCT 01-JAN-2024
+DEFINITION  Test organism chromosome,
+            complete sequence.
+ACCESSION   TEST01
+VERSION     TEST01.2
+FEATURES             Location/Qualifiers
+     source          1..24
+                     /organism=\"Test organism\"
+                     /mol_type=\"genomic DNA\"
+     gene            complement(join(3..8,
+                     13..24))
+                     /gene=\"abc\"
+     CDS             complement(join(3..8,13..24))
+                     /gene=\"abc\"
+                     /pseudo
+                     /note=\"a \"\"quoted\"\" note
+                     over two lines\"
+                     /translation=\"MKV
+                     LS\"
+ORIGIN
+        1 atgaaagtat tgagccatga aaac
+//
+";
+
+    #[test]
+    fn read_genbank() {
+        assert!(is_genbank(b"\nLOCUS       TEST01"));
+        let text: String = format!("{}\n{}", RECORD, RECORD.replace("TEST01.2", "TEST02.1"));
+        let records: Vec<GenBankRecord> = GenBankReader::new(text.as_bytes())
+            .map(|record| record.unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        let record: &GenBankRecord = &records[0];
+        assert_eq!(
+            record.fasta.to_string(),
+            "> TEST01.2 Test organism chromosome, complete sequence\nATGAAAGTATTGAGCCATGAAAAC\n"
+        );
+        assert_eq!(records[1].seqid(), "TEST02.1");
+        assert_eq!(record.features.len(), 3);
+        let gene = &record.features[1];
+        assert_eq!(gene.location, record.features[2].location);
+        assert_eq!(gene.location.to_string(), "complement(join(3..8,13..24))");
+        let cds = &record.features[2];
+        assert_eq!(
+            cds.qualifiers,
+            [
+                ("gene".to_string(), Some("abc".to_string())),
+                ("pseudo".to_string(), None),
+                (
+                    "note".to_string(),
+                    Some("a \"quoted\" note over two lines".to_string())
+                ),
+                ("translation".to_string(), Some("MKVLS".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_invalid_genbank() {
+        let unfinished: &str = RECORD.trim_end().trim_end_matches("//");
+        let bad_location: String = RECORD.replace("1..24", "1..x");
+        for text in [unfinished, "ID   not genbank", &bad_location] {
+            let mut reader = GenBankReader::new(text.as_bytes());
+            assert!(reader.next().unwrap().is_err());
+            assert!(reader.next().is_none());
+        }
+    }
+}
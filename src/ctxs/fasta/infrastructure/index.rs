@@ -0,0 +1,238 @@
+//! Samtools-compatible `.fai` index, for random access to the sequences of a fasta file.
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ctxs::fasta::domain::{errors::FastaError, region::Region},
+    shared::infrastructure::compression::{decompress, Compression},
+};
+
+type Result<T> = std::result::Result<T, FastaError>;
+
+/// Position of one sequence in the indexed file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaiEntry {
+    /// First word of the header
+    pub name: String,
+    /// Number of bases
+    pub length: usize,
+    /// Byte offset of the first base
+    pub offset: u64,
+    /// Bases per line
+    pub line_bases: usize,
+    /// Bytes per line, line terminator included
+    pub line_width: usize,
+}
+
+impl FaiEntry {
+    /// Byte offset of the base at 0-based `position`.
+    fn byte_offset(&self, position: usize) -> u64 {
+        let line_bases: usize = self.line_bases.max(1);
+        self.offset + ((position / line_bases) * self.line_width + position % line_bases) as u64
+    }
+}
+
+/// The index of a fasta file: one entry per record.
+#[derive(Debug, Default)]
+pub struct FastaIndex {
+    pub entries: Vec<FaiEntry>,
+}
+
+impl FastaIndex {
+    /// Path of the index of a given fasta file: the same path with `.fai` appended.
+    pub fn path_for(file: &Path) -> PathBuf {
+        let mut index_path = file.as_os_str().to_owned();
+        index_path.push(".fai");
+        PathBuf::from(index_path)
+    }
+
+    /// Indexes an uncompressed or BGZF fasta file. Offsets of BGZF files are in the uncompressed
+    /// bytes, as samtools writes them, to be read with the `.gzi` index of the file.
+    pub fn build_from(file: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(open(file)?);
+        match Compression::from_magic(reader.fill_buf()?) {
+            Compression::None => FastaIndex::build(reader),
+            Compression::Bgzf => FastaIndex::build(decompress(reader)?),
+            Compression::Gzip | Compression::Zstd => {
+                Err(FastaError::CompressedIndex(file.to_path_buf()))
+            }
+        }
+    }
+
+    /// Indexes fasta read from `reader`. Every line of a record but the last must have the same
+    /// length, or the record could not be seeked into.
+    pub fn build<R: BufRead>(mut reader: R) -> Result<Self> {
+        let mut entries: Vec<FaiEntry> = Vec::new();
+        let mut current: Option<FaiEntry> = None;
+        let mut short_line_seen: bool = false;
+        let mut position: u64 = 0;
+        let mut line: Vec<u8> = Vec::new();
+        loop {
+            line.clear();
+            let width: usize = reader.read_until(b'\n', &mut line)?;
+            if width == 0 {
+                break;
+            }
+            position += width as u64;
+            if let Some(header) = line.strip_prefix(b">") {
+                entries.extend(current.take());
+                let header: String = String::from_utf8_lossy(header).to_string();
+                let name: &str = header.split_whitespace().next().unwrap_or("");
+                current = Some(FaiEntry {
+                    name: name.to_string(),
+                    length: 0,
+                    offset: position,
+                    line_bases: 0,
+                    line_width: 0,
+                });
+                short_line_seen = false;
+                continue;
+            }
+            let bases: usize = line.iter().filter(|&&b| b != b'\n' && b != b'\r').count();
+            let Some(entry) = current.as_mut() else {
+                if bases == 0 {
+                    continue;
+                }
+                return Err(FastaError::SequenceBeforeHeader);
+            };
+            if bases == 0 {
+                short_line_seen = true;
+                continue;
+            }
+            if entry.line_bases == 0 {
+                entry.line_bases = bases;
+                entry.line_width = width;
+            } else if short_line_seen || bases > entry.line_bases {
+                return Err(FastaError::UnevenLines {
+                    name: entry.name.clone(),
+                });
+            }
+            if bases < entry.line_bases || width != entry.line_width {
+                short_line_seen = true;
+            }
+            entry.length += bases;
+        }
+        entries.extend(current);
+        Ok(FastaIndex { entries })
+    }
+
+    /// Reads an index file.
+    pub fn load(file: &Path) -> Result<Self> {
+        let reader = BufReader::new(open(file)?);
+        let mut entries: Vec<FaiEntry> = Vec::new();
+        for line in reader.lines() {
+            let line: String = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let malformed = || FastaError::MalformedIndex(line.clone());
+            if fields.len() < 5 {
+                return Err(malformed());
+            }
+            entries.push(FaiEntry {
+                name: fields[0].to_string(),
+                length: fields[1].parse().map_err(|_| malformed())?,
+                offset: fields[2].parse().map_err(|_| malformed())?,
+                line_bases: fields[3].parse().map_err(|_| malformed())?,
+                line_width: fields[4].parse().map_err(|_| malformed())?,
+            });
+        }
+        Ok(FastaIndex { entries })
+    }
+
+    /// Writes the index in `.fai` format.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.name, entry.length, entry.offset, entry.line_bases, entry.line_width
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Saves the index to a file.
+    pub fn save(&self, file: &Path) -> Result<()> {
+        let mut writer =
+            BufWriter::new(File::create(file).map_err(|source| FastaError::Create {
+                path: file.to_path_buf(),
+                source,
+            })?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads the bases of `region` from the indexed source, seeking straight to them.
+    pub fn fetch<R: Read + Seek>(&self, reader: &mut R, region: &Region) -> Result<String> {
+        let entry: &FaiEntry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == region.name)
+            .ok_or_else(|| FastaError::MissingSequence(region.name.clone()))?;
+        let (start, end) = region.range(entry.length);
+        if start == end {
+            return Ok(String::new());
+        }
+        let byte_start: u64 = entry.byte_offset(start);
+        let byte_end: u64 = entry.byte_offset(end - 1) + 1;
+        reader.seek(SeekFrom::Start(byte_start))?;
+        let mut bytes: Vec<u8> = vec![0; (byte_end - byte_start) as usize];
+        reader.read_exact(&mut bytes)?;
+        bytes.retain(|&b| b != b'\n' && b != b'\r');
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+}
+
+fn open(file: &Path) -> Result<File> {
+    File::open(file).map_err(|source| FastaError::Open {
+        path: file.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::ctxs::fasta::infrastructure::index::FastaIndex;
+
+    const FASTA: &str = ">chr1 first\nACGTA\nCGTAC\nGT\n>chr2\r\nAAAC\r\nCC\r\n";
+
+    #[test]
+    fn build_index() {
+        let index: FastaIndex = FastaIndex::build(FASTA.as_bytes()).unwrap();
+        let mut written: Vec<u8> = Vec::new();
+        index.write_to(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "chr1\t12\t12\t5\t6\nchr2\t6\t34\t4\t6\n"
+        );
+    }
+
+    #[test]
+    fn reject_uneven_lines() {
+        assert!(FastaIndex::build(">chr1\nACG\nACGTA\n".as_bytes()).is_err());
+        assert!(FastaIndex::build(">chr1\nACGTA\nACG\nACG\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn fetch_regions() {
+        let index: FastaIndex = FastaIndex::build(FASTA.as_bytes()).unwrap();
+        let mut reader = Cursor::new(FASTA.as_bytes());
+        let fetch = |region: &str, reader: &mut Cursor<&[u8]>| {
+            index.fetch(reader, &region.parse().unwrap()).unwrap()
+        };
+        assert_eq!(fetch("chr1", &mut reader), "ACGTACGTACGT");
+        assert_eq!(fetch("chr1:4-7", &mut reader), "TACG");
+        assert_eq!(fetch("chr1:11", &mut reader), "GT");
+        assert_eq!(fetch("chr2:3-6", &mut reader), "ACCC");
+        assert_eq!(fetch("chr2:7-9", &mut reader), "");
+        assert!(index.fetch(&mut reader, &"chr3".parse().unwrap()).is_err());
+    }
+}
@@ -1,11 +1,46 @@
-use std::path::PathBuf;
+pub mod genbank;
+pub mod index;
+pub mod reader;
+pub mod twobit;
+pub mod writer;
 
-use crate::shared::infrastructure::CommonWriteFormat;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::shared::infrastructure::{compression::Compression, CommonWriteFormat};
 
 use super::domain::fasta::Fasta;
 
 impl Fasta {
-    pub fn save(self, file: &PathBuf) -> Result<(), anyhow::Error> {
-        CommonWriteFormat::from(self).save(file)
+    pub fn save(
+        self,
+        file: Option<&Path>,
+        compression: Option<Compression>,
+    ) -> Result<(), anyhow::Error> {
+        CommonWriteFormat::from(self).save(file, compression)
+    }
+}
+
+/// Formats sequences, or their features, can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SequenceFormat {
+    /// Fasta text
+    Fasta,
+    /// UCSC .2bit, packed in two bits per base (`.2bit`)
+    #[value(name = "2bit")]
+    TwoBit,
+    /// Features of GenBank records, as GFF3 (`.gff`, `.gff3`)
+    Gff3,
+}
+
+impl SequenceFormat {
+    /// Guesses the format to write a file in from its extension, fasta unless told otherwise.
+    pub fn from_extension(file: &Path) -> Self {
+        match file.extension().and_then(|extension| extension.to_str()) {
+            Some("2bit") => SequenceFormat::TwoBit,
+            Some("gff") | Some("gff3") => SequenceFormat::Gff3,
+            _ => SequenceFormat::Fasta,
+        }
     }
 }
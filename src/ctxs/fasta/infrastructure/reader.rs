@@ -0,0 +1,588 @@
+//! Streaming fasta, fastq, .2bit and GenBank readers.
+use std::{collections::VecDeque, io::BufRead, path::Path};
+
+use crate::{
+    ctxs::fasta::domain::{
+        alphabet::{Alphabet, SequenceAlphabet, ValidationPolicy, Validator},
+        errors::FastaError,
+        fasta::Fasta,
+        fastq::{Fastq, FastqQuality, PhredOffset},
+        record::SequenceRecord,
+    },
+    ctxs::fasta::infrastructure::{
+        genbank::{is_genbank, GenBankReader},
+        twobit::{is_two_bit, TwoBitReader},
+    },
+    shared::infrastructure::io::open_input,
+};
+
+type Result<T> = std::result::Result<T, FastaError>;
+
+/// Reads records from either a fasta or a fastq source, telling which one it is from the first
+/// character: fastq records start with `@`. .2bit files are told by their signature, and GenBank
+/// ones by their leading `LOCUS`, and only their sequences are read.
+pub enum RecordReader<R: BufRead> {
+    Fasta(FastaReader<R>),
+    Fastq(FastqReader<R>),
+    TwoBit(TwoBitReader<R>),
+    GenBank(GenBankReader<R>),
+}
+
+impl RecordReader<Box<dyn BufRead>> {
+    /// Opens a file for reading, or the standard input if `file` is `-` or missing. Sequences are
+    /// checked with `validator`, if given.
+    pub fn open(file: Option<&Path>, validator: Option<Validator>) -> anyhow::Result<Self> {
+        Ok(RecordReader::new(open_input(file)?)?.validated(validator))
+    }
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        if is_two_bit(reader.fill_buf()?) {
+            return Ok(RecordReader::TwoBit(TwoBitReader::new(reader)?));
+        }
+        if is_genbank(reader.fill_buf()?) {
+            return Ok(RecordReader::GenBank(GenBankReader::new(reader)));
+        }
+        let first_char: Option<u8> = reader
+            .fill_buf()?
+            .iter()
+            .find(|c| !c.is_ascii_whitespace())
+            .copied();
+        match first_char {
+            Some(b'@') => Ok(RecordReader::Fastq(FastqReader::new(reader))),
+            _ => Ok(RecordReader::Fasta(FastaReader::new(reader))),
+        }
+    }
+
+    /// Checks every sequence line with `validator`. See `LineChecker` for what happens with
+    /// illegal characters. Sequences of .2bit and GenBank files are not checked.
+    pub fn validated(self, validator: Option<Validator>) -> Self {
+        match self {
+            RecordReader::Fasta(reader) => RecordReader::Fasta(reader.validated(validator)),
+            RecordReader::Fastq(reader) => RecordReader::Fastq(reader.validated(validator)),
+            RecordReader::TwoBit(reader) => RecordReader::TwoBit(reader),
+            RecordReader::GenBank(reader) => RecordReader::GenBank(reader),
+        }
+    }
+
+    /// Takes every sequence as `alphabet`, if given, instead of the one its letters look like.
+    pub fn with_alphabet(self, alphabet: Option<Alphabet>) -> Self {
+        match self {
+            RecordReader::Fasta(reader) => RecordReader::Fasta(reader.with_alphabet(alphabet)),
+            RecordReader::Fastq(reader) => RecordReader::Fastq(reader.with_alphabet(alphabet)),
+            RecordReader::TwoBit(reader) => RecordReader::TwoBit(reader.with_alphabet(alphabet)),
+            RecordReader::GenBank(reader) => RecordReader::GenBank(reader.with_alphabet(alphabet)),
+        }
+    }
+
+    /// Takes the illegal characters found so far by a lenient validator.
+    pub fn take_issues(&mut self) -> Vec<FastaError> {
+        match self {
+            RecordReader::Fasta(reader) => reader.checker.take_issues(),
+            RecordReader::Fastq(reader) => reader.checker.take_issues(),
+            RecordReader::TwoBit(_) | RecordReader::GenBank(_) => Vec::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RecordReader<R> {
+    type Item = Result<SequenceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            RecordReader::Fasta(reader) => reader.next().map(|r| r.map(SequenceRecord::from)),
+            RecordReader::Fastq(reader) => reader.next().map(|r| r.map(SequenceRecord::from)),
+            RecordReader::TwoBit(reader) => reader.next().map(|r| r.map(SequenceRecord::from)),
+            RecordReader::GenBank(reader) => reader
+                .next()
+                .map(|r| r.map(|record| SequenceRecord::from(record.fasta))),
+        }
+    }
+}
+
+/// Checks sequence lines as they are read. A strict validator fails on the first illegal
+/// character; a lenient one keeps them all as issues, to be taken by the caller. Without an
+/// alphabet, records are checked as DNA or RNA, or as proteins if their first line looks like one.
+#[derive(Default)]
+struct LineChecker {
+    validator: Option<Validator>,
+    issues: Vec<FastaError>,
+    /// Record whose alphabet was last guessed, and the guess
+    guessed: Option<(usize, Option<Alphabet>)>,
+}
+
+impl LineChecker {
+    /// Checks the sequence of a line, as read. Columns count from the start of the line, leading
+    /// whitespace included.
+    fn check(&mut self, record: usize, header: &str, line: usize, text: &str) -> Result<()> {
+        let Some(mut validator) = self.validator else {
+            return Ok(());
+        };
+        let sequence: &str = text.trim();
+        let indent: usize = text.chars().take_while(|c| c.is_whitespace()).count();
+        if validator.alphabet.is_none() {
+            let guessed: Option<Alphabet> = match self.guessed {
+                Some((guessed_record, alphabet)) if guessed_record == record => alphabet,
+                _ => SequenceAlphabet::detect(sequence).alphabet,
+            };
+            self.guessed = Some((record, guessed));
+            if guessed == Some(Alphabet::Protein) {
+                validator.alphabet = Some(Alphabet::Protein);
+            }
+        }
+        for (index, character) in validator.illegal_characters(sequence) {
+            let issue: FastaError = FastaError::IllegalCharacter {
+                record,
+                header: header.to_string(),
+                line,
+                column: indent + index + 1,
+                character,
+                alphabet: validator.alphabet_name(),
+            };
+            match validator.policy {
+                ValidationPolicy::Strict => return Err(issue),
+                ValidationPolicy::Lenient => self.issues.push(issue),
+            }
+        }
+        Ok(())
+    }
+
+    fn take_issues(&mut self) -> Vec<FastaError> {
+        std::mem::take(&mut self.issues)
+    }
+}
+
+/// Reads `Fasta` records one at a time from any buffered source. Only the record being built is
+/// held in memory, so files bigger than the available memory can be processed.
+pub struct FastaReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    record_number: usize,
+    next_header: Option<String>,
+    checker: LineChecker,
+    /// Alphabet to take every sequence as, instead of the one its letters look like
+    alphabet: Option<Alphabet>,
+    done: bool,
+}
+
+impl<R: BufRead> FastaReader<R> {
+    pub fn new(reader: R) -> Self {
+        FastaReader {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            record_number: 0,
+            next_header: None,
+            checker: LineChecker::default(),
+            alphabet: None,
+            done: false,
+        }
+    }
+
+    /// Checks every sequence line with `validator`, if given.
+    pub fn validated(mut self, validator: Option<Validator>) -> Self {
+        self.checker.validator = validator;
+        self
+    }
+
+    /// Takes every sequence as `alphabet`, if given.
+    pub fn with_alphabet(mut self, alphabet: Option<Alphabet>) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Builds a record, in the alphabet to take every sequence as, if any
+    fn record(&self, header: String, sequence: String) -> Fasta {
+        let record: Fasta = Fasta::from((header, sequence));
+        match self.alphabet {
+            Some(alphabet) => record.with_alphabet(alphabet),
+            None => record,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = Result<Fasta>;
+
+    /// Every `>` line starts a new record, and the lines that follow it, up to the next `>` line,
+    /// are its sequence. Lines before the first header are taken as a record without header.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut sequence: String = String::new();
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+                Ok(0) => {
+                    self.done = true;
+                    if self.next_header.is_none() && sequence.is_empty() {
+                        return None;
+                    }
+                    let header: String = self.next_header.take().unwrap_or_default();
+                    return Some(Ok(self.record(header, sequence)));
+                }
+                Ok(_) => {
+                    self.line_number += 1;
+                    let line: &str = self.line.trim();
+                    if let Some(new_header) = line.strip_prefix('>') {
+                        let new_header: String = new_header.trim().to_string();
+                        self.record_number += 1;
+                        if self.next_header.is_some() || !sequence.is_empty() {
+                            let header: String =
+                                self.next_header.replace(new_header).unwrap_or_default();
+                            return Some(Ok(self.record(header, sequence)));
+                        }
+                        self.next_header = Some(new_header);
+                    } else {
+                        if self.record_number == 0 {
+                            self.record_number = 1;
+                        }
+                        let header: &str = self.next_header.as_deref().unwrap_or_default();
+                        if let Err(e) =
+                            self.checker
+                                .check(self.record_number, header, self.line_number, &self.line)
+                        {
+                            self.done = true;
+                            return Some(Err(e));
+                        }
+                        sequence.push_str(line);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Number of fastq records whose qualities tell their encoding
+const PHRED_SAMPLE: usize = 100;
+
+/// A fastq record as read, its qualities not decoded yet
+type RawFastq = (String, String, String);
+
+/// Reads `Fastq` records one at a time from any buffered source. The quality encoding is guessed
+/// from the first `PHRED_SAMPLE` records before any is decoded, and Phred+33 is assumed if they
+/// fit both. Later records whose qualities do not fit it are an error.
+pub struct FastqReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    record_number: usize,
+    offset: Option<PhredOffset>,
+    /// Records read to guess the encoding, not returned yet
+    sample: VecDeque<RawFastq>,
+    /// Error found while reading the sample, returned after it
+    sample_error: Option<FastaError>,
+    checker: LineChecker,
+    /// Alphabet to take every sequence as, instead of the one its letters look like
+    alphabet: Option<Alphabet>,
+}
+
+impl<R: BufRead> FastqReader<R> {
+    pub fn new(reader: R) -> Self {
+        FastqReader {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            record_number: 0,
+            offset: None,
+            sample: VecDeque::new(),
+            sample_error: None,
+            checker: LineChecker::default(),
+            alphabet: None,
+        }
+    }
+
+    /// Checks every sequence line with `validator`, if given.
+    pub fn validated(mut self, validator: Option<Validator>) -> Self {
+        self.checker.validator = validator;
+        self
+    }
+
+    /// Takes every sequence as `alphabet`, if given.
+    pub fn with_alphabet(mut self, alphabet: Option<Alphabet>) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Reads the next line, without line terminators. `None` at the end of the input.
+    fn read_line(&mut self) -> Result<Option<&str>> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        self.line_number += 1;
+        Ok(Some(self.line.trim_end_matches(['\r', '\n'])))
+    }
+
+    fn read_raw(&mut self) -> Result<Option<RawFastq>> {
+        let header: String = loop {
+            let line_number: usize = self.line_number + 1;
+            match self.read_line()? {
+                None => return Ok(None),
+                Some("") => continue,
+                Some(line) => match line.strip_prefix('@') {
+                    Some(header) => break header.trim().to_string(),
+                    None => return Err(FastaError::MissingFastqHeader { line: line_number }),
+                },
+            }
+        };
+        self.record_number += 1;
+        let mut sequence: String = String::new();
+        loop {
+            match self.read_line()? {
+                None => return Err(FastaError::MissingQualities { header }),
+                Some(line) if line.starts_with('+') => break,
+                Some(line) => {
+                    let line: String = line.to_string();
+                    self.checker
+                        .check(self.record_number, &header, self.line_number, &line)?;
+                    sequence.push_str(line.trim());
+                }
+            }
+        }
+        let mut quality: String = String::new();
+        while quality.len() < sequence.len() {
+            match self.read_line()? {
+                None => break,
+                Some(line) => quality.push_str(line.trim()),
+            }
+        }
+        if quality.len() != sequence.len() {
+            return Err(FastaError::QualityLength {
+                header,
+                bases: sequence.len(),
+                qualities: quality.len(),
+            });
+        }
+        Ok(Some((header, sequence, quality)))
+    }
+
+    /// Reads the first records, up to the first error, and guesses their encoding.
+    fn detect_offset(&mut self) -> PhredOffset {
+        while self.sample.len() < PHRED_SAMPLE {
+            match self.read_raw() {
+                Ok(Some(record)) => self.sample.push_back(record),
+                Ok(None) => break,
+                Err(e) => {
+                    self.sample_error = Some(e);
+                    break;
+                }
+            }
+        }
+        PhredOffset::detect(self.sample.iter().map(|(_, _, quality)| quality.as_str()))
+            .unwrap_or(PhredOffset::Phred33)
+    }
+
+    fn read_record(&mut self) -> Result<Option<Fastq>> {
+        let offset: PhredOffset = match self.offset {
+            Some(offset) => offset,
+            None => {
+                let offset: PhredOffset = self.detect_offset();
+                self.offset = Some(offset);
+                offset
+            }
+        };
+        let raw: Option<RawFastq> = match self.sample.pop_front() {
+            Some(record) => Some(record),
+            None => match self.sample_error.take() {
+                Some(e) => return Err(e),
+                None => self.read_raw()?,
+            },
+        };
+        let Some((header, sequence, quality)) = raw else {
+            return Ok(None);
+        };
+        if !offset.fits(&quality) {
+            return Err(FastaError::MixedQualities { header, offset });
+        }
+        let quality: FastqQuality = FastqQuality::from_ascii(&quality, offset);
+        let record: Fastq = Fastq::from((header, sequence, quality));
+        Ok(Some(match self.alphabet {
+            Some(alphabet) => record.with_alphabet(alphabet),
+            None => record,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastqReader<R> {
+    type Item = Result<Fastq>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::fasta::{
+        domain::{
+            alphabet::{Alphabet, ValidationPolicy, Validator},
+            errors::FastaError,
+            fasta::Fasta,
+            fastq::Fastq,
+            record::SequenceRecord,
+        },
+        infrastructure::reader::{FastaReader, FastqReader, RecordReader},
+    };
+
+    #[test]
+    fn read_multi_fasta() {
+        let input: &[u8] = b">first one\natcg\nat\n>second\nggcc\n";
+        let records: Vec<Fasta> = FastaReader::new(input).map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].header.to_string(), "> first one\n");
+        assert_eq!(records[0].sequence.to_string(), "atcgat\n");
+        assert_eq!(records[1].header.to_string(), "> second\n");
+        assert_eq!(records[1].sequence.to_string(), "ggcc\n");
+    }
+
+    #[test]
+    fn read_headerless_sequence() {
+        let input: &[u8] = b"atcg\r\nggcc";
+        let records: Vec<Fasta> = FastaReader::new(input).map(Result::unwrap).collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].sequence.to_string(), "atcgggcc\n");
+    }
+
+    #[test]
+    fn read_empty_input() {
+        let input: &[u8] = b"";
+        assert_eq!(FastaReader::new(input).count(), 0);
+    }
+
+    #[test]
+    fn read_fastq() {
+        let input: &[u8] = b"@read1 desc\nACGT\n+\nII#!\n@read2\nAC\n+read2\n@I\n";
+        let records: Vec<Fastq> = FastqReader::new(input).map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].header.as_str(), "read1 desc");
+        assert_eq!(records[0].quality.scores(), &[40, 40, 2, 0]);
+        assert_eq!(records[1].sequence.as_str(), "AC");
+        assert_eq!(records[1].quality.scores(), &[31, 40]);
+    }
+
+    #[test]
+    fn detect_phred_offset_over_records() {
+        // The first read only has qualities above J, but the second tells it is Phred+33.
+        let input: &[u8] = b"@hifi\nACGT\n+\nKKKK\n@low\nAC\n+\n!I\n";
+        let records: Vec<Fastq> = FastqReader::new(input).map(Result::unwrap).collect();
+        assert_eq!(records[0].quality.scores(), &[42, 42, 42, 42]);
+        assert_eq!(records[1].quality.scores(), &[0, 40]);
+
+        let mut phred64: String = "@old\nACGT\n+\nBBhh\n".repeat(super::PHRED_SAMPLE);
+        phred64.push_str("@new\nAC\n+\n!I\n");
+        let mut reader = FastqReader::new(phred64.as_bytes());
+        let first: Fastq = reader.next().unwrap().unwrap();
+        assert_eq!(first.quality.scores(), &[2, 2, 40, 40]);
+        let last = reader.last().unwrap();
+        assert!(matches!(last, Err(FastaError::MixedQualities { .. })));
+    }
+
+    #[test]
+    fn read_fastq_with_missing_qualities() {
+        let input: &[u8] = b"@read1\nACGT\n+\nII\n";
+        assert!(FastqReader::new(input).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn detect_record_format() {
+        let fastq: &[u8] = b"\n@read1\nACGT\n+\nIIII\n";
+        let record = RecordReader::new(fastq).unwrap().next().unwrap().unwrap();
+        assert!(matches!(record, SequenceRecord::Fastq(_)));
+        let fasta: &[u8] = b">seq\nACGT\n";
+        let record = RecordReader::new(fasta).unwrap().next().unwrap().unwrap();
+        assert!(matches!(record, SequenceRecord::Fasta(_)));
+    }
+
+    #[test]
+    fn strict_validation_fails_with_position() {
+        let illegal = |input: &[u8]| {
+            let validator: Validator = Validator::new(None, ValidationPolicy::Strict);
+            let mut reader = FastaReader::new(input).validated(Some(validator));
+            assert!(reader.next().unwrap().is_ok());
+            let position = match reader.next().unwrap() {
+                Err(FastaError::IllegalCharacter {
+                    record,
+                    line,
+                    column,
+                    character,
+                    ..
+                }) => (record, line, column, character),
+                _ => panic!("Expected an illegal character error"),
+            };
+            assert!(reader.next().is_none());
+            position
+        };
+        assert_eq!(
+            illegal(b">first\nacgt\n>second\nacgt\nac.t\n"),
+            (2, 5, 3, '.')
+        );
+        assert_eq!(
+            illegal(b">first\nacgt\n>second\nacgt\n  ac.t\n"),
+            (2, 5, 5, '.')
+        );
+    }
+
+    #[test]
+    fn strict_validation_reads_proteins() {
+        let input: &[u8] = b">prot\nMKWVTFISLL\nLLFSSAYS*\n>dna\nacgt\n>bad\nacgtac\nacgE\n";
+        let validator: Validator = Validator::new(None, ValidationPolicy::Strict);
+        let mut reader = FastaReader::new(input).validated(Some(validator));
+        let protein: Fasta = reader.next().unwrap().unwrap();
+        assert_eq!(protein.sequence.as_str(), "MKWVTFISLLLLFSSAYS*");
+        assert!(reader.next().unwrap().is_ok());
+        match reader.next().unwrap() {
+            Err(FastaError::IllegalCharacter {
+                line, character, ..
+            }) => assert_eq!((line, character), (8, 'E')),
+            _ => panic!("Expected an illegal character error"),
+        }
+    }
+
+    #[test]
+    fn read_alphabets() {
+        let input: &[u8] = b">prot\nMKWVTFISLL\n>rna\nacguu\n>dna\nacga\n";
+        let records: Vec<Fasta> = FastaReader::new(input).map(Result::unwrap).collect();
+        let alphabets: Vec<Option<Alphabet>> = records
+            .iter()
+            .map(|record| record.sequence.alphabet().alphabet)
+            .collect();
+        assert_eq!(
+            alphabets,
+            [
+                Some(Alphabet::Protein),
+                Some(Alphabet::Rna),
+                Some(Alphabet::Dna)
+            ]
+        );
+        assert_eq!(records[2].sequence.alphabet().confidence, 0.5);
+        let mut reader = RecordReader::new(input)
+            .unwrap()
+            .with_alphabet(Some(Alphabet::Rna));
+        let record: SequenceRecord = reader.next().unwrap().unwrap();
+        assert!(record.sequence().alphabet().is_rna());
+        assert_eq!(record.sequence().alphabet().confidence, 1_f64);
+    }
+
+    #[test]
+    fn lenient_validation_keeps_characters() {
+        let input: &[u8] = b"@read\nACxT\n+\nIIII\n";
+        let validator: Validator = Validator::new(None, ValidationPolicy::Lenient);
+        let mut reader = RecordReader::new(input).unwrap().validated(Some(validator));
+        let record: SequenceRecord = reader.next().unwrap().unwrap();
+        assert_eq!(record.sequence().as_str(), "ACxT");
+        let issues: Vec<FastaError> = reader.take_issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "Illegal character 'x' for DNA or RNA in record 1 (\"read\"), line 2, column 3"
+        );
+    }
+}
@@ -0,0 +1,529 @@
+//! UCSC `.2bit` files: the sequences of a genome packed in two bits per base, with their runs of
+//! `N` and of soft-masked bases, and an index to seek straight to any of them.
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{
+    ctxs::fasta::domain::{
+        alphabet::Alphabet,
+        errors::FastaError,
+        fasta::{Fasta, FastaSequence},
+        packed::PackedSequence,
+    },
+    shared::infrastructure::{
+        compression::{Compression, Finish},
+        io::{open_output, Output},
+    },
+};
+
+type Result<T> = std::result::Result<T, FastaError>;
+
+/// Signature of .2bit files, in the byte order of the file
+const SIGNATURE: u32 = 0x1A41_2743;
+/// Bytes of the file header: signature, version, number of sequences and a reserved field
+const HEADER_SIZE: u64 = 16;
+/// Longest name a sequence can have
+const MAX_NAME: usize = 255;
+
+/// Byte order of the numbers of a .2bit file, which is the one of the machine that wrote it
+#[derive(Debug, Clone, Copy)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    /// Byte order of a file from its first bytes. `None` if they are not the signature.
+    fn detect(bytes: &[u8]) -> Option<Self> {
+        let signature: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+        match (u32::from_le_bytes(signature), u32::from_be_bytes(signature)) {
+            (SIGNATURE, _) => Some(ByteOrder::Little),
+            (_, SIGNATURE) => Some(ByteOrder::Big),
+            _ => None,
+        }
+    }
+
+    fn read_u32<R: Read>(self, reader: &mut R) -> Result<u32> {
+        let mut bytes: [u8; 4] = [0; 4];
+        reader.read_exact(&mut bytes)?;
+        Ok(match self {
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn read_u64<R: Read>(self, reader: &mut R) -> Result<u64> {
+        let mut bytes: [u8; 8] = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(match self {
+            ByteOrder::Little => u64::from_le_bytes(bytes),
+            ByteOrder::Big => u64::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// Whether some bytes are the start of a .2bit file
+pub fn is_two_bit(bytes: &[u8]) -> bool {
+    ByteOrder::detect(bytes).is_some()
+}
+
+/// A sequence of the index: its name, and the byte offset of its record
+#[derive(Debug, Clone, PartialEq)]
+struct TwoBitEntry {
+    name: String,
+    offset: u64,
+}
+
+/// The header and index of a .2bit file, and the bytes they take
+struct TwoBitIndex {
+    order: ByteOrder,
+    entries: Vec<TwoBitEntry>,
+    size: u64,
+}
+
+impl TwoBitIndex {
+    /// Reads the header and index at the start of a .2bit file. Version 1 files, with 64-bit
+    /// offsets, are read too.
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut signature: [u8; 4] = [0; 4];
+        reader.read_exact(&mut signature)?;
+        let order: ByteOrder = ByteOrder::detect(&signature)
+            .ok_or_else(|| invalid("it does not start with the .2bit signature"))?;
+        let version: u32 = order.read_u32(reader)?;
+        if version > 1 {
+            return Err(invalid(&format!("version {} is not supported", version)));
+        }
+        let count: u32 = order.read_u32(reader)?;
+        order.read_u32(reader)?;
+        let mut size: u64 = HEADER_SIZE;
+        let mut entries: Vec<TwoBitEntry> = Vec::new();
+        for _ in 0..count {
+            let mut name_size: [u8; 1] = [0];
+            reader.read_exact(&mut name_size)?;
+            let mut name: Vec<u8> = vec![0; name_size[0] as usize];
+            reader.read_exact(&mut name)?;
+            let offset: u64 = match version {
+                0 => order.read_u32(reader)? as u64,
+                _ => order.read_u64(reader)?,
+            };
+            size += 1 + name.len() as u64 + if version == 0 { 4 } else { 8 };
+            entries.push(TwoBitEntry {
+                name: String::from_utf8_lossy(&name).to_string(),
+                offset,
+            });
+        }
+        Ok(TwoBitIndex {
+            order,
+            entries,
+            size,
+        })
+    }
+}
+
+/// What comes before the bases in the record of a sequence
+struct RecordLayout {
+    length: usize,
+    n_runs: Vec<(usize, usize)>,
+    mask_runs: Vec<(usize, usize)>,
+    /// Bytes it takes
+    size: u64,
+}
+
+impl RecordLayout {
+    fn read<R: Read>(reader: &mut R, order: ByteOrder) -> Result<Self> {
+        let length: usize = order.read_u32(reader)? as usize;
+        let n_runs: Vec<(usize, usize)> = read_runs(reader, order)?;
+        let mask_runs: Vec<(usize, usize)> = read_runs(reader, order)?;
+        order.read_u32(reader)?;
+        let size: u64 = 16 + 8 * (n_runs.len() + mask_runs.len()) as u64;
+        Ok(RecordLayout {
+            length,
+            n_runs,
+            mask_runs,
+            size,
+        })
+    }
+
+    /// The bases from `start` on, taken from `bytes`, which begin at the byte holding that base
+    fn sequence(&self, bytes: Vec<u8>, start: usize, end: usize) -> PackedSequence {
+        let first: usize = start / 4 * 4;
+        let length: usize = (bytes.len() * 4).min(self.length - first);
+        PackedSequence::from_two_bit(bytes, first, length, &self.n_runs, &self.mask_runs)
+            .slice(start - first, end - first)
+    }
+}
+
+/// Reads runs, as their count, their starts and their lengths
+fn read_runs<R: Read>(reader: &mut R, order: ByteOrder) -> Result<Vec<(usize, usize)>> {
+    let count: u32 = order.read_u32(reader)?;
+    let mut starts: Vec<usize> = Vec::new();
+    for _ in 0..count {
+        starts.push(order.read_u32(reader)? as usize);
+    }
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for start in starts {
+        runs.push((start, order.read_u32(reader)? as usize));
+    }
+    Ok(runs)
+}
+
+/// Reads every sequence of a .2bit file, one at a time, in the order they are stored, from any
+/// buffered source. Only the sequence being read is held in memory.
+pub struct TwoBitReader<R: BufRead> {
+    reader: R,
+    order: ByteOrder,
+    entries: std::vec::IntoIter<TwoBitEntry>,
+    /// Bytes read so far
+    position: u64,
+    /// Alphabet to take every sequence as, instead of DNA
+    alphabet: Option<Alphabet>,
+    done: bool,
+}
+
+impl<R: BufRead> TwoBitReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let index: TwoBitIndex = TwoBitIndex::read(&mut reader)?;
+        let mut entries: Vec<TwoBitEntry> = index.entries;
+        entries.sort_by_key(|entry| entry.offset);
+        Ok(TwoBitReader {
+            reader,
+            order: index.order,
+            entries: entries.into_iter(),
+            position: index.size,
+            alphabet: None,
+            done: false,
+        })
+    }
+
+    /// Takes every sequence as `alphabet`, if given.
+    pub fn with_alphabet(mut self, alphabet: Option<Alphabet>) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Skips to the record of a sequence, and reads it
+    fn read_record(&mut self, entry: TwoBitEntry) -> Result<Fasta> {
+        let skipped: u64 = entry
+            .offset
+            .checked_sub(self.position)
+            .ok_or_else(|| invalid(&format!("sequence {} overlaps another", entry.name)))?;
+        io::copy(&mut (&mut self.reader).take(skipped), &mut io::sink())?;
+        let layout: RecordLayout = RecordLayout::read(&mut self.reader, self.order)?;
+        let mut bytes: Vec<u8> = Vec::new();
+        let size: u64 = layout.length.div_ceil(4) as u64;
+        if (&mut self.reader).take(size).read_to_end(&mut bytes)? as u64 != size {
+            return Err(invalid(&format!("sequence {} is cut short", entry.name)));
+        }
+        self.position = entry.offset + layout.size + size;
+        let record: Fasta = Fasta::from((
+            entry.name,
+            FastaSequence::from(layout.sequence(bytes, 0, layout.length)),
+        ));
+        Ok(match self.alphabet {
+            Some(alphabet) => record.with_alphabet(alphabet),
+            None => record,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for TwoBitReader<R> {
+    type Item = Result<Fasta>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let entry: TwoBitEntry = self.entries.next()?;
+        let record: Result<Fasta> = self.read_record(entry);
+        self.done = record.is_err();
+        Some(record)
+    }
+}
+
+/// A .2bit file, to read any part of any of its sequences, seeking straight to its bases.
+pub struct TwoBitFile<R: Read + Seek> {
+    reader: R,
+    order: ByteOrder,
+    entries: Vec<TwoBitEntry>,
+}
+
+impl TwoBitFile<BufReader<File>> {
+    pub fn open(file: &Path) -> Result<Self> {
+        TwoBitFile::new(BufReader::new(open(file)?))
+    }
+
+    /// Whether a file is a .2bit one, from its first bytes. Compressed files are not.
+    pub fn sniff(file: &Path) -> bool {
+        let mut signature: [u8; 4] = [0; 4];
+        open(file).is_ok_and(|mut opened| {
+            opened.read_exact(&mut signature).is_ok() && is_two_bit(&signature)
+        })
+    }
+}
+
+impl<R: Read + Seek> TwoBitFile<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let index: TwoBitIndex = TwoBitIndex::read(&mut reader)?;
+        Ok(TwoBitFile {
+            reader,
+            order: index.order,
+            entries: index.entries,
+        })
+    }
+
+    /// Names of the sequences, in the order of the index
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    /// Number of bases of a sequence
+    pub fn length(&mut self, name: &str) -> Result<usize> {
+        Ok(self.layout(name)?.0.length)
+    }
+
+    /// The bases of a sequence from `start` to `end`, 0-based and end-exclusive, reading only
+    /// the bytes that hold them. Positions past the end of the sequence are left out.
+    pub fn fetch(&mut self, name: &str, start: usize, end: usize) -> Result<FastaSequence> {
+        let (layout, bases_offset) = self.layout(name)?;
+        let end: usize = end.min(layout.length);
+        let start: usize = start.min(end);
+        let (first, last) = (start / 4, end.div_ceil(4));
+        self.reader
+            .seek(SeekFrom::Start(bases_offset + first as u64))?;
+        let mut bytes: Vec<u8> = vec![0; last - first];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(FastaSequence::from(layout.sequence(bytes, start, end)))
+    }
+
+    /// Layout of the record of a sequence, and the byte offset of its bases
+    fn layout(&mut self, name: &str) -> Result<(RecordLayout, u64)> {
+        let offset: u64 = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| FastaError::MissingSequence(name.to_string()))?
+            .offset;
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let layout: RecordLayout = RecordLayout::read(&mut self.reader, self.order)?;
+        let bases_offset: u64 = offset + layout.size;
+        Ok((layout, bases_offset))
+    }
+}
+
+/// Writes sequences as a .2bit file, named after the first word of their headers. The index goes
+/// first, so sequences are held, packed, until `flush` writes them all.
+pub struct TwoBitWriter<W: Finish> {
+    writer: W,
+    records: Vec<(String, PackedSequence)>,
+}
+
+impl TwoBitWriter<Output> {
+    /// Creates, or truncates, a file for writing, or writes to the standard output if `file` is
+    /// `-` or missing, compressed as `open_output` does.
+    pub fn create(file: Option<&Path>, compression: Option<Compression>) -> anyhow::Result<Self> {
+        Ok(TwoBitWriter::new(open_output(file, compression)?))
+    }
+}
+
+impl<W: Finish> TwoBitWriter<W> {
+    pub fn new(writer: W) -> Self {
+        TwoBitWriter {
+            writer,
+            records: Vec::new(),
+        }
+    }
+
+    /// Adds a sequence. Bases other than `A`, `C`, `G` and `T` are written as `N`, as the format
+    /// has no room for them.
+    pub fn write(&mut self, record: Fasta) -> Result<()> {
+        let name: &str = record
+            .header
+            .as_str()
+            .split_whitespace()
+            .next()
+            .unwrap_or("");
+        if name.len() > MAX_NAME {
+            return Err(invalid(&format!(
+                "sequence names are up to {} bytes long, and {} is longer",
+                MAX_NAME, name
+            )));
+        }
+        let sequence: PackedSequence = record.sequence.two_bit();
+        if u32::try_from(sequence.len()).is_err() {
+            return Err(invalid(&format!("sequence {} is too long", name)));
+        }
+        self.records.push((name.to_string(), sequence));
+        Ok(())
+    }
+
+    /// Writes every sequence added, little-endian. Offsets take 32 bits (version 0), or 64 bits
+    /// (version 1) if the file is bigger than 4 GiB. Ends the output, so nothing can be written
+    /// after.
+    pub fn flush(&mut self) -> Result<()> {
+        let record_sizes: Vec<u64> = self
+            .records
+            .iter()
+            .map(|(_, sequence)| {
+                let runs: usize = sequence.n_runs().len() + sequence.mask_runs().len();
+                16 + 8 * runs as u64 + sequence.len().div_ceil(4) as u64
+            })
+            .collect();
+        let names_size: u64 = self
+            .records
+            .iter()
+            .map(|(name, _)| 1 + name.len() as u64)
+            .sum();
+        let total: u64 = HEADER_SIZE + names_size + 4 * self.records.len() as u64;
+        let version: u32 = match total + record_sizes.iter().sum::<u64>() > u32::MAX as u64 {
+            true => 1,
+            false => 0,
+        };
+        let mut offset: u64 =
+            HEADER_SIZE + names_size + (4 + 4 * version as u64) * self.records.len() as u64;
+
+        let writer: &mut W = &mut self.writer;
+        writer.write_all(&SIGNATURE.to_le_bytes())?;
+        writer.write_all(&version.to_le_bytes())?;
+        writer.write_all(&(self.records.len() as u32).to_le_bytes())?;
+        writer.write_all(&0_u32.to_le_bytes())?;
+        for ((name, _), size) in self.records.iter().zip(&record_sizes) {
+            writer.write_all(&[name.len() as u8])?;
+            writer.write_all(name.as_bytes())?;
+            match version {
+                0 => writer.write_all(&(offset as u32).to_le_bytes())?,
+                _ => writer.write_all(&offset.to_le_bytes())?,
+            }
+            offset += size;
+        }
+        for (_, sequence) in &self.records {
+            writer.write_all(&(sequence.len() as u32).to_le_bytes())?;
+            write_runs(writer, sequence.n_runs())?;
+            write_runs(writer, sequence.mask_runs())?;
+            writer.write_all(&0_u32.to_le_bytes())?;
+            writer.write_all(sequence.two_bit_bytes().unwrap_or_default())?;
+        }
+        writer.finish()?;
+        self.records.clear();
+        Ok(())
+    }
+}
+
+/// Writes runs, as their count, their starts and their lengths
+fn write_runs<W: Write>(writer: &mut W, runs: &[(usize, usize)]) -> Result<()> {
+    writer.write_all(&(runs.len() as u32).to_le_bytes())?;
+    for (start, _) in runs {
+        writer.write_all(&(*start as u32).to_le_bytes())?;
+    }
+    for (_, length) in runs {
+        writer.write_all(&(*length as u32).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn invalid(reason: &str) -> FastaError {
+    FastaError::InvalidTwoBit(reason.to_string())
+}
+
+fn open(file: &Path) -> Result<File> {
+    File::open(file).map_err(|source| FastaError::Open {
+        path: file.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::ctxs::fasta::{
+        domain::fasta::Fasta,
+        infrastructure::twobit::{is_two_bit, TwoBitFile, TwoBitReader, TwoBitWriter},
+    };
+
+    fn two_bit(records: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut writer = TwoBitWriter::new(&mut bytes);
+        for &record in records {
+            writer.write(Fasta::from(record)).unwrap();
+        }
+        writer.flush().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn write_two_bit() {
+        let bytes: Vec<u8> = two_bit(&[("chr1 first", "ACGTnnA")]);
+        assert!(is_two_bit(&bytes));
+        let expected: Vec<u8> = [
+            &[0x43, 0x27, 0x41, 0x1a, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0][..],
+            &[4, b'c', b'h', b'r', b'1', 25, 0, 0, 0],
+            &[7, 0, 0, 0],
+            &[1, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0],
+            &[1, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0],
+            &[0, 0, 0, 0],
+            &[0b1001_1100, 0b0000_1000],
+        ]
+        .concat();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn read_two_bit() {
+        let records: [(&str, &str); 3] =
+            [("chr1", "ACGTNNacgtnAC"), ("chr2", ""), ("chrM", "TTRAuu-")];
+        let bytes: Vec<u8> = two_bit(&records);
+        let read: Vec<String> = TwoBitReader::new(bytes.as_slice())
+            .unwrap()
+            .map(|record| record.unwrap().to_string())
+            .collect();
+        assert_eq!(
+            read,
+            ["> chr1\nACGTNNacgtnAC\n", "> chr2\n\n", "> chrM\nTTNAttN\n"]
+        );
+    }
+
+    #[test]
+    fn fetch_from_two_bit() {
+        let bytes: Vec<u8> = two_bit(&[("chr1", "A"), ("chr2", "ACGTNNacgtnACGTA")]);
+        let mut genome = TwoBitFile::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(genome.names(), ["chr1", "chr2"]);
+        assert_eq!(genome.length("chr2").unwrap(), 16);
+        let fetch = |genome: &mut TwoBitFile<Cursor<Vec<u8>>>, start: usize, end: usize| {
+            genome
+                .fetch("chr2", start, end)
+                .unwrap()
+                .as_str()
+                .to_string()
+        };
+        assert_eq!(fetch(&mut genome, 0, 16), "ACGTNNacgtnACGTA");
+        assert_eq!(fetch(&mut genome, 5, 11), "Nacgtn");
+        assert_eq!(fetch(&mut genome, 3, 4), "T");
+        assert_eq!(fetch(&mut genome, 14, 40), "TA");
+        assert_eq!(fetch(&mut genome, 20, 40), "");
+        assert!(genome.fetch("chr3", 0, 1).is_err());
+    }
+
+    #[test]
+    fn read_big_endian_two_bit() {
+        let bytes: Vec<u8> = [
+            &[0x1a, 0x41, 0x27, 0x43, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0][..],
+            &[1, b's', 0, 0, 0, 22],
+            &[
+                0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0,
+            ],
+            &[0b1001_1100, 0b0000_0000],
+        ]
+        .concat();
+        let read: Vec<String> = TwoBitReader::new(bytes.as_slice())
+            .unwrap()
+            .map(|record| record.unwrap().to_string())
+            .collect();
+        assert_eq!(read, ["> s\nAcgTT\n"]);
+    }
+}
@@ -0,0 +1,42 @@
+//! Streaming fasta writer.
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::shared::infrastructure::{
+    compression::{Compression, Finish},
+    io::{open_output, Output},
+    CommonWriteFormat,
+};
+
+/// Writes records one at a time to any destination. Anything that can be turned into a
+/// `CommonWriteFormat` can be written.
+pub struct FastaWriter<W: Finish> {
+    writer: W,
+}
+
+impl FastaWriter<Output> {
+    /// Creates, or truncates, a file for writing, or writes to the standard output if `file` is
+    /// `-` or missing. The output is compressed with `compression`, or as the file extension tells
+    /// if `None`.
+    pub fn create(file: Option<&Path>, compression: Option<Compression>) -> Result<Self> {
+        Ok(FastaWriter::new(open_output(file, compression)?))
+    }
+}
+
+impl<W: Finish> FastaWriter<W> {
+    pub fn new(writer: W) -> Self {
+        FastaWriter { writer }
+    }
+
+    /// Writes a single record.
+    pub fn write<T: Into<CommonWriteFormat>>(&mut self, record: T) -> Result<()> {
+        record.into().write_to(&mut self.writer)
+    }
+
+    /// Ends the output, compressed or not, and flushes it. Nothing can be written after.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
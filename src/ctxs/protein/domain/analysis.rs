@@ -0,0 +1,308 @@
+//! Physicochemical properties of proteins, computed as ExPASy ProtParam does, but for the
+//! isoelectric point, which takes the pKa values of EMBOSS rather than those of Bjellqvist
+use std::collections::BTreeMap;
+
+use crate::ctxs::protein::domain::protein::Protein;
+
+/// The twenty standard aminoacids, in the order of the tables below
+const STANDARD_AMINOACIDS: &str = "acdefghiklmnpqrstvwy";
+
+/// Average masses of the standard aminoacids within a chain, in daltons
+const RESIDUE_MASSES: [f64; 20] = [
+    71.0788, 103.1388, 115.0886, 129.1155, 147.1766, 57.0519, 137.1411, 113.1594, 128.1741,
+    113.1594, 131.1926, 114.1038, 97.1167, 128.1307, 156.1875, 87.0782, 101.1051, 99.1326,
+    186.2132, 163.1760,
+];
+
+/// Average mass of the water added by the ends of a chain, in daltons
+const WATER_MASS: f64 = 18.01524;
+
+/// Kyte-Doolittle hydropathy of the standard aminoacids
+const HYDROPATHY: [f64; 20] = [
+    1.8, 2.5, -3.5, -3.5, 2.8, -0.4, -3.2, 4.5, -3.9, 3.8, 1.9, -3.5, -1.6, -3.5, -4.5, -0.8, -0.7,
+    4.2, -0.9, -1.3,
+];
+
+/// Dipeptide instability weight values (Guruprasad et al., 1990): how much a pair of consecutive
+/// aminoacids, first one by row, destabilizes a protein
+const DIWV: [[f64; 20]; 20] = [
+    // A
+    [
+        1.0, 44.94, -7.49, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 1.0, 1.0, 20.26, 1.0, 1.0, 1.0,
+        1.0, 1.0, 1.0, 1.0,
+    ],
+    // C
+    [
+        1.0, 1.0, 20.26, 1.0, 1.0, 1.0, 33.60, 1.0, 1.0, 20.26, 33.60, 1.0, 20.26, -6.54, 1.0, 1.0,
+        33.60, -6.54, 24.68, 1.0,
+    ],
+    // D
+    [
+        1.0, 1.0, 1.0, 1.0, -6.54, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 1.0, 1.0, -6.54, 20.26,
+        -14.03, 1.0, 1.0, 1.0,
+    ],
+    // E
+    [
+        1.0, 44.94, 20.26, 33.60, 1.0, 1.0, -6.54, 20.26, 1.0, 1.0, 1.0, 1.0, 20.26, 20.26, 1.0,
+        20.26, 1.0, 1.0, -14.03, 1.0,
+    ],
+    // F
+    [
+        1.0, 1.0, 13.34, 1.0, 1.0, 1.0, 1.0, 1.0, -14.03, 1.0, 1.0, 1.0, 20.26, 1.0, 1.0, 1.0, 1.0,
+        1.0, 1.0, 33.601,
+    ],
+    // G
+    [
+        -7.49, 1.0, 1.0, -6.54, 1.0, 13.34, 1.0, -7.49, -7.49, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 1.0,
+        -7.49, 1.0, 13.34, -7.49,
+    ],
+    // H
+    [
+        1.0, 1.0, 1.0, 1.0, -9.37, -9.37, 1.0, 44.94, 24.68, 1.0, 1.0, 24.68, -1.88, 1.0, 1.0, 1.0,
+        -6.54, 1.0, -1.88, 44.94,
+    ],
+    // I
+    [
+        1.0, 1.0, 1.0, 44.94, 1.0, 1.0, 13.34, 1.0, -7.49, 20.26, 1.0, 1.0, -1.88, 1.0, 1.0, 1.0,
+        1.0, -7.49, 1.0, 1.0,
+    ],
+    // K
+    [
+        1.0, 1.0, 1.0, 1.0, 1.0, -7.49, 1.0, -7.49, 1.0, -7.49, 33.60, 1.0, -6.54, 24.64, 33.60,
+        1.0, 1.0, -7.49, 1.0, 1.0,
+    ],
+    // L
+    [
+        1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 20.26, 33.60, 20.26, 1.0,
+        1.0, 1.0, 24.68, 1.0,
+    ],
+    // M
+    [
+        13.34, 1.0, 1.0, 1.0, 1.0, 1.0, 58.28, 1.0, 1.0, 1.0, -1.88, 1.0, 44.94, -6.54, -6.54,
+        44.94, -1.88, 1.0, 1.0, 24.68,
+    ],
+    // N
+    [
+        1.0, -1.88, 1.0, 1.0, -14.03, -14.03, 1.0, 44.94, 24.68, 1.0, 1.0, 1.0, -1.88, -6.54, 1.0,
+        1.0, -7.49, 1.0, -9.37, 1.0,
+    ],
+    // P
+    [
+        20.26, -6.54, -6.54, 18.38, 20.26, 1.0, 1.0, 1.0, 1.0, 1.0, -6.54, 1.0, 20.26, 20.26,
+        -6.54, 20.26, 1.0, 20.26, -1.88, 1.0,
+    ],
+    // Q
+    [
+        1.0, -6.54, 20.26, 20.26, -6.54, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 20.26, 20.26, 1.0,
+        44.94, 1.0, -6.54, 1.0, -6.54,
+    ],
+    // R
+    [
+        1.0, 1.0, 1.0, 1.0, 1.0, -7.49, 20.26, 1.0, 1.0, 1.0, 1.0, 13.34, 20.26, 20.26, 58.28,
+        44.94, 1.0, 1.0, 58.28, -6.54,
+    ],
+    // S
+    [
+        1.0, 33.60, 1.0, 20.26, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 44.94, 20.26, 20.26, 20.26,
+        1.0, 1.0, 1.0, 1.0,
+    ],
+    // T
+    [
+        1.0, 1.0, 1.0, 20.26, 13.34, -7.49, 1.0, 1.0, 1.0, 1.0, 1.0, -14.03, 1.0, -6.54, 1.0, 1.0,
+        1.0, 1.0, -14.03, 1.0,
+    ],
+    // V
+    [
+        1.0, 1.0, -14.03, 1.0, 1.0, -7.49, 1.0, 1.0, -1.88, 1.0, 1.0, 1.0, 20.26, 1.0, 1.0, 1.0,
+        -7.49, 1.0, 1.0, -6.54,
+    ],
+    // W
+    [
+        -14.03, 1.0, 1.0, 1.0, 1.0, -9.37, 24.68, 1.0, 1.0, 13.34, 24.68, 13.34, 1.0, 1.0, 1.0,
+        1.0, -14.03, -7.49, 1.0, 1.0,
+    ],
+    // Y
+    [
+        24.68, 1.0, 24.68, -6.54, 1.0, -7.49, 13.34, 1.0, 1.0, 1.0, 44.94, 1.0, 13.34, 1.0, -15.91,
+        1.0, -7.49, 1.0, -9.37, 13.34,
+    ],
+];
+
+/// pKa of the amino and carboxyl ends of a chain, as EMBOSS sets them
+const N_TERMINUS_PKA: f64 = 8.6;
+const C_TERMINUS_PKA: f64 = 3.6;
+
+/// pKa of the side chains that take a positive charge
+const POSITIVE_PKAS: [(char, f64); 3] = [('k', 10.8), ('r', 12.5), ('h', 6.5)];
+
+/// pKa of the side chains that take a negative charge
+const NEGATIVE_PKAS: [(char, f64); 4] = [('d', 3.9), ('e', 4.1), ('c', 8.5), ('y', 10.1)];
+
+/// Molar extinction at 280 nm of tryptophans, tyrosines and cystines
+const TRYPTOPHAN_EXTINCTION: usize = 5500;
+const TYROSINE_EXTINCTION: usize = 1490;
+const CYSTINE_EXTINCTION: usize = 125;
+
+/// Proteins with an instability index below this are predicted to be stable
+pub const STABILITY_THRESHOLD: f64 = 40_f64;
+
+/// Physicochemical properties of a protein. Stops and gaps are not part of the chain, and
+/// aminoacids other than the twenty standard ones are left out of every measure but the length.
+pub struct ProteinAnalysis {
+    /// Lowercase letters of the chain
+    residues: Vec<char>,
+}
+
+impl From<&Protein> for ProteinAnalysis {
+    fn from(protein: &Protein) -> Self {
+        ProteinAnalysis {
+            residues: protein
+                .chain
+                .iter()
+                .map(|aa| aa.aminoacid.to_ascii_lowercase())
+                .filter(|&aa| aa != '*' && aa != '-')
+                .collect(),
+        }
+    }
+}
+
+impl ProteinAnalysis {
+    /// Number of aminoacids
+    pub fn length(&self) -> usize {
+        self.residues.len()
+    }
+
+    /// Times every aminoacid appears, by letter
+    pub fn composition(&self) -> BTreeMap<char, usize> {
+        self.residues
+            .iter()
+            .fold(BTreeMap::new(), |mut composition, &aa| {
+                *composition.entry(aa).or_insert(0) += 1;
+                composition
+            })
+    }
+
+    /// Average molecular weight, in daltons. 0 for an empty chain.
+    pub fn molecular_weight(&self) -> f64 {
+        if self.residues.is_empty() {
+            return 0_f64;
+        }
+        self.standard().map(|idx| RESIDUE_MASSES[idx]).sum::<f64>() + WATER_MASS
+    }
+
+    /// pH at which the protein has no net charge, to two decimals
+    pub fn isoelectric_point(&self) -> f64 {
+        let (mut low, mut high): (f64, f64) = (0_f64, 14_f64);
+        while high - low > 0.001 {
+            let middle: f64 = (low + high) / 2_f64;
+            match self.charge(middle) > 0_f64 {
+                true => low = middle,
+                false => high = middle,
+            }
+        }
+        ((low + high) * 50_f64).round() / 100_f64
+    }
+
+    /// Net charge at a given pH
+    pub fn charge(&self, ph: f64) -> f64 {
+        let positive = |pka: f64| 1_f64 / (1_f64 + 10_f64.powf(ph - pka));
+        let negative = |pka: f64| 1_f64 / (1_f64 + 10_f64.powf(pka - ph));
+        let count = |aa: char| self.residues.iter().filter(|&&r| r == aa).count() as f64;
+        let side_chains: f64 = POSITIVE_PKAS
+            .iter()
+            .map(|&(aa, pka)| count(aa) * positive(pka))
+            .sum::<f64>()
+            - NEGATIVE_PKAS
+                .iter()
+                .map(|&(aa, pka)| count(aa) * negative(pka))
+                .sum::<f64>();
+        positive(N_TERMINUS_PKA) - negative(C_TERMINUS_PKA) + side_chains
+    }
+
+    /// Molar extinction coefficients at 280 nm, in M⁻¹ cm⁻¹: with every cysteine reduced, and with
+    /// every pair of them forming a cystine
+    pub fn extinction_coefficients(&self) -> (usize, usize) {
+        let count = |aa: char| self.residues.iter().filter(|&&r| r == aa).count();
+        let reduced: usize = count('w') * TRYPTOPHAN_EXTINCTION + count('y') * TYROSINE_EXTINCTION;
+        (reduced, reduced + count('c') / 2 * CYSTINE_EXTINCTION)
+    }
+
+    /// Grand average of hydropathy: the mean Kyte-Doolittle hydropathy of the aminoacids. 0 for an
+    /// empty chain.
+    pub fn gravy(&self) -> f64 {
+        let hydropathies: Vec<f64> = self.standard().map(|idx| HYDROPATHY[idx]).collect();
+        match hydropathies.is_empty() {
+            true => 0_f64,
+            false => hydropathies.iter().sum::<f64>() / hydropathies.len() as f64,
+        }
+    }
+
+    /// Instability index (Guruprasad et al., 1990). Proteins below [STABILITY_THRESHOLD] are
+    /// predicted to be stable in a test tube. 0 for an empty chain.
+    pub fn instability_index(&self) -> f64 {
+        if self.residues.is_empty() {
+            return 0_f64;
+        }
+        let weights: f64 = self
+            .residues
+            .windows(2)
+            .filter_map(|pair| Some(DIWV[standard_index(pair[0])?][standard_index(pair[1])?]))
+            .sum();
+        weights * 10_f64 / self.residues.len() as f64
+    }
+
+    /// Indices in the tables of the standard aminoacids of the chain
+    fn standard(&self) -> impl Iterator<Item = usize> + '_ {
+        self.residues.iter().filter_map(|&aa| standard_index(aa))
+    }
+}
+
+/// Index of a lowercase standard aminoacid in the tables
+fn standard_index(aminoacid: char) -> Option<usize> {
+    STANDARD_AMINOACIDS.find(aminoacid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::{
+        fasta::domain::fasta::Fasta,
+        protein::domain::{analysis::ProteinAnalysis, protein::Protein},
+    };
+
+    fn analysis(letters: &str) -> ProteinAnalysis {
+        ProteinAnalysis::from(&Protein::read(&Fasta::from(("", letters))))
+    }
+
+    #[test]
+    fn protein_length_and_composition() {
+        let analysis: ProteinAnalysis = analysis("MKkA-W*");
+        assert_eq!(analysis.length(), 5);
+        let composition: Vec<(char, usize)> = analysis.composition().into_iter().collect();
+        assert_eq!(composition, [('a', 1), ('k', 2), ('m', 1), ('w', 1)]);
+    }
+
+    #[test]
+    fn protein_weight_and_extinction() {
+        let analysis: ProteinAnalysis = analysis("ACDW");
+        assert!((analysis.molecular_weight() - 493.53464).abs() < 1e-6);
+        assert_eq!(analysis.extinction_coefficients(), (5500, 5500));
+        let cystines: ProteinAnalysis = self::analysis("CCYCCC");
+        assert_eq!(cystines.extinction_coefficients(), (1490, 1740));
+        assert_eq!(self::analysis("").molecular_weight(), 0_f64);
+    }
+
+    #[test]
+    fn protein_isoelectric_point() {
+        assert_eq!(analysis("ACDW").isoelectric_point(), 3.75);
+        assert_eq!(analysis("KKKR").isoelectric_point(), 11.92);
+        assert_eq!(analysis("MKWVTFISLLLLFSSAYS").isoelectric_point(), 9.3);
+    }
+
+    #[test]
+    fn protein_hydropathy_and_stability() {
+        let analysis: ProteinAnalysis = analysis("ACDW");
+        assert!((analysis.gravy() + 0.025).abs() < 1e-9);
+        assert!((analysis.instability_index() - 165.5).abs() < 1e-9);
+        assert_eq!(self::analysis("AXA").instability_index(), 0_f64);
+    }
+}
@@ -0,0 +1,174 @@
+//! Back-translation: DNA sequences that code for a protein
+use clap::ValueEnum;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+use crate::ctxs::{
+    aminoacid::domain::aminoacid::Aminoacid,
+    codon::domain::{codon::Codon, usage::CodonUsage},
+    fasta::domain::{fasta::Fasta, iupac},
+    protein::domain::protein::Protein,
+};
+
+/// Ambiguous aminoacids, with the aminoacids they stand for
+const AMBIGUOUS_AMINOACIDS: [(char, [char; 2]); 3] =
+    [('b', ['d', 'n']), ('z', ['e', 'q']), ('j', ['i', 'l'])];
+
+/// How to choose a codon for every aminoacid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum BackTranslationMode {
+    /// The codon the usage table uses the most
+    #[default]
+    MostFrequent,
+    /// Any codon, at random, weighted by the usage table if there is one
+    Random,
+    /// A codon with IUPAC ambiguity codes standing for all of them
+    Degenerate,
+}
+
+/// Settings for back-translating proteins with the standard genetic code
+#[derive(Clone, Default)]
+pub struct BackTranslator {
+    /// How to choose a codon for every aminoacid
+    pub mode: BackTranslationMode,
+    /// How often the target organism uses every codon. Without it, every codon is as frequent.
+    pub usage: CodonUsage,
+}
+
+impl BackTranslator {
+    /// An uppercase DNA sequence coding for `protein`, with its header. Aminoacids without codons,
+    /// like `X`, become `NNN`, and gaps `---`. B, Z and J may be any codon of the aminoacids they
+    /// stand for.
+    pub fn backtranslate<R: Rng>(&self, protein: &Protein, rng: &mut R) -> Fasta {
+        let sequence: String = protein
+            .chain
+            .iter()
+            .map(|aminoacid| self.codon_for(aminoacid, rng))
+            .collect::<String>()
+            .to_uppercase();
+        let header: String = protein.header.clone().unwrap_or_default();
+        Fasta::from((header, sequence))
+    }
+
+    /// The bases of the codon chosen for an aminoacid, in DNA letters.
+    fn codon_for<R: Rng>(&self, aminoacid: &Aminoacid, rng: &mut R) -> String {
+        let codons: Vec<Codon> = codons_of(aminoacid);
+        if codons.is_empty() {
+            return match aminoacid.aminoacid {
+                '-' => "---".to_string(),
+                _ => "nnn".to_string(),
+            };
+        }
+        let bases: [char; 3] = match self.mode {
+            BackTranslationMode::MostFrequent => self.most_frequent(&codons).bases(),
+            BackTranslationMode::Random => self.random(&codons, rng).bases(),
+            BackTranslationMode::Degenerate => degenerate(&codons),
+        };
+        bases
+            .iter()
+            .map(|&base| if base == 'u' { 't' } else { base })
+            .collect()
+    }
+
+    /// The most used codon, the first one listed on ties.
+    fn most_frequent<'a>(&self, codons: &'a [Codon]) -> &'a Codon {
+        codons.iter().fold(&codons[0], |best, codon| {
+            match self.usage.usage(codon) > self.usage.usage(best) {
+                true => codon,
+                false => best,
+            }
+        })
+    }
+
+    /// A codon at random, weighted by its usage. Any codon if none is used.
+    fn random<'a, R: Rng>(&self, codons: &'a [Codon], rng: &mut R) -> &'a Codon {
+        let weights: Vec<f64> = codons.iter().map(|codon| self.usage.usage(codon)).collect();
+        match WeightedIndex::new(&weights) {
+            Ok(distribution) => &codons[distribution.sample(rng)],
+            Err(_) => &codons[rng.gen_range(0..codons.len())],
+        }
+    }
+}
+
+/// Codons of an aminoacid in the standard genetic code, those of the aminoacids it stands for if
+/// it is ambiguous.
+fn codons_of(aminoacid: &Aminoacid) -> Vec<Codon> {
+    if !aminoacid.codons.is_empty() {
+        return aminoacid.codons.clone();
+    }
+    AMBIGUOUS_AMINOACIDS
+        .iter()
+        .filter(|(ambiguous, _)| *ambiguous == aminoacid.aminoacid.to_ascii_lowercase())
+        .flat_map(|(_, aminoacids)| aminoacids.iter())
+        .flat_map(|&letter| Aminoacid::from_letter(letter).codons)
+        .collect()
+}
+
+/// A codon with, at every position, the IUPAC code of the bases of all the codons there.
+fn degenerate(codons: &[Codon]) -> [char; 3] {
+    [0, 1, 2].map(|position| {
+        let bases: Vec<char> = codons.iter().map(|codon| codon.bases()[position]).collect();
+        iupac::code_for(&bases).unwrap_or('n')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::ctxs::{
+        codon::domain::usage::CodonUsage,
+        fasta::domain::fasta::Fasta,
+        protein::domain::{
+            backtranslation::{BackTranslationMode, BackTranslator},
+            protein::Protein,
+        },
+    };
+
+    fn backtranslate(translator: &BackTranslator, letters: &str) -> Fasta {
+        let protein: Protein = Protein::read(&Fasta::from(("prot1", letters)));
+        translator.backtranslate(&protein, &mut StdRng::seed_from_u64(7))
+    }
+
+    #[test]
+    fn backtranslate_most_frequent_codons() {
+        let translator: BackTranslator = BackTranslator {
+            mode: BackTranslationMode::MostFrequent,
+            usage: "GCT 1 GCC 30 GCA 2 AAA 5 AAG 10".parse().unwrap(),
+        };
+        let fasta: Fasta = backtranslate(&translator, "MakX-*");
+        assert_eq!(fasta.header.as_str(), "prot1");
+        assert_eq!(fasta.sequence.as_str(), "ATGGCCAAGNNN---TAA");
+    }
+
+    #[test]
+    fn backtranslate_random_codons() {
+        let uniform: BackTranslator = BackTranslator {
+            mode: BackTranslationMode::Random,
+            ..Default::default()
+        };
+        let letters: &str = "MLSRWYBZ*";
+        let fasta: Fasta = backtranslate(&uniform, letters);
+        let protein: String = Protein::from(fasta.clone()).to_string();
+        assert_eq!(&protein[..6], "mlsrwy");
+        assert!("dn".contains(&protein[6..7]) && "eq".contains(&protein[7..8]));
+        assert_eq!(&protein[8..], "*");
+        let weighted: BackTranslator = BackTranslator {
+            mode: BackTranslationMode::Random,
+            usage: "CUG 1 UUA 0".parse::<CodonUsage>().unwrap(),
+        };
+        assert_eq!(
+            backtranslate(&weighted, "LLLL").sequence.as_str(),
+            "CTGCTGCTGCTG"
+        );
+    }
+
+    #[test]
+    fn backtranslate_degenerate_codons() {
+        let translator: BackTranslator = BackTranslator {
+            mode: BackTranslationMode::Degenerate,
+            ..Default::default()
+        };
+        let fasta: Fasta = backtranslate(&translator, "MLSR*BJ");
+        assert_eq!(fasta.sequence.as_str(), "ATGYTNWSNMGNTRRRAYHTN");
+    }
+}
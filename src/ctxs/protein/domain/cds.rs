@@ -0,0 +1,154 @@
+//! Coding sequences annotated as CDS features of GenBank records
+use crate::ctxs::{
+    aminoacid::domain::genetic_code::GeneticCode,
+    fasta::domain::{
+        errors::FastaError,
+        fasta::Fasta,
+        genbank::{Feature, GenBankRecord},
+    },
+    protein::domain::protein::{Protein, TranslationOptions},
+};
+
+/// Tags of the header of a coding sequence, as NCBI writes them, and the qualifiers they are from
+const HEADER_TAGS: [(&str, &str); 4] = [
+    ("gene", "gene"),
+    ("locus_tag", "locus_tag"),
+    ("protein", "product"),
+    ("protein_id", "protein_id"),
+];
+
+/// The bases of a CDS feature, and how to translate them
+pub struct CodingSequence {
+    /// Bases of the feature, in its own strand
+    pub bases: Fasta,
+    /// Genetic code given with `/transl_table`, if any
+    pub code: Option<GeneticCode>,
+    /// Base the first whole codon starts at, from 1, as `/codon_start` tells
+    pub codon_start: usize,
+    /// Whether the feature lacks its first codon, so it is not translated as a start
+    pub is_partial: bool,
+}
+
+impl CodingSequence {
+    /// The coding sequence of the `number`th CDS feature of `record`, named after the record and
+    /// the protein, as NCBI names them. `None` if any of its bases are in another record, or past
+    /// the end of the sequence.
+    pub fn of(
+        record: &GenBankRecord,
+        feature: &Feature,
+        number: usize,
+    ) -> Result<Option<Self>, FastaError> {
+        let Some(bases) = feature.location.bases(&record.fasta) else {
+            return Ok(None);
+        };
+        let code: Option<GeneticCode> = match feature.qualifier("transl_table") {
+            Some(table) => Some(table.parse()?),
+            None => None,
+        };
+        let name: String = match feature.qualifier("protein_id") {
+            Some(protein_id) => format!("{}_cds_{}_{}", record.seqid(), protein_id, number),
+            None => format!("{}_cds_{}", record.seqid(), number),
+        };
+        let tags: String = HEADER_TAGS
+            .iter()
+            .filter_map(|(tag, key)| Some(format!(" [{}={}]", tag, feature.qualifier(key)?)))
+            .collect();
+        let header: String = format!("{}{} [location={}]", name, tags, feature.location);
+        Ok(Some(CodingSequence {
+            bases: Fasta::from((header, bases)),
+            code,
+            codon_start: feature
+                .qualifier("codon_start")
+                .and_then(|start| start.parse().ok())
+                .filter(|start| (1..=3).contains(start))
+                .unwrap_or(1),
+            is_partial: feature.location.is_partial_start(),
+        }))
+    }
+
+    /// The protein the sequence codes for, from its first whole codon, with its own genetic code,
+    /// or `code` if it has none. Its start codon is translated as methionine, and its final stop
+    /// is left out, as in `/translation`.
+    pub fn protein(&self, code: &GeneticCode) -> Protein {
+        let options: TranslationOptions = TranslationOptions {
+            code: self.code.clone().unwrap_or_else(|| code.clone()),
+            cds: !self.is_partial,
+            ..Default::default()
+        };
+        let bases: Fasta = self
+            .bases
+            .slice(self.codon_start - 1, self.bases.sequence.len());
+        let mut protein: Protein = Protein::translate(&bases, &options);
+        if protein
+            .chain
+            .last()
+            .is_some_and(|aminoacid| aminoacid.is_stop())
+        {
+            protein.chain.pop();
+        }
+        protein
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::{
+        aminoacid::domain::genetic_code::GeneticCode,
+        fasta::domain::{
+            fasta::Fasta,
+            genbank::{Feature, GenBankRecord},
+        },
+        protein::domain::cds::CodingSequence,
+    };
+
+    fn cds(location: &str, qualifiers: &[(&str, &str)]) -> Feature {
+        Feature {
+            kind: "CDS".to_string(),
+            location: location.parse().unwrap(),
+            qualifiers: qualifiers
+                .iter()
+                .map(|(key, value)| (key.to_string(), Some(value.to_string())))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn translate_coding_sequences() {
+        let record: GenBankRecord = GenBankRecord {
+            fasta: Fasta::from(("NC_1.1 test", "ccTTAccGGTCATcc")),
+            features: Vec::new(),
+        };
+        let standard: GeneticCode = GeneticCode::standard();
+        let feature: Feature = cds(
+            "complement(join(3..5,8..13))",
+            &[
+                ("gene", "abc"),
+                ("product", "test"),
+                ("protein_id", "NP_1.1"),
+            ],
+        );
+        let coding: CodingSequence = CodingSequence::of(&record, &feature, 1).unwrap().unwrap();
+        assert_eq!(
+            coding.bases.header.as_str(),
+            "NC_1.1_cds_NP_1.1_1 [gene=abc] [protein=test] [protein_id=NP_1.1] \
+             [location=complement(join(3..5,8..13))]"
+        );
+        assert_eq!(coding.bases.sequence.as_str(), "ATGACCTAA");
+        assert_eq!(coding.protein(&standard).to_string(), "mt");
+
+        let partial: Feature = cds("<8..>13", &[("codon_start", "2"), ("transl_table", "11")]);
+        let coding: CodingSequence = CodingSequence::of(&record, &partial, 2).unwrap().unwrap();
+        assert_eq!(coding.bases.sequence.as_str(), "GGTCAT");
+        assert_eq!(coding.protein(&standard).to_string(), "v");
+        assert!(coding
+            .protein(&standard)
+            .header
+            .unwrap()
+            .contains(", table 11 ("));
+
+        let outside: Feature = cds("10..20", &[]);
+        assert!(CodingSequence::of(&record, &outside, 3).unwrap().is_none());
+        let unknown_code: Feature = cds("3..5", &[("transl_table", "99")]);
+        assert!(CodingSequence::of(&record, &unknown_code, 4).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Reading frames of a nucleotide sequence
+use std::{fmt::Display, str::FromStr};
+
+use crate::ctxs::fasta::domain::{errors::FastaError, fasta::Fasta};
+
+/// One of the six reading frames: +1, +2 and +3 start at the first, second and third base of the
+/// sequence; -1, -2 and -3 at the first, second and third base of its reverse-complementary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    /// Bases skipped before the first codon: 0, 1 or 2
+    offset: usize,
+    /// Whether the frame is on the reverse-complementary strand
+    is_reverse: bool,
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let strand: char = match self.is_reverse {
+            true => '-',
+            false => '+',
+        };
+        write!(f, "{}{}", strand, self.offset + 1)
+    }
+}
+
+impl FromStr for Frame {
+    type Err = FastaError;
+
+    /// Parses `1`, `+1`, `-1`... up to `-3`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let frame: i8 = value
+            .trim()
+            .trim_start_matches('+')
+            .parse()
+            .map_err(|_| FastaError::InvalidFrame(value.to_string()))?;
+        match frame {
+            1..=3 => Ok(Frame {
+                offset: (frame - 1) as usize,
+                is_reverse: false,
+            }),
+            -3..=-1 => Ok(Frame {
+                offset: (-frame - 1) as usize,
+                is_reverse: true,
+            }),
+            _ => Err(FastaError::InvalidFrame(value.to_string())),
+        }
+    }
+}
+
+impl Frame {
+    /// The six reading frames, forward ones first
+    pub fn all() -> Vec<Frame> {
+        [false, true]
+            .into_iter()
+            .flat_map(|is_reverse| (0..3).map(move |offset| Frame { offset, is_reverse }))
+            .collect()
+    }
+
+    /// Whether the frame is on the reverse-complementary strand
+    pub fn is_reverse(&self) -> bool {
+        self.is_reverse
+    }
+
+    /// The bases of `fasta` read in this frame. The header is kept.
+    pub fn of(&self, fasta: &Fasta) -> Fasta {
+        let strand: Fasta = match self.is_reverse {
+            true => Fasta {
+                header: fasta.header.clone(),
+                sequence: fasta.reverse_complement().sequence,
+            },
+            false => fasta.clone(),
+        };
+        let length: usize = strand.sequence.len();
+        strand.slice(self.offset.min(length), length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::{fasta::domain::fasta::Fasta, protein::domain::frame::Frame};
+
+    #[test]
+    fn parse_frames() {
+        assert_eq!("+2".parse::<Frame>().unwrap().to_string(), "+2");
+        assert_eq!("3".parse::<Frame>().unwrap().to_string(), "+3");
+        assert_eq!("-1".parse::<Frame>().unwrap().to_string(), "-1");
+        assert!("0".parse::<Frame>().is_err());
+        assert!("-4".parse::<Frame>().is_err());
+        let all: Vec<String> = Frame::all().iter().map(Frame::to_string).collect();
+        assert_eq!(all, ["+1", "+2", "+3", "-1", "-2", "-3"]);
+    }
+
+    #[test]
+    fn read_in_frame() {
+        let fasta: Fasta = Fasta::from(("seq", "atgcGATta"));
+        let bases = |frame: &str| frame.parse::<Frame>().unwrap().of(&fasta);
+        assert_eq!(bases("+1").sequence.as_str(), "atgcGATta");
+        assert_eq!(bases("+2").sequence.as_str(), "tgcGATta");
+        assert_eq!(bases("+3").sequence.as_str(), "gcGATta");
+        assert_eq!(bases("-1").sequence.as_str(), "taATCgcat");
+        assert_eq!(bases("-3").sequence.as_str(), "ATCgcat");
+        assert_eq!(bases("-2").header.as_str(), "seq");
+    }
+}
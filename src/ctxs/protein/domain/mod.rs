@@ -1 +1,6 @@
+pub mod analysis;
+pub mod backtranslation;
+pub mod cds;
+pub mod frame;
+pub mod orf;
 pub mod protein;
@@ -0,0 +1,293 @@
+//! Open reading frames: runs of codons from a start codon to a stop codon
+use clap::ValueEnum;
+
+use crate::ctxs::{
+    aminoacid::domain::{aminoacid::to_rna_codon, genetic_code::GeneticCode},
+    codon::domain::codon::Codon,
+    fasta::domain::fasta::Fasta,
+    protein::domain::{
+        frame::Frame,
+        protein::{Protein, TranslationOptions},
+    },
+};
+
+/// Codons an open reading frame may start with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum StartCodons {
+    /// Only `ATG`
+    #[default]
+    Atg,
+    /// Any start codon of the genetic code, alternative ones included
+    Alternative,
+    /// Any codon but stops, so frames run from stop to stop
+    Any,
+}
+
+/// Formats for the coordinates of open reading frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CoordinateFormat {
+    /// BED6: 0-based, half-open positions
+    #[default]
+    Bed,
+    /// GFF3: 1-based, closed positions
+    Gff3,
+}
+
+impl CoordinateFormat {
+    /// Line to write before any coordinates, if the format has one
+    pub fn header(&self) -> Option<&'static str> {
+        match self {
+            CoordinateFormat::Bed => None,
+            CoordinateFormat::Gff3 => Some("##gff-version 3\n"),
+        }
+    }
+}
+
+/// Settings for finding open reading frames in all six frames of a sequence.
+#[derive(Clone, Default)]
+pub struct OrfFinder {
+    /// Genetic code telling start and stop codons apart
+    pub code: GeneticCode,
+    /// Codons a frame may start with
+    pub starts: StartCodons,
+    /// Frames with fewer aminoacids than this, stop not included, are left out
+    pub min_length: usize,
+    /// Whether to keep every frame starting inside a longer one and sharing its stop, instead of
+    /// just the longest
+    pub nested: bool,
+}
+
+/// An open reading frame of a sequence, stop codon included
+pub struct Orf {
+    /// First word of the header of the sequence
+    pub seqid: String,
+    /// Number of the frame in its sequence, from 1
+    pub number: usize,
+    /// Reading frame it was found in
+    pub frame: Frame,
+    /// First base, 0-based, on the forward strand
+    pub start: usize,
+    /// Base after the last one, on the forward strand
+    pub end: usize,
+    /// Bases of the frame, in its own strand
+    pub bases: Fasta,
+}
+
+impl OrfFinder {
+    /// Finds the open reading frames of every frame of `fasta`, forward frames first. Frames
+    /// without a stop codon are left out.
+    pub fn find(&self, fasta: &Fasta) -> Vec<Orf> {
+        let seqid: &str = fasta
+            .header
+            .as_str()
+            .split_whitespace()
+            .next()
+            .unwrap_or("");
+        let length: usize = fasta.sequence.len();
+        Frame::all()
+            .into_iter()
+            .flat_map(|frame| self.find_in_frame(fasta, frame, length))
+            .enumerate()
+            .map(|(idx, (frame, start, end, bases))| {
+                let number: usize = idx + 1;
+                let header: String = format!(
+                    "{}, orf {} frame {} {}-{}",
+                    fasta.header.as_str(),
+                    number,
+                    frame,
+                    start + 1,
+                    end
+                );
+                Orf {
+                    seqid: seqid.to_string(),
+                    number,
+                    frame,
+                    start,
+                    end,
+                    bases: Fasta::from((header, bases)),
+                }
+            })
+            .collect()
+    }
+
+    /// Frame, forward start and end, and bases of every open reading frame in a frame.
+    fn find_in_frame(
+        &self,
+        fasta: &Fasta,
+        frame: Frame,
+        length: usize,
+    ) -> Vec<(Frame, usize, usize, String)> {
+        let strand: Vec<char> = frame.of(fasta).sequence.get_chars().collect();
+        let offset: usize = length - strand.len();
+        let codons: Vec<Codon> = strand
+            .chunks_exact(3)
+            .map(|bases| Codon::from_chars([bases[0], bases[1], bases[2]]))
+            .collect();
+        let mut orfs: Vec<(Frame, usize, usize, String)> = Vec::new();
+        let mut open_starts: Vec<usize> = Vec::new();
+        for (idx, codon) in codons.iter().enumerate() {
+            if !self.code.translate(codon.bases()).is_stop() {
+                if self.is_start(codon) {
+                    open_starts.push(idx);
+                }
+                continue;
+            }
+            let starts: &[usize] = match self.nested {
+                true => &open_starts,
+                false => &open_starts[..open_starts.len().min(1)],
+            };
+            for &start in starts
+                .iter()
+                .filter(|&&start| idx - start >= self.min_length)
+            {
+                let (first, last) = (start * 3, (idx + 1) * 3);
+                let bases: String = strand[first..last].iter().collect();
+                let (start, end) = match frame.is_reverse() {
+                    true => (length - offset - last, length - offset - first),
+                    false => (offset + first, offset + last),
+                };
+                orfs.push((frame, start, end, bases));
+            }
+            open_starts.clear();
+        }
+        orfs
+    }
+
+    fn is_start(&self, codon: &Codon) -> bool {
+        match self.starts {
+            StartCodons::Atg => to_rna_codon(codon.bases()) == ['a', 'u', 'g'],
+            StartCodons::Alternative => self.code.is_start(codon.bases()),
+            StartCodons::Any => true,
+        }
+    }
+}
+
+impl Orf {
+    /// The protein the frame codes for, its start codon translated as methionine
+    pub fn protein(&self, code: &GeneticCode) -> Protein {
+        let options: TranslationOptions = TranslationOptions {
+            code: code.clone(),
+            cds: true,
+            ..Default::default()
+        };
+        Protein {
+            header: Some(self.bases.header.as_str().to_string()),
+            ..Protein::translate(&self.bases, &options)
+        }
+    }
+
+    /// A line with the coordinates of the frame, in the given format
+    pub fn coordinates(&self, format: CoordinateFormat) -> String {
+        let strand: char = match self.frame.is_reverse() {
+            true => '-',
+            false => '+',
+        };
+        let name: String = format!("{}_orf{}", self.seqid, self.number);
+        match format {
+            CoordinateFormat::Bed => format!(
+                "{}\t{}\t{}\t{}\t0\t{}\n",
+                self.seqid, self.start, self.end, name, strand
+            ),
+            CoordinateFormat::Gff3 => format!(
+                "{}\tfasta_cli_toolkit\tORF\t{}\t{}\t.\t{}\t0\tID={};frame={}\n",
+                self.seqid,
+                self.start + 1,
+                self.end,
+                strand,
+                name,
+                self.frame
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctxs::{
+        aminoacid::domain::genetic_code::GeneticCode,
+        fasta::domain::fasta::Fasta,
+        protein::domain::orf::{CoordinateFormat, Orf, OrfFinder, StartCodons},
+    };
+
+    fn finder() -> OrfFinder {
+        OrfFinder {
+            min_length: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn find_forward_orfs() {
+        let fasta: Fasta = Fasta::from(("seq1 test", "ccATGaaaATGcccTAAgg"));
+        let orfs: Vec<Orf> = finder().find(&fasta);
+        assert_eq!(orfs.len(), 1);
+        assert_eq!((orfs[0].start, orfs[0].end), (2, 17));
+        assert_eq!(orfs[0].bases.sequence.as_str(), "ATGaaaATGcccTAA");
+        assert_eq!(
+            orfs[0].bases.header.as_str(),
+            "seq1 test, orf 1 frame +3 3-17"
+        );
+        let protein: String = orfs[0].protein(&GeneticCode::standard()).to_string();
+        assert_eq!(protein, "mkmp*");
+    }
+
+    #[test]
+    fn find_nested_and_short_orfs() {
+        let fasta: Fasta = Fasta::from(("seq1", "ccATGaaaATGcccTAAgg"));
+        let nested: OrfFinder = OrfFinder {
+            nested: true,
+            ..finder()
+        };
+        let orfs: Vec<Orf> = nested.find(&fasta);
+        let bases: Vec<String> = orfs
+            .iter()
+            .map(|orf| orf.bases.sequence.as_str().to_string())
+            .collect();
+        assert_eq!(bases, ["ATGaaaATGcccTAA", "ATGcccTAA"]);
+        let long: OrfFinder = OrfFinder {
+            min_length: 3,
+            ..nested
+        };
+        assert_eq!(long.find(&fasta).len(), 1);
+    }
+
+    #[test]
+    fn find_reverse_orfs() {
+        // Reverse-complementary: ccTTAgggCATttt
+        let fasta: Fasta = Fasta::from(("seq2", "aaaATGcccTAAgg"));
+        let any: OrfFinder = OrfFinder {
+            starts: StartCodons::Any,
+            min_length: 1,
+            ..Default::default()
+        };
+        let reverse: Vec<Orf> = any
+            .find(&fasta)
+            .into_iter()
+            .filter(|orf| orf.frame.is_reverse())
+            .collect();
+        assert_eq!(reverse.len(), 1);
+        assert_eq!(reverse[0].bases.sequence.as_str(), "ccTTAg");
+        assert_eq!((reverse[0].start, reverse[0].end), (8, 14));
+        assert_eq!(
+            reverse[0].coordinates(CoordinateFormat::Bed),
+            "seq2\t8\t14\tseq2_orf2\t0\t-\n"
+        );
+        assert_eq!(
+            reverse[0].coordinates(CoordinateFormat::Gff3),
+            "seq2\tfasta_cli_toolkit\tORF\t9\t14\t.\t-\t0\tID=seq2_orf2;frame=-1\n"
+        );
+    }
+
+    #[test]
+    fn find_alternative_starts() {
+        let fasta: Fasta = Fasta::from(("seq3", "TTGaaacccTGA"));
+        assert!(finder().find(&fasta).is_empty());
+        let alternative: OrfFinder = OrfFinder {
+            starts: StartCodons::Alternative,
+            ..finder()
+        };
+        let orfs: Vec<Orf> = alternative.find(&fasta);
+        assert_eq!(orfs.len(), 1);
+        assert_eq!(orfs[0].protein(&alternative.code).to_string(), "mkp*");
+    }
+}
@@ -1,9 +1,26 @@
 use std::fmt::{self, Display};
 
-use crate::ctxs::{aminoacid::domain::aminoacid::Aminoacid, fasta::domain::fasta::Fasta};
+use clap::ValueEnum;
+
+use crate::ctxs::{
+    aminoacid::domain::{
+        aminoacid::Aminoacid,
+        genetic_code::{GeneticCode, STANDARD_CODE},
+    },
+    fasta::domain::{
+        alphabet::{Alphabet, SequenceAlphabet},
+        fasta::Fasta,
+    },
+    protein::domain::frame::Frame,
+};
+
+/// Lowest confidence of the guessed alphabet of a sequence for its codons to be checked against it
+pub const SURE_ALPHABET: f64 = 0.9;
 
 /// Struct representing a protein: a chain of aminoacids
 pub struct Protein {
+    /// header: Where the protein comes from, if known
+    pub header: Option<String>,
     /// chain: A Vector holding Aminoacid structs
     pub chain: Vec<Aminoacid>,
 }
@@ -17,26 +34,152 @@ pub struct Protein {
 /// ```
 impl From<Vec<Aminoacid>> for Protein {
     fn from(aminoacids: std::vec::Vec<Aminoacid>) -> Self {
-        Protein { chain: aminoacids }
+        Protein {
+            header: None,
+            chain: aminoacids,
+        }
     }
 }
 
+/// How to translate nucleotides to aminoacids
+#[derive(Clone, Default)]
+pub struct TranslationOptions {
+    /// Genetic code to translate codons with
+    pub code: GeneticCode,
+    /// Whether the sequence is a coding one, so its first codon, if it is any start codon of the
+    /// genetic code, is translated as methionine
+    pub cds: bool,
+    /// Alphabet of the sequences, DNA or RNA. Codons with bases of another alphabet translate to the
+    /// unknown aminoacid. If `None`, the alphabet of each sequence is used when its confidence is
+    /// at least [SURE_ALPHABET], so a few ambiguity codes do not turn the check off, and both DNA
+    /// and RNA codons are translated when it is not
+    pub alphabet: Option<Alphabet>,
+    /// What to do with the bases left after the last whole codon
+    pub partial: PartialCodon,
+    /// Whether to skip the bases before the first `ATG`, starting the translation there
+    pub from_atg: bool,
+    /// Whether to end the translation at the first stop codon
+    pub to_stop: bool,
+    /// Whether to leave stop codons out of the protein, instead of writing them as `*`
+    pub omit_stops: bool,
+}
+
+/// What to do with an incomplete codon at the end of a sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PartialCodon {
+    /// Leave it untranslated
+    #[default]
+    Drop,
+    /// Translate it as the unknown aminoacid, `x`
+    Unknown,
+}
+
+/// Translates a fasta sequence with the standard genetic code.
 impl From<Fasta> for Protein {
     fn from(value: Fasta) -> Self {
-        let rna_sequence_spl: Vec<char> = value.sequence.get_chars().collect();
-        let aa_seq = {
-            let mut aa_seq_tmp: Vec<Aminoacid> = Vec::new();
-            for gidx in (0..(rna_sequence_spl.len())).step_by(3) {
-                let group: [char; 3] = [
-                    rna_sequence_spl[gidx],
-                    rna_sequence_spl[gidx + 1],
-                    rna_sequence_spl[gidx + 2],
-                ];
-                aa_seq_tmp.push(Aminoacid::from(group));
+        Protein::translate(&value, &TranslationOptions::default())
+    }
+}
+
+impl Protein {
+    /// Reads the letters of a protein fasta sequence, keeping its header.
+    pub fn read(value: &Fasta) -> Self {
+        Protein {
+            header: Some(value.header.as_str().to_string()),
+            chain: value
+                .sequence
+                .get_chars()
+                .map(Aminoacid::from_letter)
+                .collect(),
+        }
+    }
+
+    /// Translates a DNA or RNA fasta sequence as `options` tell. The protein has the header of the
+    /// sequence, tagged with the genetic code if it is not the standard one.
+    pub fn translate(value: &Fasta, options: &TranslationOptions) -> Self {
+        Protein {
+            header: Some(annotate(value.header.as_str().to_string(), &options.code)),
+            ..Protein::from(translate_chain(value, options))
+        }
+    }
+
+    /// Translates every given reading frame of a DNA or RNA fasta sequence as `options` tell. Every
+    /// protein has the header of the sequence, tagged with its frame and, if it is not the standard
+    /// one, the genetic code.
+    pub fn translate_frames(
+        value: &Fasta,
+        frames: &[Frame],
+        options: &TranslationOptions,
+    ) -> Vec<Self> {
+        frames
+            .iter()
+            .map(|frame| {
+                let header: String = format!("{}, frame {}", value.header.as_str(), frame);
+                Protein {
+                    header: Some(annotate(header, &options.code)),
+                    ..Protein::from(translate_chain(&frame.of(value), options))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Tags a header with the genetic code, unless it is the standard one.
+fn annotate(header: String, code: &GeneticCode) -> String {
+    match code.id {
+        STANDARD_CODE => header,
+        _ => format!("{}, {}", header, code),
+    }
+}
+
+/// Translates the bases of a DNA or RNA fasta sequence as `options` tell.
+fn translate_chain(value: &Fasta, options: &TranslationOptions) -> Vec<Aminoacid> {
+    let rna_sequence_spl: Vec<char> = value.sequence.get_chars().collect();
+    let sequence_alphabet: SequenceAlphabet = value.sequence.alphabet();
+    let alphabet: Option<Alphabet> = options.alphabet.or(
+        match sequence_alphabet.confidence >= SURE_ALPHABET {
+            true => sequence_alphabet.alphabet,
+            false => None,
+        },
+    );
+    let start: usize = match options.from_atg {
+        true => match value.sequence.find("atg") {
+            Some(start) => start,
+            None => return Vec::new(),
+        },
+        false => 0,
+    };
+    {
+        let mut aa_seq_tmp: Vec<Aminoacid> = Vec::new();
+        for (cidx, bases) in rna_sequence_spl[start..].chunks(3).enumerate() {
+            let &[first, second, third] = bases else {
+                if options.partial == PartialCodon::Unknown {
+                    aa_seq_tmp.push(Aminoacid::unknown());
+                }
+                break;
+            };
+            let group: [char; 3] = [first, second, third];
+            let is_foreign: bool =
+                alphabet.is_some_and(|alphabet| !group.iter().all(|&b| alphabet.is_valid(b)));
+            let aminoacid: Aminoacid = match is_foreign {
+                true => Aminoacid::unknown(),
+                false if cidx == 0 && options.cds && options.code.is_start(group) => {
+                    options.code.translate(['a', 'u', 'g'])
+                }
+                false => options.code.translate(group),
+            };
+            if aminoacid.is_stop() {
+                if !options.omit_stops {
+                    aa_seq_tmp.push(aminoacid);
+                }
+                if options.to_stop {
+                    break;
+                }
+                continue;
             }
-            aa_seq_tmp
-        };
-        Protein::from(aa_seq)
+            aa_seq_tmp.push(aminoacid);
+        }
+        aa_seq_tmp
     }
 }
 
@@ -53,8 +196,12 @@ impl Display for Protein {
 #[cfg(test)]
 mod tests {
     use crate::ctxs::{
-        aminoacid::domain::aminoacid::Aminoacid, fasta::domain::fasta::Fasta,
-        protein::domain::protein::Protein,
+        aminoacid::domain::{aminoacid::Aminoacid, genetic_code::GeneticCode},
+        fasta::domain::{alphabet::Alphabet, fasta::Fasta},
+        protein::domain::{
+            frame::Frame,
+            protein::{PartialCodon, Protein, TranslationOptions},
+        },
     };
 
     #[test]
@@ -69,6 +216,16 @@ mod tests {
         assert_eq!(proteins.to_string(), "mmmm".to_string())
     }
 
+    #[test]
+    fn protein_from_letters() {
+        let protein: Protein = Protein::read(&Fasta::from(("prot1", "MKwX*")));
+        assert_eq!(protein.header.as_deref(), Some("prot1"));
+        assert_eq!(protein.to_string(), "mkwx*");
+        assert_eq!(protein.chain[0].codons.len(), 1);
+        assert!(protein.chain[3].codons.is_empty());
+        assert!(protein.chain[4].is_stop());
+    }
+
     #[test]
     fn protein_from_fasta() {
         {
@@ -82,4 +239,115 @@ mod tests {
             assert_eq!(aa_sequence.to_string(), "mrr*".to_string())
         }
     }
+
+    #[test]
+    fn protein_from_ambiguous_fasta() {
+        let ff = Fasta::from(("", "auggcnaygnnn"));
+        let aa_sequence: Protein = Protein::from(ff);
+        assert_eq!(aa_sequence.to_string(), "maxx".to_string())
+    }
+
+    #[test]
+    fn protein_with_genetic_code() {
+        let ff = Fasta::from(("", "augugaagaaua"));
+        assert_eq!(Protein::from(ff.clone()).to_string(), "m*ri");
+        let mut options: TranslationOptions = TranslationOptions {
+            code: GeneticCode::from_id(2).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "mw*m");
+        let ff = Fasta::from(("", "auaauaugg"));
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "mmw");
+        options.code = GeneticCode::from_id(11).unwrap();
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "iiw");
+        options.cds = true;
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "miw");
+    }
+
+    #[test]
+    fn protein_from_dna_fasta() {
+        let ff = Fasta::from(("", "ATGgcctaa"));
+        assert_eq!(Protein::from(ff).to_string(), "ma*");
+        let ff = Fasta::from(("", "augtaa"));
+        let mut options: TranslationOptions = TranslationOptions::default();
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "m*");
+        options.alphabet = Some(Alphabet::Dna);
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "x*");
+        options.alphabet = Some(Alphabet::Rna);
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "mx");
+        let ambiguous = Fasta::from(("", "atgtttttttttttttttaugcgr"));
+        options.alphabet = None;
+        assert_eq!(Protein::translate(&ambiguous, &options).to_string(), "mfffffxr");
+    }
+
+    #[test]
+    fn protein_from_six_frames() {
+        let ff = Fasta::from(("seq", "atggcctaagc"));
+        let proteins: Vec<Protein> =
+            Protein::translate_frames(&ff, &Frame::all(), &TranslationOptions::default());
+        let headers: Vec<&str> = proteins.iter().flat_map(|p| p.header.as_deref()).collect();
+        assert_eq!(
+            headers,
+            [
+                "seq, frame +1",
+                "seq, frame +2",
+                "seq, frame +3",
+                "seq, frame -1",
+                "seq, frame -2",
+                "seq, frame -3"
+            ]
+        );
+        let chains: Vec<String> = proteins.iter().map(Protein::to_string).collect();
+        assert_eq!(chains, ["ma*", "wpk", "gls", "a*a", "lrp", "lgh"]);
+    }
+
+    #[test]
+    fn protein_headers_from_source() {
+        let ff = Fasta::from(("seq1 desc", "auggcc"));
+        assert_eq!(
+            Protein::from(ff.clone()).header.as_deref(),
+            Some("seq1 desc")
+        );
+        let options: TranslationOptions = TranslationOptions {
+            code: GeneticCode::from_id(2).unwrap(),
+            ..Default::default()
+        };
+        let frames: Vec<Protein> = Protein::translate_frames(&ff, &Frame::all()[3..4], &options);
+        assert_eq!(
+            frames[0].header.as_deref(),
+            Some("seq1 desc, frame -1, table 2 (Vertebrate Mitochondrial)")
+        );
+    }
+
+    #[test]
+    fn protein_from_partial_codons() {
+        let ff = Fasta::from(("", "auggcuga"));
+        assert_eq!(Protein::from(ff.clone()).to_string(), "ma");
+        let options: TranslationOptions = TranslationOptions {
+            partial: PartialCodon::Unknown,
+            ..Default::default()
+        };
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "max");
+        assert_eq!(Protein::from(Fasta::from(("", "a"))).to_string(), "");
+    }
+
+    #[test]
+    fn protein_with_stops() {
+        let ff = Fasta::from(("", "ccATGgcuuaaggcuga"));
+        assert_eq!(Protein::from(ff.clone()).to_string(), "pwlka");
+        let mut options: TranslationOptions = TranslationOptions {
+            from_atg: true,
+            ..Default::default()
+        };
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "ma*g*");
+        options.omit_stops = true;
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "mag");
+        options.omit_stops = false;
+        options.to_stop = true;
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "ma*");
+        options.omit_stops = true;
+        assert_eq!(Protein::translate(&ff, &options).to_string(), "ma");
+        let without_atg = Fasta::from(("", "ccgcuuaa"));
+        assert_eq!(Protein::translate(&without_atg, &options).to_string(), "");
+    }
 }
@@ -1,2 +1 @@
 pub mod domain;
-pub mod infrastructure;
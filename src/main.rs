@@ -2,7 +2,7 @@ mod apps;
 mod ctxs;
 use apps::args::{Arguments, Command, FastaOperation};
 use clap::Parser;
-use ctxs::fasta::application::{edit, make, view};
+use ctxs::fasta::application::{cds, codons, convert, edit, index, make, orfs, view};
 mod shared;
 
 // ----------------
@@ -11,43 +11,167 @@ mod shared;
 fn main() {
     let args = Arguments::parse();
 
+    // Commands that write sequences may be writing them to the standard output, so their status
+    // messages go to the standard error, to keep pipelines clean.
+    let is_report: bool = matches!(
+        args.cmdline,
+        Command::Print(_) | Command::Analyze(_) | Command::Validate(_)
+    );
+
+    let io = args.io_options();
     let result = match args.cmdline {
         Command::Cut(args) => edit::cut(
-            args.input_file_name,
-            args.output_file_name,
+            args.input_file_name.as_deref(),
+            args.output_file_name.as_deref(),
             args.from,
             args.to,
+            &io,
+        )
+        .unwrap_or_else(|e| format!("Could not cut: {:#}", e)),
+        Command::Generate(args) => {
+            make::generate(args.length, args.output_file.as_deref(), args.is_rna, &io)
+                .unwrap_or_else(|e| format!("Could not generate: {:#}", e))
+        }
+        Command::Print(args) => view::cat(args.file.as_deref(), &io)
+            .unwrap_or_else(|e| format!("Could not print file: {:#}", e)),
+        Command::Format(args) => edit::format(
+            args.file.as_deref(),
+            args.case(),
+            args.output_file.as_deref(),
+            &io,
+        )
+        .unwrap_or_else(|e| format!("Could not format: {:#}", e)),
+        Command::Mask(args) => edit::mask(
+            args.file.as_deref(),
+            args.output_file.as_deref(),
+            args.mode,
+            &io,
         )
-        .unwrap_or_else(|_| String::from("Could not cut")),
-        Command::Generate(args) => make::generate(args.length, args.output_file, args.is_rna)
-            .unwrap_or_else(|_| String::from("Could not generate")),
-        Command::Print(args) => {
-            view::cat(&args.file).unwrap_or_else(|_| String::from("Could not print file"))
+        .unwrap_or_else(|e| format!("Could not mask: {:#}", e)),
+        Command::Analyze(args) => view::analize(args.file.as_deref(), &io)
+            .unwrap_or_else(|e| format!("Could not analyze: {:#}", e)),
+        Command::Trim(args) => edit::trim(
+            args.file.as_deref(),
+            args.output_file.as_deref(),
+            &args.trimmer(),
+            args.fasta,
+            &io,
+        )
+        .unwrap_or_else(|e| format!("Could not trim: {:#}", e)),
+        Command::Faidx(args) => {
+            index::faidx(&args.file).unwrap_or_else(|e| format!("Could not index: {:#}", e))
         }
-        Command::Format(args) => edit::format(args.file, args.uppercase, args.output_file)
-            .unwrap_or_else(|_| String::from("Could not format")),
-        Command::Analyze(args) => {
-            view::analize(&args.file).unwrap_or_else(|_| String::from("Could not analyze"))
+        Command::Region(args) => {
+            index::region(&args.file, &args.regions, args.output_file.as_deref(), &io)
+                .unwrap_or_else(|e| format!("Could not extract regions: {:#}", e))
         }
+        Command::Validate(args) => view::validate(args.file.as_deref(), &io)
+            .unwrap_or_else(|e| format!("Could not validate: {:#}", e)),
+        Command::Orfs(args) => orfs::find_orfs(
+            args.file.as_deref(),
+            args.ofile.as_deref(),
+            &args.finder(),
+            args.protein,
+            args.coordinates
+                .as_deref()
+                .map(|coordinates| (coordinates, args.coordinates_format)),
+            &io,
+        )
+        .unwrap_or_else(|e| format!("Could not find open reading frames: {:#}", e)),
+        Command::Backtranslate(args) => make::backtranslate(
+            args.file.as_deref(),
+            args.ofile.as_deref(),
+            args.mode,
+            args.usage.as_deref(),
+            args.seed,
+            &io,
+        )
+        .unwrap_or_else(|e| format!("Could not back-translate: {:#}", e)),
+        Command::CodonUsage(args) => codons::codon_usage(
+            args.file.as_deref(),
+            args.ofile.as_deref(),
+            &args.table,
+            args.reference.as_deref(),
+            args.genes.as_deref(),
+            args.format,
+            &io,
+        )
+        .unwrap_or_else(|e| format!("Could not count codons: {:#}", e)),
+        Command::OptimizeCodons(args) => codons::read_usage(&args.usage)
+            .and_then(|usage| {
+                codons::optimize_codons(
+                    args.file.as_deref(),
+                    args.ofile.as_deref(),
+                    &args.optimizer(usage),
+                    &io,
+                )
+            })
+            .unwrap_or_else(|e| format!("Could not optimize codons: {:#}", e)),
+        Command::Convert(args) => {
+            convert::convert(args.file.as_deref(), args.ofile.as_deref(), args.to, &io)
+                .unwrap_or_else(|e| format!("Could not convert: {:#}", e))
+        }
+        Command::Cds(args) => cds::extract_cds(
+            args.file.as_deref(),
+            args.ofile.as_deref(),
+            args.protein,
+            &args.table,
+            &io,
+        )
+        .unwrap_or_else(|e| format!("Could not extract coding sequences: {:#}", e)),
         Command::Get(args) => match args {
-            FastaOperation::Reverse(fst) => {
-                make::operate_on_chain(fst.file, fst.ofile, make::FastaAllowedOperations::Reverse)
-                    .unwrap_or_else(|_| String::from("Could not get reverse strand"))
-            }
+            FastaOperation::Reverse(fst) => make::operate_on_chain(
+                fst.file.as_deref(),
+                fst.ofile.as_deref(),
+                make::FastaAllowedOperations::Reverse,
+                &io,
+            )
+            .unwrap_or_else(|e| format!("Could not get reverse strand: {:#}", e)),
             FastaOperation::Complementary(fst) => make::operate_on_chain(
-                fst.file,
-                fst.ofile,
+                fst.file.as_deref(),
+                fst.ofile.as_deref(),
                 make::FastaAllowedOperations::Complement,
+                &io,
+            )
+            .unwrap_or_else(|e| format!("Could not get complementary strand: {:#}", e)),
+            FastaOperation::Revcomp(fst) => make::operate_on_chain(
+                fst.file.as_deref(),
+                fst.ofile.as_deref(),
+                make::FastaAllowedOperations::Both,
+                &io,
+            )
+            .unwrap_or_else(|e| format!("Could not get reverse-complementary strand: {:#}", e)),
+            FastaOperation::Transcribe(fst) => make::operate_on_chain(
+                fst.file.as_deref(),
+                fst.ofile.as_deref(),
+                make::FastaAllowedOperations::Transcribe(fst.strand),
+                &io,
+            )
+            .unwrap_or_else(|e| format!("Could not transcribe: {:#}", e)),
+            FastaOperation::ReverseTranscribe(fst) => make::operate_on_chain(
+                fst.file.as_deref(),
+                fst.ofile.as_deref(),
+                make::FastaAllowedOperations::ReverseTranscribe(fst.strand),
+                &io,
+            )
+            .unwrap_or_else(|e| format!("Could not reverse-transcribe: {:#}", e)),
+            FastaOperation::Amioacids(fst) => make::to_aacids(
+                fst.file.as_deref(),
+                fst.ofile.as_deref(),
+                &fst.translation_options(),
+                &fst.frames(),
+                fst.layout(),
+                &io,
             )
-            .unwrap_or_else(|_| String::from("Could not get complementary strand")),
-            FastaOperation::Revcomp(fst) => {
-                make::operate_on_chain(fst.file, fst.ofile, make::FastaAllowedOperations::Both)
-                    .unwrap_or_else(|_| String::from("Could not get reverse-complementary strand"))
-            }
-            FastaOperation::Amioacids(fst) => make::to_aacids(fst.file, fst.ofile)
-                .unwrap_or_else(|_| String::from("Could not convert to aminoacids")),
+            .unwrap_or_else(|e| format!("Could not convert to aminoacids: {:#}", e)),
         },
     };
 
-    println!("{}", result);
+    if result.is_empty() {
+        return;
+    }
+    match is_report {
+        true => println!("{}", result),
+        false => eprintln!("{}", result),
+    }
 }
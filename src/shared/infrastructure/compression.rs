@@ -0,0 +1,537 @@
+//! Compressed streams: gzip, BGZF and zstd.
+use std::{
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use flate2::{
+    read::{GzDecoder, MultiGzDecoder},
+    write::{DeflateEncoder, GzEncoder},
+    Crc,
+};
+
+/// gzip magic bytes. BGZF files are gzip files too.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// zstd frame magic bytes.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Maximum uncompressed size of a BGZF block, as written by htslib.
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+/// Size of the header of a BGZF block, its `BC` extra field included.
+const BGZF_HEADER_SIZE: usize = 18;
+/// Empty block marking the end of a BGZF file.
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Available compression formats.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Compression {
+    /// Plain text
+    None,
+    /// Plain gzip, streamed in a single member. It cannot be indexed
+    Gzip,
+    /// Blocked gzip, readable by any gzip tool and indexable, as bgzip writes it (`.gz`, `.bgz`)
+    Bgzf,
+    /// zstd (`.zst`)
+    Zstd,
+}
+
+impl Compression {
+    /// Guesses the compression of a stream from its first bytes.
+    pub fn from_magic(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            // BGZF is told apart by its `BC` extra field, but gzip readers handle both.
+            match bytes.len() >= 16 && bytes[3] & 0x04 != 0 && &bytes[12..14] == b"BC" {
+                true => Compression::Bgzf,
+                false => Compression::Gzip,
+            }
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Guesses the compression of a file from its first bytes. Files that cannot be read are
+    /// taken as uncompressed, for opening them to tell why.
+    pub fn of_file(file: &Path) -> Self {
+        let mut header: Vec<u8> = Vec::with_capacity(BGZF_HEADER_SIZE);
+        match std::fs::File::open(file) {
+            Ok(opened) => match opened
+                .take(BGZF_HEADER_SIZE as u64)
+                .read_to_end(&mut header)
+            {
+                Ok(_) => Compression::from_magic(&header),
+                Err(_) => Compression::None,
+            },
+            Err(_) => Compression::None,
+        }
+    }
+
+    /// Guesses the compression to use for a file from its extension. `.gz` files are written as
+    /// BGZF, as bgzip does, so they can be indexed.
+    pub fn from_extension(file: &Path) -> Self {
+        match file.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") | Some("bgz") | Some("bgzf") => Compression::Bgzf,
+            Some("zst") | Some("zstd") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Wraps a reader with a decoder for whatever compression its first bytes show.
+pub(crate) fn decompress<'a, R: BufRead + 'a>(mut reader: R) -> io::Result<Box<dyn BufRead + 'a>> {
+    let compression: Compression = Compression::from_magic(reader.fill_buf()?);
+    Ok(match compression {
+        Compression::Gzip | Compression::Bgzf => {
+            Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+        }
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        Compression::None => Box::new(reader),
+    })
+}
+
+/// Wraps a writer with an encoder for the given compression.
+pub(crate) fn compress<W: Write>(
+    writer: W,
+    compression: Compression,
+) -> io::Result<CompressedWriter<W>> {
+    Ok(match compression {
+        Compression::None => CompressedWriter::None(writer),
+        Compression::Gzip => {
+            CompressedWriter::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+        }
+        Compression::Bgzf => CompressedWriter::Bgzf(BgzfWriter::new(writer)),
+        Compression::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(writer, 0)?),
+    })
+}
+
+/// A writer whose output must be ended explicitly, so that errors doing it are not lost as they
+/// would be on drop.
+pub trait Finish: Write {
+    /// Writes whatever ends the output, and flushes it.
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+impl Finish for Vec<u8> {
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: Finish + ?Sized> Finish for &mut F {
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+/// A writer compressing its output, or not
+pub enum CompressedWriter<W: Write> {
+    None(W),
+    Gzip(GzEncoder<W>),
+    Bgzf(BgzfWriter<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressedWriter::None(writer) => writer.write(buf),
+            CompressedWriter::Gzip(writer) => writer.write(buf),
+            CompressedWriter::Bgzf(writer) => writer.write(buf),
+            CompressedWriter::Zstd(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressedWriter::None(writer) => writer.flush(),
+            CompressedWriter::Gzip(writer) => writer.flush(),
+            CompressedWriter::Bgzf(writer) => writer.flush(),
+            CompressedWriter::Zstd(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Writes the gzip trailer, the end of the zstd frame or the BGZF end-of-file block.
+impl<W: Write> Finish for CompressedWriter<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        match self {
+            CompressedWriter::None(writer) => writer.flush(),
+            CompressedWriter::Gzip(writer) => {
+                writer.try_finish()?;
+                writer.get_mut().flush()
+            }
+            CompressedWriter::Bgzf(writer) => writer.finish(),
+            CompressedWriter::Zstd(writer) => {
+                writer.do_finish()?;
+                writer.get_mut().flush()
+            }
+        }
+    }
+}
+
+/// Writes BGZF: a series of independent gzip members of at most 64 KiB each, whose compressed
+/// size is stored in a `BC` extra field so that readers can seek to block boundaries.
+pub struct BgzfWriter<W: Write> {
+    writer: W,
+    block: Vec<u8>,
+    /// Whether the end-of-file block is written
+    finished: bool,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(writer: W) -> Self {
+        BgzfWriter {
+            writer,
+            block: Vec::with_capacity(BGZF_BLOCK_SIZE),
+            finished: false,
+        }
+    }
+
+    /// Writes the last block and the end-of-file one, and flushes them.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if !self.finished {
+            self.write_block()?;
+            self.writer.write_all(&BGZF_EOF)?;
+            self.finished = true;
+        }
+        self.writer.flush()
+    }
+
+    /// Compresses and writes the buffered bytes as one block.
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&self.block)?;
+        let deflated: Vec<u8> = encoder.finish()?;
+        let mut crc: Crc = Crc::new();
+        crc.update(&self.block);
+
+        // 18 bytes of header, the deflated data, and 8 bytes of trailer. BSIZE is the total
+        // block size minus one.
+        let block_size: u16 = u16::try_from(deflated.len() + 25)
+            .map_err(|_| io::Error::other("BGZF block does not fit in 64 KiB"))?;
+        self.writer.write_all(&[
+            0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, b'B', b'C',
+            0x02, 0x00,
+        ])?;
+        self.writer.write_all(&block_size.to_le_bytes())?;
+        self.writer.write_all(&deflated)?;
+        self.writer.write_all(&crc.sum().to_le_bytes())?;
+        self.writer
+            .write_all(&(self.block.len() as u32).to_le_bytes())?;
+        self.block.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written: usize = buf.len().min(BGZF_BLOCK_SIZE - self.block.len());
+        self.block.extend_from_slice(&buf[..written]);
+        if self.block.len() == BGZF_BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.writer.flush()
+    }
+}
+
+/// Ends the file if `finish` was not called, ignoring errors
+impl<W: Write> Drop for BgzfWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Reads the next BGZF block, header and trailer included. `None` at the end of the input.
+fn read_bgzf_block<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, reason.to_string());
+    let mut block: Vec<u8> = vec![0; 12];
+    let read: usize = reader.take(12).read(&mut block)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    reader.read_exact(&mut block[read..])?;
+    if !block.starts_with(&GZIP_MAGIC) || block[3] & 0x04 == 0 {
+        return Err(invalid("not a BGZF block"));
+    }
+    let extra_length: usize = u16::from_le_bytes([block[10], block[11]]) as usize;
+    block.resize(12 + extra_length, 0);
+    reader.read_exact(&mut block[12..])?;
+    // Subfields of the extra field: two id bytes, a 16 bit length and the data.
+    let mut block_size: Option<usize> = None;
+    let mut subfield: &[u8] = &block[12..];
+    while subfield.len() >= 4 {
+        let length: usize = u16::from_le_bytes([subfield[2], subfield[3]]) as usize;
+        if &subfield[..2] == b"BC" && length == 2 && subfield.len() >= 6 {
+            block_size = Some(u16::from_le_bytes([subfield[4], subfield[5]]) as usize + 1);
+        }
+        subfield = subfield.get(4 + length..).unwrap_or(&[]);
+    }
+    let block_size: usize = block_size.ok_or_else(|| invalid("BGZF block without its size"))?;
+    if block_size < block.len() + 8 {
+        return Err(invalid("BGZF block smaller than its header"));
+    }
+    let header_size: usize = block.len();
+    block.resize(block_size, 0);
+    reader.read_exact(&mut block[header_size..])?;
+    Ok(Some(block))
+}
+
+/// Uncompressed size of a BGZF block, as its trailer tells
+fn bgzf_block_length(block: &[u8]) -> u64 {
+    let trailer: [u8; 4] = block[block.len() - 4..].try_into().unwrap_or_default();
+    u32::from_le_bytes(trailer) as u64
+}
+
+/// Index of the blocks of a BGZF file, as bgzip writes it next to the file, as `<file>.gzi`: the
+/// compressed and uncompressed offsets of every block but the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GziIndex {
+    /// Compressed and uncompressed offsets of every block with data, the first one included
+    blocks: Vec<(u64, u64)>,
+}
+
+impl GziIndex {
+    /// Path of the index of a given BGZF file: the same path with `.gzi` appended.
+    pub fn path_for(file: &Path) -> PathBuf {
+        let mut index_path = file.as_os_str().to_owned();
+        index_path.push(".gzi");
+        PathBuf::from(index_path)
+    }
+
+    /// Indexes a BGZF stream, reading the header and trailer of every block.
+    pub fn build<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut blocks: Vec<(u64, u64)> = vec![(0, 0)];
+        let (mut compressed, mut uncompressed): (u64, u64) = (0, 0);
+        while let Some(block) = read_bgzf_block(&mut reader)? {
+            let length: u64 = bgzf_block_length(&block);
+            if length > 0 && compressed > 0 {
+                blocks.push((compressed, uncompressed));
+            }
+            compressed += block.len() as u64;
+            uncompressed += length;
+        }
+        Ok(GziIndex { blocks })
+    }
+
+    /// Reads an index in `.gzi` format.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut read_u64 = || -> io::Result<u64> {
+            let mut bytes: [u8; 8] = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        };
+        let count: u64 = read_u64()?;
+        let mut blocks: Vec<(u64, u64)> = vec![(0, 0)];
+        for _ in 0..count {
+            blocks.push((read_u64()?, read_u64()?));
+        }
+        Ok(GziIndex { blocks })
+    }
+
+    /// Writes the index in `.gzi` format: the number of blocks but the first, and their offsets,
+    /// as 64 bit little-endian integers.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let blocks: &[(u64, u64)] = &self.blocks[1..];
+        writer.write_all(&(blocks.len() as u64).to_le_bytes())?;
+        for (compressed, uncompressed) in blocks {
+            writer.write_all(&compressed.to_le_bytes())?;
+            writer.write_all(&uncompressed.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Compressed and uncompressed offsets of the block holding the uncompressed `offset`
+    fn block_of(&self, offset: u64) -> (u64, u64) {
+        let after: usize = self
+            .blocks
+            .partition_point(|&(_, uncompressed)| uncompressed <= offset);
+        self.blocks[after.saturating_sub(1)]
+    }
+}
+
+/// Reads a BGZF stream, seeking to uncompressed offsets with its `.gzi` index. Only seeking from
+/// the start is supported.
+pub struct BgzfReader<R: Read + Seek> {
+    reader: R,
+    index: GziIndex,
+    /// Uncompressed bytes of the current block
+    block: Vec<u8>,
+    /// Position in `block` of the next byte to read
+    position: usize,
+}
+
+impl<R: Read + Seek> BgzfReader<R> {
+    pub fn new(reader: R, index: GziIndex) -> Self {
+        BgzfReader {
+            reader,
+            index,
+            block: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Decompresses the next block with data. `false` at the end of the stream.
+    fn next_block(&mut self) -> io::Result<bool> {
+        self.position = 0;
+        self.block.clear();
+        while self.block.is_empty() {
+            let Some(block) = read_bgzf_block(&mut self.reader)? else {
+                return Ok(false);
+            };
+            GzDecoder::new(block.as_slice()).read_to_end(&mut self.block)?;
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> Read for BgzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.block.len() && !self.next_block()? {
+            return Ok(0);
+        }
+        let read: usize = buf.len().min(self.block.len() - self.position);
+        buf[..read].copy_from_slice(&self.block[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> Seek for BgzfReader<R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let SeekFrom::Start(offset) = position else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "BGZF streams are only seeked from their start",
+            ));
+        };
+        let (compressed, uncompressed) = self.index.block_of(offset);
+        self.reader.seek(SeekFrom::Start(compressed))?;
+        self.next_block()?;
+        self.position = ((offset - uncompressed) as usize).min(self.block.len());
+        Ok(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Cursor, Read, Seek, SeekFrom, Write},
+        path::Path,
+    };
+
+    use crate::shared::infrastructure::compression::{
+        compress, decompress, BgzfReader, Compression, Finish, GziIndex,
+    };
+
+    fn round_trip(compression: Compression) {
+        let text: String = "> header\natcg\n".repeat(10_000);
+        let mut compressed: Vec<u8> = Vec::new();
+        let mut writer = compress(&mut compressed, compression).unwrap();
+        writer.write_all(text.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        assert_eq!(
+            Compression::from_magic(&compressed) == Compression::None,
+            compression == Compression::None
+        );
+        let mut decompressed: String = String::new();
+        decompress(compressed.as_slice())
+            .unwrap()
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, text);
+    }
+
+    #[test]
+    fn round_trip_all_formats() {
+        round_trip(Compression::None);
+        round_trip(Compression::Gzip);
+        round_trip(Compression::Bgzf);
+        round_trip(Compression::Zstd);
+    }
+
+    /// A destination with room for a few bytes only
+    struct Full {
+        room: usize,
+    }
+
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.room {
+                return Err(io::Error::other("no space left on device"));
+            }
+            self.room -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn finish_reports_errors() {
+        for compression in [Compression::Gzip, Compression::Bgzf, Compression::Zstd] {
+            let mut writer = compress(Full { room: 10 }, compression).unwrap();
+            writer.write_all(b"> header\natcg\n").unwrap();
+            assert!(writer.finish().is_err());
+        }
+    }
+
+    #[test]
+    fn bgzf_ends_with_eof_block() {
+        let mut compressed: Vec<u8> = Vec::new();
+        let mut writer = compress(&mut compressed, Compression::Bgzf).unwrap();
+        writer.write_all(b"> header\natcg\n").unwrap();
+        writer.finish().unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        assert!(compressed.ends_with(&super::BGZF_EOF));
+        assert!(!compressed[..compressed.len() - 28].ends_with(&super::BGZF_EOF));
+        assert_eq!(&compressed[12..14], b"BC");
+    }
+
+    #[test]
+    fn seek_in_bgzf() {
+        assert_eq!(
+            Compression::from_extension(Path::new("genome.fa.gz")),
+            Compression::Bgzf
+        );
+        let text: String = (0..30_000).map(|i| format!("{:05}\n", i)).collect();
+        let mut compressed: Vec<u8> = Vec::new();
+        let mut writer = compress(&mut compressed, Compression::Bgzf).unwrap();
+        writer.write_all(text.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        assert_eq!(Compression::from_magic(&compressed), Compression::Bgzf);
+
+        let index: GziIndex = GziIndex::build(compressed.as_slice()).unwrap();
+        assert_eq!(index.blocks.len(), 3);
+        let mut written: Vec<u8> = Vec::new();
+        index.write_to(&mut written).unwrap();
+        assert_eq!(written.len(), 8 + 2 * 16);
+        assert_eq!(GziIndex::read_from(written.as_slice()).unwrap(), index);
+
+        let mut reader = BgzfReader::new(Cursor::new(&compressed), index);
+        for offset in [0, 12, 0xff00 - 3, 100_000, 179_994] {
+            reader.seek(SeekFrom::Start(offset as u64)).unwrap();
+            let mut bytes: [u8; 6] = [0; 6];
+            reader.read_exact(&mut bytes).unwrap();
+            assert_eq!(&bytes, &text.as_bytes()[offset..offset + 6]);
+        }
+        assert_eq!(reader.read(&mut [0; 6]).unwrap(), 0);
+    }
+}
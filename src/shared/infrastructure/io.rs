@@ -0,0 +1,74 @@
+//! Opening of input and output streams, be it files or the standard streams, compressed or not.
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+
+use crate::ctxs::fasta::domain::alphabet::{Alphabet, ValidationPolicy};
+
+use super::compression::{compress, decompress, CompressedWriter, Compression};
+
+/// A file or the standard output, compressed or not, as `open_output` opens them
+pub(crate) type Output = CompressedWriter<Box<dyn Write>>;
+
+/// Options on how every command reads and writes files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IoOptions {
+    /// Compression of written files. Guessed from the file extension if `None`
+    pub compression: Option<Compression>,
+    /// What to do with illegal characters in read sequences
+    pub policy: ValidationPolicy,
+    /// Alphabet of every read sequence. Detected for each one if `None`
+    pub alphabet: Option<Alphabet>,
+}
+
+/// Whether the given path stands for a standard stream: no path at all, or `-`.
+pub(crate) fn is_std(file: Option<&Path>) -> bool {
+    file.is_none_or(|file| file == Path::new("-"))
+}
+
+/// Name of the given path to use in messages, `std_name` if it is a standard stream.
+pub(crate) fn display_name(file: Option<&Path>, std_name: &str) -> String {
+    match file {
+        Some(file) if !is_std(Some(file)) => file.display().to_string(),
+        _ => std_name.to_string(),
+    }
+}
+
+/// Opens a file for buffered reading. Reads from the standard input if the path is `-` or missing.
+/// Compressed input is detected from its first bytes, and decompressed on the fly.
+pub(crate) fn open_input(file: Option<&Path>) -> Result<Box<dyn BufRead>> {
+    let reader: Box<dyn BufRead> = match file {
+        Some(file) if !is_std(Some(file)) => {
+            let opened: File =
+                File::open(file).with_context(|| format!("Could not open {}", file.display()))?;
+            Box::new(BufReader::new(opened))
+        }
+        _ => Box::new(BufReader::new(io::stdin().lock())),
+    };
+    Ok(decompress(reader)?)
+}
+
+/// Creates a file for buffered writing. Writes to the standard output if the path is `-` or
+/// missing. The output is compressed with `compression` if given, or else with the compression
+/// matching the file extension. It must be ended with `Finish::finish`.
+pub(crate) fn open_output(file: Option<&Path>, compression: Option<Compression>) -> Result<Output> {
+    let (writer, compression): (Box<dyn Write>, Compression) = match file {
+        Some(file) if !is_std(Some(file)) => {
+            let created: File = File::create(file)
+                .with_context(|| format!("Could not create {}", file.display()))?;
+            (
+                Box::new(BufWriter::new(created)),
+                compression.unwrap_or_else(|| Compression::from_extension(file)),
+            )
+        }
+        _ => (
+            Box::new(BufWriter::new(io::stdout().lock())),
+            compression.unwrap_or(Compression::None),
+        ),
+    };
+    Ok(compress(writer, compression)?)
+}
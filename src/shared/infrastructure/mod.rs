@@ -1,12 +1,21 @@
-use std::{fs::File, io::Write, path::PathBuf};
+pub mod compression;
+pub mod io;
+
+use std::{io::Write, path::Path};
 
 use anyhow::Result;
+use compression::{Compression, Finish};
+use textwrap::fill;
 
-use crate::ctxs::{fasta::domain::fasta::Fasta, protein::domain::protein::Protein};
+use crate::ctxs::{
+    fasta::domain::{fasta::Fasta, fastq::Fastq, record::SequenceRecord},
+    protein::domain::protein::Protein,
+};
 
 pub struct CommonWriteFormat {
     header: Option<String>,
     chain: String,
+    quality: Option<String>,
 }
 
 impl From<Fasta> for CommonWriteFormat {
@@ -14,26 +23,126 @@ impl From<Fasta> for CommonWriteFormat {
         CommonWriteFormat {
             header: Some(value.header.to_string()),
             chain: value.sequence.to_string(),
+            quality: None,
+        }
+    }
+}
+
+impl From<Fastq> for CommonWriteFormat {
+    fn from(value: Fastq) -> Self {
+        CommonWriteFormat {
+            header: Some(format!("@{}\n", value.header.as_str())),
+            chain: format!("{}\n", value.sequence.as_str()),
+            quality: Some(format!("+\n{}\n", value.quality)),
+        }
+    }
+}
+
+impl From<SequenceRecord> for CommonWriteFormat {
+    fn from(value: SequenceRecord) -> Self {
+        match value {
+            SequenceRecord::Fasta(fasta) => CommonWriteFormat::from(fasta),
+            SequenceRecord::Fastq(fastq) => CommonWriteFormat::from(fastq),
+        }
+    }
+}
+
+/// How to lay proteins out: line width and case of the aminoacids
+#[derive(Debug, Clone, Copy)]
+pub struct ProteinLayout {
+    /// Aminoacids per line. A single line if `0`
+    pub width: usize,
+    /// Whether to write aminoacids in lowercase, instead of uppercase
+    pub lowercase: bool,
+}
+
+impl Default for ProteinLayout {
+    fn default() -> Self {
+        ProteinLayout {
+            width: 60,
+            lowercase: false,
         }
     }
 }
 
 impl From<Protein> for CommonWriteFormat {
     fn from(value: Protein) -> Self {
+        CommonWriteFormat::from((value, ProteinLayout::default()))
+    }
+}
+
+impl From<(Protein, ProteinLayout)> for CommonWriteFormat {
+    fn from((value, layout): (Protein, ProteinLayout)) -> Self {
+        let chain: String = match layout.lowercase {
+            true => value.to_string().to_lowercase(),
+            false => value.to_string().to_uppercase(),
+        };
+        let chain: String = match layout.width {
+            0 => chain,
+            width => fill(&chain, width),
+        };
         CommonWriteFormat {
-            header: None,
-            chain: value.to_string(),
+            header: value
+                .header
+                .as_ref()
+                .map(|header| format!("> {}\n", header)),
+            chain: format!("{}\n", chain),
+            quality: None,
         }
     }
 }
 
 impl CommonWriteFormat {
-    pub(crate) fn save(&self, file_name: &PathBuf) -> Result<()> {
-        let mut output_file: File = File::create(file_name)?;
-        if self.header.is_some() {
-            output_file.write_all(self.header.as_ref().unwrap().as_bytes())?;
+    /// Saves to a file, or to the standard output if `file_name` is `-` or missing. The output is
+    /// compressed with `compression`, or as the file extension tells if `None`.
+    pub(crate) fn save(
+        &self,
+        file_name: Option<&Path>,
+        compression: Option<Compression>,
+    ) -> Result<()> {
+        let mut output_file: io::Output = io::open_output(file_name, compression)?;
+        self.write_to(&mut output_file)?;
+        output_file.finish()?;
+        Ok(())
+    }
+
+    pub(crate) fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if let Some(header) = &self.header {
+            writer.write_all(header.as_bytes())?;
+        }
+        writer.write_all(self.chain.as_bytes())?;
+        if let Some(quality) = &self.quality {
+            writer.write_all(quality.as_bytes())?;
         }
-        output_file.write_all(self.chain.as_bytes())?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ctxs::{fasta::domain::fasta::Fasta, protein::domain::protein::Protein},
+        shared::infrastructure::{CommonWriteFormat, ProteinLayout},
+    };
+
+    fn written(record: CommonWriteFormat) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        record.write_to(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn write_proteins_as_fasta() {
+        let protein: Protein = Protein::from(Fasta::from(("seq1", "auggcuaaauga")));
+        assert_eq!(written(CommonWriteFormat::from(protein)), "> seq1\nMAK*\n");
+        let protein: Protein = Protein::from(Fasta::from(("seq1", "auggcuaaauga")));
+        let layout: ProteinLayout = ProteinLayout {
+            width: 3,
+            lowercase: true,
+        };
+        assert_eq!(
+            written(CommonWriteFormat::from((protein, layout))),
+            "> seq1\nmak\n*\n"
+        );
+    }
+}
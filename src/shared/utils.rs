@@ -4,7 +4,8 @@ use rand::seq::SliceRandom;
 use crate::ctxs::aminoacid::domain::aminoacid::Aminoacid;
 
 lazy_static! {
-    /// Creates a vector holding all possible aminoacids.
+    /// Creates a vector holding all possible aminoacids, with their codons in the standard genetic
+    /// code (NCBI table 1).
     pub(crate) static ref AMINOACID_TABLE: Vec<Aminoacid> = vec![
         Aminoacid::from(('-', Vec::new())),
         Aminoacid::from((
@@ -44,8 +45,9 @@ lazy_static! {
             ],
         )),
         Aminoacid::from(('m', vec![['a', 'u', 'g']])),
+        Aminoacid::from(('n', vec![['a', 'a', 'u'], ['a', 'a', 'c']])),
         Aminoacid::from((
-            'n',
+            'p',
             vec![
                 ['c', 'c', 'u'],
                 ['c', 'c', 'c'],
@@ -53,7 +55,7 @@ lazy_static! {
                 ['c', 'c', 'g'],
             ],
         )),
-        Aminoacid::from(('p', vec![['c', 'a', 'a'], ['c', 'a', 'g']])),
+        Aminoacid::from(('q', vec![['c', 'a', 'a'], ['c', 'a', 'g']])),
         Aminoacid::from((
             'r',
             vec![
@@ -76,6 +78,15 @@ lazy_static! {
                 ['a', 'g', 'c'],
             ],
         )),
+        Aminoacid::from((
+            't',
+            vec![
+                ['a', 'c', 'u'],
+                ['a', 'c', 'c'],
+                ['a', 'c', 'a'],
+                ['a', 'c', 'g'],
+            ],
+        )),
         Aminoacid::from((
             'v',
             vec![
@@ -92,6 +103,6 @@ lazy_static! {
 }
 
 /// Select a random `String` from a given `Vector`.
-pub(crate) fn select_rnd_str(string_list: &Vec<String>) -> String {
+pub(crate) fn select_rnd_str(string_list: &[String]) -> String {
     String::from(string_list.choose(&mut rand::thread_rng()).unwrap())
 }